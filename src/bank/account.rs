@@ -1,63 +1,324 @@
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Serialize, Deserialize};
-use super::{Transaction, TransactionType};
+use super::card::CreditCard;
+use super::certificate::Certificate;
+use super::hold::Hold;
+use super::interest::InterestAccrual;
+use super::loan::Loan;
+use super::party::{AccountParty, PartyRole, SigningRule};
+use super::statement::AccountStatement;
+use super::{AccountId, AccountNumber, AccountProduct, AccountStatus, BankError, Counterparty, Currency, CustomerId, Money, Transaction, TransactionType};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Account {
-    pub id: u64,
-    pub customer_id: u64,
+    pub id: AccountId,
+    /// The number customers and tellers use. Empty for accounts never
+    /// opened through a `Bank`.
+    #[serde(default)]
+    pub number: AccountNumber,
+    /// The primary holder.
+    pub customer_id: CustomerId,
     pub name: String,
-    pub account_type: String,
-    pub balance: f64,
+    #[serde(alias = "account_type", deserialize_with = "super::product::deserialize_stored")]
+    pub product: AccountProduct,
+    pub balance: Money,
     pub history: Vec<Transaction>,
+    /// When a `Bank` opened the account; unknown for older data.
+    #[serde(default)]
+    pub opened_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub interest: InterestAccrual,
+    /// How far below zero the balance may go, if the product allows it at
+    /// all. `None` means no arranged overdraft.
+    #[serde(default)]
+    pub overdraft_limit: Option<Money>,
+    /// A savings account of the same customer that covers shortfalls before
+    /// the overdraft is used.
+    #[serde(default)]
+    pub overdraft_protection: Option<AccountId>,
+    /// The last month end fees have been assessed for.
+    #[serde(default)]
+    pub fees_assessed_through: Option<NaiveDate>,
+    #[serde(default)]
+    pub status: AccountStatus,
+    /// When the account was last closed, kept after a reopen.
+    #[serde(default)]
+    pub closed_at: Option<DateTime<Utc>>,
+    /// The final statement issued when the account was last closed.
+    #[serde(default)]
+    pub closing_statement: Option<AccountStatement>,
+    /// Holds not yet released, captured or expired, oldest first.
+    #[serde(default)]
+    pub holds: Vec<Hold>,
+    /// The amortization state of a disbursed loan account.
+    #[serde(default)]
+    pub loan: Option<Loan>,
+    /// Credit card terms and statements, for card accounts.
+    #[serde(default)]
+    pub card: Option<CreditCard>,
+    /// Term, rate and maturity, for certificates of deposit.
+    #[serde(default)]
+    pub certificate: Option<Certificate>,
+    /// Everyone with a role on the account besides the primary holder, in
+    /// the order they were added.
+    #[serde(default)]
+    pub parties: Vec<AccountParty>,
+    #[serde(default)]
+    pub signing_rule: SigningRule,
 }
 
 impl Account {
-    pub fn new(id: u64, customer_id: u64, name: String, account_type: String) -> Self {
+    pub fn new(id: AccountId, customer_id: CustomerId, name: String, product: AccountProduct) -> Self {
+        Self::with_currency(id, customer_id, name, product, Currency::default())
+    }
+
+    pub fn with_currency(id: AccountId, customer_id: CustomerId, name: String, product: AccountProduct, currency: Currency) -> Self {
         Self {
             id,
+            number: AccountNumber::default(),
             customer_id,
             name,
-            account_type,
-            balance: 0.0,
+            product,
+            balance: Money::zero(currency),
             history: vec![],
+            opened_at: None,
+            interest: InterestAccrual::default(),
+            overdraft_limit: None,
+            overdraft_protection: None,
+            fees_assessed_through: None,
+            status: AccountStatus::Active,
+            closed_at: None,
+            closing_statement: None,
+            holds: vec![],
+            loan: None,
+            card: None,
+            certificate: None,
+            parties: vec![],
+            signing_rule: SigningRule::AnyOne,
         }
     }
 
-    pub fn deposit(&mut self, amount: f64) {
-        self.balance += amount;
-        self.history.push(Transaction::new(
-            TransactionType::Deposit,
-            amount,
-        ));
+    pub fn with_number(mut self, number: AccountNumber) -> Self {
+        self.number = number;
+        self
     }
 
-    pub fn withdraw(&mut self, amount: f64) -> bool {
-        if self.balance >= amount {
-            self.balance -= amount;
-            self.history.push(Transaction::new(
-                TransactionType::Withdrawal,
-                amount,
-            ));
-            true
-        } else {
-            false
-        }
+    pub fn with_opened_at(mut self, opened_at: DateTime<Utc>) -> Self {
+        self.opened_at = Some(opened_at);
+        self
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.balance.currency()
+    }
+
+    /// The balance from posted transactions alone.
+    pub fn ledger_balance(&self) -> Money {
+        self.balance
     }
 
-    pub fn transfer(&mut self, amount: f64, to: &mut Account) -> bool {
-        if self.withdraw(amount) {
-            to.deposit(amount);
-            self.history.push(Transaction::new(
-                TransactionType::Transfer { to_account: to.id },
-                amount,
-            ));
-            true
-        } else {
-            false
+    /// The total of the account's open holds.
+    pub fn held_amount(&self) -> Result<Money, BankError> {
+        self.holds
+            .iter()
+            .try_fold(Money::zero(self.currency()), |total, hold| Ok(total.checked_add(hold.amount)?))
+    }
+
+    /// What can be debited right now: the ledger balance less open holds,
+    /// plus any arranged overdraft the product allows.
+    pub fn available_balance(&self) -> Result<Money, BankError> {
+        let unheld = self.balance.checked_sub(self.held_amount()?)?;
+        match self.overdraft_limit {
+            Some(limit) if self.product.rules().overdraft_allowed => Ok(unheld.checked_add(limit)?),
+            _ => Ok(unheld),
         }
     }
 
     pub fn get_history(&self) -> &[Transaction] {
         &self.history
     }
+
+    /// The balance at the end of `date`: today's balance with every later
+    /// transaction undone.
+    pub fn balance_at_end_of(&self, date: NaiveDate) -> Result<Money, BankError> {
+        self.balance_without(|tx| tx.timestamp.date_naive() > date)
+    }
+
+    /// The balance as it was booked at the end of `date`, leaving out
+    /// anything entered later even if it counts from an earlier day.
+    pub fn booked_balance_at_end_of(&self, date: NaiveDate) -> Result<Money, BankError> {
+        self.balance_without(|tx| tx.booked_on() > date)
+    }
+
+    /// The current balance with the transactions matching `later` undone.
+    fn balance_without(&self, later: impl Fn(&Transaction) -> bool) -> Result<Money, BankError> {
+        let mut balance = self.balance;
+        for tx in self.history.iter().rev().filter(|tx| later(tx)) {
+            balance = if tx.tx_type.is_credit() {
+                balance.checked_sub(tx.amount)?
+            } else {
+                balance.checked_add(tx.amount)?
+            };
+        }
+        Ok(balance)
+    }
+
+    /// Appends a transaction that has already been posted to the ledger,
+    /// along with the account's resulting balance. Only `Bank::post` calls
+    /// this, once it has given the transaction its ID.
+    pub(crate) fn record(&mut self, mut tx: Transaction, balance: Money) {
+        self.balance = balance;
+        tx.balance_after = Some(balance);
+        self.history.push(tx);
+    }
+
+    /// This account as seen from the other side of a transaction.
+    pub(crate) fn counterparty(&self, holder: Option<String>) -> Counterparty {
+        Counterparty { account_id: self.id, account_number: self.number.clone(), holder }
+    }
+
+    /// Every party to the account, the primary holder first.
+    pub fn all_parties(&self) -> Vec<AccountParty> {
+        let primary = AccountParty { customer_id: self.customer_id, role: PartyRole::PrimaryHolder };
+        std::iter::once(primary).chain(self.parties.iter().copied()).collect()
+    }
+
+    /// The roles `customer_id` has on the account, if any.
+    pub fn roles_of(&self, customer_id: CustomerId) -> Vec<PartyRole> {
+        self.all_parties().into_iter().filter(|p| p.customer_id == customer_id).map(|p| p.role).collect()
+    }
+
+    /// Whether `customer_id` owns the account, as its primary or a joint
+    /// holder.
+    pub fn is_held_by(&self, customer_id: CustomerId) -> bool {
+        self.roles_of(customer_id).iter().any(PartyRole::is_holder)
+    }
+
+    /// Checks that `signers` may take money out under the signing rule.
+    /// Every signer needs a role that can sign; with no signers named, the
+    /// primary holder is taken to be acting, which only "any one" allows.
+    pub fn check_signatures(&self, signers: &[CustomerId]) -> Result<(), BankError> {
+        for &customer_id in signers {
+            if !self.roles_of(customer_id).iter().any(PartyRole::can_sign) {
+                return Err(BankError::NotAuthorized { account_id: self.id, customer_id });
+            }
+        }
+        if self.signing_rule == SigningRule::AllMustSign {
+            let missing: Vec<CustomerId> = self.all_parties()
+                .iter()
+                .filter(|p| p.role.is_holder() && !signers.contains(&p.customer_id))
+                .map(|p| p.customer_id)
+                .collect();
+            if !missing.is_empty() {
+                return Err(BankError::SignaturesMissing { account_id: self.id, missing });
+            }
+        }
+        Ok(())
+    }
+
+    /// The last deposit, withdrawal, transfer, card capture or loan
+    /// repayment, or the opening if there has been none. Interest, fees and
+    /// money the bank moves itself, such as a loan disbursement, don't count.
+    pub fn last_customer_activity(&self) -> Option<DateTime<Utc>> {
+        self.history
+            .iter()
+            .rev()
+            .find(|tx| !tx.internal && matches!(
+                tx.tx_type,
+                TransactionType::Deposit
+                    | TransactionType::Withdrawal
+                    | TransactionType::Transfer { .. }
+                    | TransactionType::TransferIn { .. }
+                    | TransactionType::HoldCapture { .. }
+                    | TransactionType::LoanRepayment { .. }
+                    | TransactionType::PrincipalRepaid { .. }
+            ))
+            .map(|tx| tx.timestamp)
+            .or(self.opened_at)
+    }
+
+    /// Checks that `amount` could be credited to this account given its
+    /// status and any certificate lock. A locked certificate takes money
+    /// only when it is opened and when it matures.
+    pub(crate) fn check_credit(&self, amount: Money) -> Result<(), BankError> {
+        if let Some(certificate) = self.certificate.as_ref().filter(|c| c.is_locked()) {
+            return Err(BankError::DepositLocked { account_id: self.id, until: certificate.maturity });
+        }
+        self.check_credit_to_certificate(amount)
+    }
+
+    /// Checks that `amount` could be credited to this account given its
+    /// status alone, for the credits a certificate takes while locked.
+    pub(crate) fn check_credit_to_certificate(&self, amount: Money) -> Result<(), BankError> {
+        if !self.status.allows_credits() {
+            return Err(BankError::AccountStatus { account_id: self.id, status: self.status });
+        }
+        if !amount.is_positive() {
+            return Err(BankError::InvalidAmount(amount));
+        }
+        self.balance.checked_add(amount)?;
+        Ok(())
+    }
+
+    /// Checks that `amount` could be debited from this account at `at`,
+    /// given its status, any certificate lock, its product's monthly
+    /// withdrawal limit and its available balance. A matured certificate
+    /// has no withdrawal limit.
+    pub(crate) fn check_debit(&self, amount: Money, at: DateTime<Utc>) -> Result<(), BankError> {
+        if !self.status.allows_debits() {
+            return Err(BankError::AccountStatus { account_id: self.id, status: self.status });
+        }
+        if !amount.is_positive() {
+            return Err(BankError::InvalidAmount(amount));
+        }
+        if let Some(certificate) = self.certificate.as_ref().filter(|c| c.is_locked()) {
+            return Err(BankError::DepositLocked { account_id: self.id, until: certificate.maturity });
+        }
+        if let Some(limit) = self.product.rules().monthly_withdrawal_limit.filter(|_| self.certificate.is_none()) {
+            if self.debits_in_month(at.date_naive()) >= limit as usize {
+                return Err(BankError::WithdrawalLimitExceeded { account_id: self.id, limit });
+            }
+        }
+        let available = self.available_balance()?;
+        if available.checked_cmp(&amount)?.is_lt() {
+            return Err(BankError::InsufficientFunds {
+                account_id: self.id,
+                requested: amount,
+                available,
+            });
+        }
+        Ok(())
+    }
+
+    /// Withdrawals and outgoing transfers in the calendar month of `date`.
+    /// Debits since reversed don't count.
+    pub(crate) fn debits_in_month(&self, date: NaiveDate) -> usize {
+        self.debits_in(date).count()
+    }
+
+    /// Like [`Account::debits_in_month`], leaving out
+    /// [internal](Transaction::internal) transfers.
+    pub(crate) fn customer_debits_in_month(&self, date: NaiveDate) -> usize {
+        self.debits_in(date).filter(|tx| !tx.internal).count()
+    }
+
+    fn debits_in(&self, date: NaiveDate) -> impl Iterator<Item = &Transaction> {
+        self.history
+            .iter()
+            .filter(|tx| matches!(tx.tx_type, TransactionType::Withdrawal | TransactionType::Transfer { .. }))
+            .filter(|tx| tx.reversed_by.is_none())
+            .filter(move |tx| tx.timestamp.year() == date.year() && tx.timestamp.month() == date.month())
+    }
+}
+
+/// Builds the outgoing and incoming transactions for a transfer, stamped
+/// with the same time.
+pub(crate) fn transfer_legs(from_id: AccountId, to_id: AccountId, amount: Money, memo: Option<String>, timestamp: DateTime<Utc>) -> (Transaction, Transaction) {
+    let debit = Transaction::new(TransactionType::Transfer { to_account: to_id }, amount)
+        .with_timestamp(timestamp)
+        .with_memo(memo.clone());
+    let credit = Transaction::new(TransactionType::TransferIn { from_account: from_id }, amount)
+        .with_timestamp(timestamp)
+        .with_memo(memo);
+    (debit, credit)
 }
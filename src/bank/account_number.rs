@@ -0,0 +1,182 @@
+use std::fmt;
+use serde::{Serialize, Deserialize, Deserializer};
+use super::{AccountId, BankError};
+
+/// The customer-facing number of an [`Account`](super::Account), as opposed
+/// to its internal [`AccountId`]. Held as digits only; empty means the
+/// account was never given one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AccountNumber(String);
+
+impl AccountNumber {
+    /// Drops the spaces and hyphens people type to group digits. Does not
+    /// check anything else.
+    pub(crate) fn strip_separators(input: &str) -> Self {
+        Self(input.chars().filter(|c| !matches!(c, ' ' | '-')).collect())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for AccountNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// How the trailing check digits are computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CheckDigit {
+    /// One Luhn (mod 10) digit. Catches every single-digit typo and most
+    /// swaps of adjacent digits.
+    #[default]
+    Luhn,
+    /// Two ISO 7064 MOD 97-10 digits, as used in IBANs. Also catches every
+    /// adjacent swap.
+    Mod97,
+}
+
+impl CheckDigit {
+    fn width(&self) -> usize {
+        match self {
+            CheckDigit::Luhn => 1,
+            CheckDigit::Mod97 => 2,
+        }
+    }
+
+    fn compute(&self, digits: &str) -> String {
+        match self {
+            CheckDigit::Luhn => {
+                // Double every second digit from the right, counting the
+                // check digit that is about to be appended.
+                let sum: u32 = digits
+                    .bytes()
+                    .rev()
+                    .enumerate()
+                    .map(|(i, b)| {
+                        let d = u32::from(b - b'0');
+                        if i % 2 == 0 {
+                            let doubled = d * 2;
+                            if doubled > 9 { doubled - 9 } else { doubled }
+                        } else {
+                            d
+                        }
+                    })
+                    .sum();
+                ((10 - sum % 10) % 10).to_string()
+            }
+            CheckDigit::Mod97 => format!("{:02}", 98 - mod97(digits, 2)),
+        }
+    }
+}
+
+/// `digits` followed by `zeros` zeros, modulo 97.
+fn mod97(digits: &str, zeros: usize) -> u32 {
+    digits
+        .bytes()
+        .chain(std::iter::repeat_n(b'0', zeros))
+        .fold(0, |rem, b| (rem * 10 + u32::from(b - b'0')) % 97)
+}
+
+/// Builds account numbers as a branch prefix, the account ID zero-padded to
+/// a fixed width, and check digits: account 42 is `100` `0000042` `1` by default.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AccountNumberFormat {
+    branch: String,
+    sequence_digits: usize,
+    check: CheckDigit,
+}
+
+impl AccountNumberFormat {
+    pub fn new(branch: &str, sequence_digits: usize, check: CheckDigit) -> Result<Self, BankError> {
+        if branch.is_empty() || !branch.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(BankError::InvalidField { field: "branch", reason: "must be one or more digits".to_string() });
+        }
+        if !(1..=18).contains(&sequence_digits) {
+            return Err(BankError::InvalidField {
+                field: "sequence digits",
+                reason: "must be between 1 and 18".to_string(),
+            });
+        }
+        Ok(Self { branch: branch.to_string(), sequence_digits, check })
+    }
+
+    pub fn branch(&self) -> &str {
+        &self.branch
+    }
+
+    pub fn check(&self) -> CheckDigit {
+        self.check
+    }
+
+    /// Total number of digits in an account number.
+    pub fn number_len(&self) -> usize {
+        self.branch.len() + self.sequence_digits + self.check.width()
+    }
+
+    /// The account number for the account with this ID.
+    pub fn number_for(&self, id: AccountId) -> Result<AccountNumber, BankError> {
+        let sequence = format!("{:0width$}", id.0, width = self.sequence_digits);
+        if sequence.len() > self.sequence_digits {
+            return Err(BankError::InvalidField {
+                field: "account number",
+                reason: format!("account {} does not fit in {} digits", id, self.sequence_digits),
+            });
+        }
+        let body = format!("{}{}", self.branch, sequence);
+        let check = self.check.compute(&body);
+        Ok(AccountNumber(body + &check))
+    }
+
+    /// Reads an account number as typed, ignoring spaces and hyphens, and
+    /// rejects it unless it has this format's branch, length and a matching
+    /// check digit.
+    pub fn parse(&self, input: &str) -> Result<AccountNumber, BankError> {
+        let invalid = |reason: &str| BankError::InvalidField { field: "account number", reason: reason.to_string() };
+        let AccountNumber(digits) = AccountNumber::strip_separators(input);
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid("must contain only digits"));
+        }
+        if digits.len() != self.number_len() {
+            return Err(invalid(&format!("must be {} digits", self.number_len())));
+        }
+        if !digits.starts_with(&self.branch) {
+            return Err(invalid(&format!("must start with branch {}", self.branch)));
+        }
+        let (body, check) = digits.split_at(digits.len() - self.check.width());
+        if self.check.compute(body) != check {
+            return Err(invalid("check digit does not match; please re-check the number"));
+        }
+        Ok(AccountNumber(digits))
+    }
+}
+
+impl<'de> Deserialize<'de> for AccountNumberFormat {
+    /// Goes through [`AccountNumberFormat::new`], so a hand-edited file
+    /// can't bring in a format that would issue malformed numbers.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Fields {
+            branch: String,
+            sequence_digits: usize,
+            check: CheckDigit,
+        }
+
+        let fields = Fields::deserialize(deserializer)?;
+        Self::new(&fields.branch, fields.sequence_digits, fields.check).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Default for AccountNumberFormat {
+    /// Branch `100`, seven sequence digits and a Luhn check digit.
+    fn default() -> Self {
+        Self { branch: "100".to_string(), sequence_digits: 7, check: CheckDigit::Luhn }
+    }
+}
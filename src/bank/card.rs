@@ -0,0 +1,216 @@
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Serialize, Deserialize};
+use super::interest::Rate;
+use super::{Account, BankError, Money, MoneyError, RoundingMode, Transaction, TransactionType};
+
+/// How much of a statement balance must be paid by the due date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MinimumPayment {
+    /// The share of the statement balance, in the same parts per million as
+    /// interest rates: 2% is 20,000.
+    pub percent: Rate,
+    /// The least that is ever asked for, unless the balance is smaller.
+    pub floor: Option<Money>,
+}
+
+/// The terms of a revolving credit card. The credit limit itself is the
+/// card account's overdraft limit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CardTerms {
+    /// The annual rate charged on purchases once the grace period is lost.
+    pub apr: Rate,
+    /// The day of the month each statement cycle closes on.
+    pub cycle_day: u32,
+    /// Days from a statement's closing date to its due date.
+    pub grace_days: u32,
+    pub minimum_payment: MinimumPayment,
+    /// Charged when less than the minimum payment arrives by the due date.
+    pub late_fee: Option<Money>,
+}
+
+impl CardTerms {
+    /// Terms with a 25-day grace period, a minimum payment of 1% of the
+    /// balance and no late fee.
+    pub fn new(apr: Rate, cycle_day: u32) -> Self {
+        Self {
+            apr,
+            cycle_day,
+            grace_days: 25,
+            minimum_payment: MinimumPayment { percent: Rate::from_bps(100), floor: None },
+            late_fee: None,
+        }
+    }
+
+    pub fn with_grace_days(mut self, grace_days: u32) -> Self {
+        self.grace_days = grace_days;
+        self
+    }
+
+    pub fn with_minimum_payment(mut self, percent: Rate, floor: Option<Money>) -> Self {
+        self.minimum_payment = MinimumPayment { percent, floor };
+        self
+    }
+
+    pub fn with_late_fee(mut self, fee: Money) -> Self {
+        self.late_fee = Some(fee);
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), BankError> {
+        let invalid = |field: &'static str, reason: &str| BankError::InvalidField { field, reason: reason.to_string() };
+        if self.apr.ppm() < 0 {
+            return Err(invalid("card APR", "must not be negative"));
+        }
+        // Every month has a 28th, and a due date before the next closing
+        // date keeps the cycles from overlapping.
+        if !(1..=28).contains(&self.cycle_day) {
+            return Err(invalid("statement cycle day", "must be between 1 and 28"));
+        }
+        if !(1..=27).contains(&self.grace_days) {
+            return Err(invalid("grace period", "must be between 1 and 27 days"));
+        }
+        let percent = self.minimum_payment.percent.ppm();
+        if percent <= 0 || percent > 1_000_000 {
+            return Err(invalid("minimum payment", "must be more than 0% and at most 100% of the balance"));
+        }
+        for amount in [self.minimum_payment.floor, self.late_fee].into_iter().flatten() {
+            if !amount.is_positive() {
+                return Err(BankError::InvalidAmount(amount));
+            }
+        }
+        Ok(())
+    }
+
+    /// The minimum payment on a statement balance of `owed`.
+    pub fn minimum_payment_on(&self, owed: Money) -> Result<Money, BankError> {
+        if !owed.is_positive() {
+            return Ok(Money::zero(owed.currency()));
+        }
+        let mut minimum = owed.mul_ratio(self.minimum_payment.percent.ppm(), 1_000_000, RoundingMode::Up)?;
+        if let Some(floor) = self.minimum_payment.floor {
+            if minimum.checked_cmp(&floor)?.is_lt() {
+                minimum = floor;
+            }
+        }
+        if minimum.checked_cmp(&owed)?.is_gt() {
+            minimum = owed;
+        }
+        Ok(minimum)
+    }
+
+    /// Interest at the APR on what `account` owed at the end of each day
+    /// from `start` to `end`, over a 365-day year.
+    pub(crate) fn interest_between(&self, account: &Account, start: NaiveDate, end: NaiveDate) -> Result<Money, BankError> {
+        let mut owed_days: i128 = 0;
+        for day in start.iter_days().take_while(|day| *day <= end) {
+            let balance = account.balance_at_end_of(day)?;
+            if balance.is_negative() {
+                owed_days += i128::from(balance.minor_units().unsigned_abs());
+            }
+        }
+        let minor = RoundingMode::HalfEven.divide(owed_days * i128::from(self.apr.ppm()), 1_000_000 * 365);
+        let minor = i64::try_from(minor).map_err(|_| MoneyError::Overflow)?;
+        Ok(Money::from_minor(minor, account.currency()))
+    }
+}
+
+/// A closed statement cycle. Balances are what the customer owes, so a
+/// card in credit has a negative statement balance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Statement {
+    /// Counts from 1 for each card.
+    pub number: u32,
+    pub period_start: NaiveDate,
+    pub closing_date: NaiveDate,
+    pub due_date: NaiveDate,
+    pub previous_balance: Money,
+    pub statement_balance: Money,
+    pub minimum_payment: Money,
+    /// Everything posted during the cycle, as it stood at closing.
+    pub transactions: Vec<Transaction>,
+    /// Payments received after closing and by the due date, once the due
+    /// date has passed.
+    pub paid_by_due: Option<Money>,
+}
+
+impl Statement {
+    /// Whether the statement balance was paid off by the due date, or
+    /// `None` before the due date has been reached.
+    pub fn paid_in_full(&self) -> Option<bool> {
+        let paid = self.paid_by_due?;
+        Some(paid >= self.statement_balance || !self.statement_balance.is_positive())
+    }
+}
+
+/// The card side of a credit card account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreditCard {
+    pub terms: CardTerms,
+    pub opened_on: NaiveDate,
+    /// Every statement so far, oldest first.
+    pub statements: Vec<Statement>,
+}
+
+impl CreditCard {
+    pub(crate) fn new(terms: CardTerms, opened_on: NaiveDate) -> Self {
+        Self { terms, opened_on, statements: vec![] }
+    }
+
+    pub fn last_statement(&self) -> Option<&Statement> {
+        self.statements.last()
+    }
+
+    /// The closing date of the cycle in progress: the first cycle day after
+    /// the last closing date, or after the card was opened.
+    pub fn next_closing_date(&self) -> NaiveDate {
+        let after = self.last_statement().map_or(self.opened_on, |s| s.closing_date);
+        let this_month = after.with_day(self.terms.cycle_day).expect("cycle day is at most 28");
+        if this_month > after {
+            this_month
+        } else {
+            this_month.checked_add_months(Months::new(1)).expect("within the supported date range")
+        }
+    }
+
+    /// Snapshots the cycle closing on `closing_date` from `account`'s
+    /// history.
+    pub(crate) fn statement_for(&self, account: &Account, closing_date: NaiveDate) -> Result<Statement, BankError> {
+        let previous = self.last_statement();
+        let period_start = previous.map_or(self.opened_on, |s| s.closing_date.succ_opt().expect("before the next closing"));
+        let previous_balance = match previous {
+            Some(statement) => statement.statement_balance,
+            None => account.balance_at_end_of(period_start.pred_opt().expect("after the first date"))?.checked_neg()?,
+        };
+        let transactions: Vec<Transaction> = account.history
+            .iter()
+            .filter(|tx| (period_start..=closing_date).contains(&tx.timestamp.date_naive()))
+            .cloned()
+            .collect();
+        let statement_balance = account.balance_at_end_of(closing_date)?.checked_neg()?;
+        Ok(Statement {
+            number: previous.map_or(1, |s| s.number + 1),
+            period_start,
+            closing_date,
+            due_date: closing_date + Days::new(u64::from(self.terms.grace_days)),
+            previous_balance,
+            statement_balance,
+            minimum_payment: self.terms.minimum_payment_on(statement_balance)?,
+            transactions,
+            paid_by_due: None,
+        })
+    }
+}
+
+/// Payments into a card account booked after `closing_date` and on or
+/// before `due_date`. Payments since reversed don't count.
+pub(crate) fn payments_between(account: &Account, closing_date: NaiveDate, due_date: NaiveDate) -> Result<Money, BankError> {
+    account.history
+        .iter()
+        .filter(|tx| matches!(tx.tx_type, TransactionType::Deposit | TransactionType::TransferIn { .. }))
+        .filter(|tx| tx.reversed_by.is_none())
+        .filter(|tx| {
+            let on = tx.booked_on();
+            on > closing_date && on <= due_date
+        })
+        .try_fold(Money::zero(account.currency()), |total, tx| Ok(total.checked_add(tx.amount)?))
+}
@@ -0,0 +1,143 @@
+use chrono::{Months, NaiveDate};
+use serde::{Serialize, Deserialize};
+use super::interest::Rate;
+use super::{Account, AccountId, BankError, Money, MoneyError, RoundingMode};
+
+/// What happens to a certificate of deposit when it matures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MaturityAction {
+    /// Starts another term at the same rate, interest included.
+    AutoRenew,
+    /// Pays the balance to another account of the customer.
+    PayOut { to: AccountId },
+    /// Leaves the money free to withdraw for `days`, then renews whatever
+    /// is left.
+    GracePeriod { days: u32 },
+}
+
+/// The terms of a certificate of deposit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertificateTerms {
+    /// The fixed annual rate, paid at maturity.
+    pub rate: Rate,
+    pub term_months: u32,
+    pub maturity_action: MaturityAction,
+    /// Days of interest forfeited on whatever is withdrawn early.
+    pub penalty_days: u32,
+}
+
+impl CertificateTerms {
+    /// Terms that renew at maturity, with 90 days' interest as the early
+    /// withdrawal penalty.
+    pub fn new(rate: Rate, term_months: u32) -> Self {
+        Self { rate, term_months, maturity_action: MaturityAction::AutoRenew, penalty_days: 90 }
+    }
+
+    pub fn with_maturity_action(mut self, action: MaturityAction) -> Self {
+        self.maturity_action = action;
+        self
+    }
+
+    pub fn with_penalty_days(mut self, days: u32) -> Self {
+        self.penalty_days = days;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), BankError> {
+        let invalid = |field: &'static str, reason: &str| BankError::InvalidField { field, reason: reason.to_string() };
+        if self.rate.ppm() < 0 {
+            return Err(invalid("certificate rate", "must not be negative"));
+        }
+        if self.term_months == 0 {
+            return Err(invalid("certificate term", "must be at least one month"));
+        }
+        if self.maturity_action == (MaturityAction::GracePeriod { days: 0 }) {
+            return Err(invalid("grace period", "must be at least one day"));
+        }
+        Ok(())
+    }
+
+    /// Interest for `days` days on `amount`, over a 365-day year.
+    pub fn interest_for_days(&self, amount: Money, days: u32) -> Result<Money, BankError> {
+        let rate_days = self.rate.ppm().checked_mul(i64::from(days)).ok_or(MoneyError::Overflow)?;
+        Ok(amount.mul_ratio(rate_days, 1_000_000 * 365, RoundingMode::HalfEven)?)
+    }
+
+    /// The penalty for taking `amount` out before maturity, at most the
+    /// amount itself.
+    pub fn early_withdrawal_penalty(&self, amount: Money) -> Result<Money, BankError> {
+        let penalty = self.interest_for_days(amount, self.penalty_days)?;
+        Ok(if penalty.checked_cmp(&amount)?.is_gt() { amount } else { penalty })
+    }
+}
+
+/// Where a certificate is in its life.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CertificatePhase {
+    /// Nothing can be taken out before maturity except through the early
+    /// withdrawal penalty.
+    Locked,
+    /// Matured, with money free to withdraw until `until`, when whatever
+    /// is left starts a new term.
+    Grace { until: NaiveDate },
+    /// Matured for good, paid out or emptied.
+    Ended,
+}
+
+/// The term deposit side of a certificate of deposit account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Certificate {
+    pub terms: CertificateTerms,
+    /// The first day of the current term.
+    pub start: NaiveDate,
+    pub maturity: NaiveDate,
+    pub phase: CertificatePhase,
+    /// Completed terms, renewals included.
+    pub terms_completed: u32,
+}
+
+impl Certificate {
+    pub(crate) fn new(terms: CertificateTerms, start: NaiveDate) -> Result<Self, BankError> {
+        Ok(Self {
+            maturity: maturity_after(start, terms.term_months)?,
+            terms,
+            start,
+            phase: CertificatePhase::Locked,
+            terms_completed: 0,
+        })
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.phase == CertificatePhase::Locked
+    }
+
+    /// Starts a new term on `start`.
+    pub(crate) fn renew(&mut self, start: NaiveDate) -> Result<(), BankError> {
+        self.start = start;
+        self.maturity = maturity_after(start, self.terms.term_months)?;
+        self.phase = CertificatePhase::Locked;
+        Ok(())
+    }
+
+    /// Interest earned over the current term on `account`'s balance at the
+    /// end of each day from the start up to, but not including, maturity.
+    pub(crate) fn term_interest(&self, account: &Account) -> Result<Money, BankError> {
+        let mut balance_days: i128 = 0;
+        for day in self.start.iter_days().take_while(|day| *day < self.maturity) {
+            let balance = account.balance_at_end_of(day)?;
+            if balance.is_positive() {
+                balance_days += i128::from(balance.minor_units());
+            }
+        }
+        let minor = RoundingMode::HalfEven.divide(balance_days * i128::from(self.terms.rate.ppm()), 1_000_000 * 365);
+        let minor = i64::try_from(minor).map_err(|_| MoneyError::Overflow)?;
+        Ok(Money::from_minor(minor, account.currency()))
+    }
+}
+
+fn maturity_after(start: NaiveDate, term_months: u32) -> Result<NaiveDate, BankError> {
+    start.checked_add_months(Months::new(term_months)).ok_or_else(|| BankError::InvalidField {
+        field: "certificate term",
+        reason: "runs past the last supported date".to_string(),
+    })
+}
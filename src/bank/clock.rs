@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+
+/// Where a [`Bank`](super::Bank) gets the current time. Batch runs and
+/// tests pin it to a fixed instant; everything else reads the system clock.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Clock {
+    #[default]
+    System,
+    Fixed(DateTime<Utc>),
+}
+
+impl Clock {
+    pub fn now(&self) -> DateTime<Utc> {
+        match self {
+            Clock::System => Utc::now(),
+            Clock::Fixed(at) => *at,
+        }
+    }
+}
@@ -1,26 +1,36 @@
+use std::collections::BTreeSet;
 use serde::{Serialize, Deserialize};
+use super::fees::FeeReason;
+use super::{BankError, CustomerId};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Customer {
-    pub id: u64,
+    pub id: CustomerId,
     pub name: String,
     pub address: String,
     pub phone: String,
     pub email: String,
+    /// Fees this customer is never charged.
+    #[serde(default)]
+    pub fee_waivers: BTreeSet<FeeReason>,
 }
 
 impl Customer {
-    pub fn new(id: u64, name: String, address: String, phone: String, email: String) -> Self {
+    pub fn new(id: CustomerId, name: String, address: String, phone: String, email: String) -> Self {
         Self {
             id,
             name,
             address,
             phone,
             email,
+            fee_waivers: BTreeSet::new(),
         }
     }
 
-    pub fn update(&mut self, name: Option<String>, address: Option<String>, phone: Option<String>, email: Option<String>) {
+    /// Replaces the given fields. Nothing is changed if any value is invalid.
+    pub fn update(&mut self, name: Option<String>, address: Option<String>, phone: Option<String>, email: Option<String>) -> Result<(), BankError> {
+        validate_fields(name.as_deref(), address.as_deref(), phone.as_deref(), email.as_deref())?;
+
         if let Some(name) = name {
             self.name = name;
         }
@@ -33,5 +43,21 @@ impl Customer {
         if let Some(email) = email {
             self.email = email;
         }
+        Ok(())
+    }
+}
+
+pub(crate) fn validate_fields(name: Option<&str>, address: Option<&str>, phone: Option<&str>, email: Option<&str>) -> Result<(), BankError> {
+    for (field, value) in [("name", name), ("address", address), ("phone", phone), ("email", email)] {
+        if value.is_some_and(|v| v.trim().is_empty()) {
+            return Err(BankError::InvalidField { field, reason: "must not be empty".to_string() });
+        }
+    }
+    if phone.is_some_and(|p| !p.chars().all(|c| c.is_ascii_digit() || c == '-' || c == '+' || c == ' ')) {
+        return Err(BankError::InvalidField { field: "phone", reason: "may only contain digits, spaces, '+' and '-'".to_string() });
+    }
+    if email.is_some_and(|e| !e.contains('@')) {
+        return Err(BankError::InvalidField { field: "email", reason: "must contain '@'".to_string() });
     }
+    Ok(())
 }
@@ -0,0 +1,80 @@
+use std::fmt;
+use chrono::NaiveDate;
+use super::{AccountId, AccountStatus, CustomerId, Money, MoneyError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BankError {
+    CustomerNotFound(CustomerId),
+    AccountNotFound(AccountId),
+    /// Amounts must be strictly positive.
+    InvalidAmount(Money),
+    InsufficientFunds { account_id: AccountId, requested: Money, available: Money },
+    /// A transfer named the same account as source and destination.
+    SameAccount(AccountId),
+    /// The account's product allows no more withdrawals this month.
+    WithdrawalLimitExceeded { account_id: AccountId, limit: u32 },
+    /// The account's status doesn't allow the operation.
+    AccountStatus { account_id: AccountId, status: AccountStatus },
+    /// A certificate of deposit can't be drawn on before it matures.
+    DepositLocked { account_id: AccountId, until: NaiveDate },
+    /// The customer has no role on the account that lets them sign.
+    NotAuthorized { account_id: AccountId, customer_id: CustomerId },
+    /// The account's signing rule needs these holders to sign as well.
+    SignaturesMissing { account_id: AccountId, missing: Vec<CustomerId> },
+    InvalidField { field: &'static str, reason: String },
+    /// A journal entry whose debits and credits do not match.
+    UnbalancedEntry(String),
+    /// Reading or writing saved bank data failed.
+    Storage(String),
+    /// A document such as a statement couldn't be rendered.
+    Render(String),
+    Money(MoneyError),
+}
+
+impl fmt::Display for BankError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BankError::CustomerNotFound(id) => write!(f, "customer {} not found", id),
+            BankError::AccountNotFound(id) => write!(f, "account {} not found", id),
+            BankError::InvalidAmount(amount) => write!(f, "invalid amount {}: must be positive", amount),
+            BankError::InsufficientFunds { account_id, requested, available } => write!(
+                f,
+                "insufficient funds in account {}: requested {}, available {}",
+                account_id, requested, available
+            ),
+            BankError::SameAccount(id) => write!(f, "cannot transfer from account {} to itself", id),
+            BankError::WithdrawalLimitExceeded { account_id, limit } => write!(
+                f,
+                "account {} allows {} withdrawals per month and has used them all",
+                account_id, limit
+            ),
+            BankError::AccountStatus { account_id, status } => write!(
+                f,
+                "account {} is {}",
+                account_id,
+                status.name().to_lowercase()
+            ),
+            BankError::DepositLocked { account_id, until } => write!(f, "account {} is locked until {}", account_id, until),
+            BankError::NotAuthorized { account_id, customer_id } => {
+                write!(f, "customer {} may not sign for account {}", customer_id, account_id)
+            }
+            BankError::SignaturesMissing { account_id, missing } => {
+                let missing: Vec<String> = missing.iter().map(|id| id.to_string()).collect();
+                write!(f, "account {} also needs the signatures of customers {}", account_id, missing.join(", "))
+            }
+            BankError::InvalidField { field, reason } => write!(f, "invalid {}: {}", field, reason),
+            BankError::UnbalancedEntry(reason) => write!(f, "unbalanced journal entry: {}", reason),
+            BankError::Storage(reason) => write!(f, "storage error: {}", reason),
+            BankError::Render(reason) => write!(f, "could not render document: {}", reason),
+            BankError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for BankError {}
+
+impl From<MoneyError> for BankError {
+    fn from(err: MoneyError) -> Self {
+        BankError::Money(err)
+    }
+}
@@ -0,0 +1,181 @@
+use std::fmt;
+use chrono::{Datelike, NaiveDate};
+use serde::{Serialize, Deserialize};
+use super::{Account, BankError, Money, TransactionType};
+
+/// Why a fee was charged. Recorded on every fee transaction and used to
+/// waive fees per customer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum FeeReason {
+    MonthlyMaintenance,
+    BelowMinimumBalance,
+    ExcessWithdrawal,
+    Transfer,
+    /// A credit card's minimum payment arrived after the due date.
+    LatePayment,
+    /// Interest forfeited for drawing on a certificate of deposit early.
+    EarlyWithdrawal,
+}
+
+impl FeeReason {
+    pub const ALL: [FeeReason; 6] = [
+        FeeReason::MonthlyMaintenance,
+        FeeReason::BelowMinimumBalance,
+        FeeReason::ExcessWithdrawal,
+        FeeReason::Transfer,
+        FeeReason::LatePayment,
+        FeeReason::EarlyWithdrawal,
+    ];
+
+    /// The short code printed on statements.
+    pub fn code(&self) -> &'static str {
+        match self {
+            FeeReason::MonthlyMaintenance => "MAINT",
+            FeeReason::BelowMinimumBalance => "MINBAL",
+            FeeReason::ExcessWithdrawal => "EXCWD",
+            FeeReason::Transfer => "XFER",
+            FeeReason::LatePayment => "LATE",
+            FeeReason::EarlyWithdrawal => "EARLY",
+        }
+    }
+}
+
+impl fmt::Display for FeeReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Charged when the balance ends any day of the month below `minimum`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MinimumBalanceFee {
+    pub minimum: Money,
+    pub fee: Money,
+}
+
+/// Charged for each withdrawal or outgoing transfer in a month beyond the
+/// first `free_per_month`. Internal and reversed transfers don't count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExcessWithdrawalFee {
+    pub free_per_month: u32,
+    pub fee: Money,
+}
+
+/// A fee worked out for one month, before any waiver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssessedFee {
+    pub reason: FeeReason,
+    pub amount: Money,
+}
+
+/// The fees a product charges, all assessed once a month.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    pub monthly_maintenance: Option<Money>,
+    pub below_minimum: Option<MinimumBalanceFee>,
+    pub excess_withdrawal: Option<ExcessWithdrawalFee>,
+    /// Charged for each outgoing transfer other than internal and reversed
+    /// ones.
+    pub per_transfer: Option<Money>,
+}
+
+impl FeeSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_monthly_maintenance(mut self, fee: Money) -> Self {
+        self.monthly_maintenance = Some(fee);
+        self
+    }
+
+    pub fn with_below_minimum(mut self, minimum: Money, fee: Money) -> Self {
+        self.below_minimum = Some(MinimumBalanceFee { minimum, fee });
+        self
+    }
+
+    pub fn with_excess_withdrawal(mut self, free_per_month: u32, fee: Money) -> Self {
+        self.excess_withdrawal = Some(ExcessWithdrawalFee { free_per_month, fee });
+        self
+    }
+
+    pub fn with_per_transfer(mut self, fee: Money) -> Self {
+        self.per_transfer = Some(fee);
+        self
+    }
+
+    /// Checks that every fee is positive and that a minimum balance isn't
+    /// negative.
+    pub fn validate(&self) -> Result<(), BankError> {
+        let fees = [
+            self.monthly_maintenance,
+            self.below_minimum.map(|rule| rule.fee),
+            self.excess_withdrawal.map(|rule| rule.fee),
+            self.per_transfer,
+        ];
+        if let Some(fee) = fees.into_iter().flatten().find(|fee| !fee.is_positive()) {
+            return Err(BankError::InvalidAmount(fee));
+        }
+        if self.below_minimum.is_some_and(|rule| rule.minimum.is_negative()) {
+            return Err(BankError::InvalidField { field: "minimum balance", reason: "must not be negative".to_string() });
+        }
+        Ok(())
+    }
+
+    /// Works out the fees `account` owes for the calendar month ending on
+    /// `month_end`, from its history. Days before the account was opened
+    /// don't count towards the minimum balance.
+    pub fn assess(&self, account: &Account, month_end: NaiveDate) -> Result<Vec<AssessedFee>, BankError> {
+        let mut fees = vec![];
+        if let Some(fee) = self.monthly_maintenance {
+            fees.push(AssessedFee { reason: FeeReason::MonthlyMaintenance, amount: fee });
+        }
+
+        if let Some(rule) = self.below_minimum {
+            let month_start = month_end.with_day(1).expect("every month has a first day");
+            let opened = account.opened_at.map(|at| at.date_naive()).unwrap_or(month_start);
+            let mut below = false;
+            for day in month_start.max(opened).iter_days().take_while(|day| *day <= month_end) {
+                if account.balance_at_end_of(day)?.checked_cmp(&rule.minimum)?.is_lt() {
+                    below = true;
+                    break;
+                }
+            }
+            if below {
+                fees.push(AssessedFee { reason: FeeReason::BelowMinimumBalance, amount: rule.fee });
+            }
+        }
+
+        if let Some(rule) = self.excess_withdrawal {
+            let excess = account.customer_debits_in_month(month_end).saturating_sub(rule.free_per_month as usize);
+            if excess > 0 {
+                fees.push(AssessedFee { reason: FeeReason::ExcessWithdrawal, amount: rule.fee.checked_mul(excess as i64)? });
+            }
+        }
+
+        if let Some(fee) = self.per_transfer {
+            let transfers = account.history
+                .iter()
+                .filter(|tx| matches!(tx.tx_type, TransactionType::Transfer { .. }) && !tx.internal)
+                .filter(|tx| tx.reversed_by.is_none())
+                .filter(|tx| same_month(tx.timestamp.date_naive(), month_end))
+                .count();
+            if transfers > 0 {
+                fees.push(AssessedFee { reason: FeeReason::Transfer, amount: fee.checked_mul(transfers as i64)? });
+            }
+        }
+        Ok(fees)
+    }
+}
+
+pub(crate) fn same_month(a: NaiveDate, b: NaiveDate) -> bool {
+    a.year() == b.year() && a.month() == b.month()
+}
+
+/// The last day of the month `date` falls in.
+pub(crate) fn month_end(date: NaiveDate) -> NaiveDate {
+    let (year, month) = if date.month() == 12 { (date.year() + 1, 1) } else { (date.year(), date.month() + 1) };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first| first.pred_opt())
+        .expect("month ends are valid dates")
+}
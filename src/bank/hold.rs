@@ -0,0 +1,50 @@
+use std::fmt;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use super::{HoldId, Money};
+
+/// Why money is being held back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HoldKind {
+    /// A card payment authorized but not yet settled. Needs the funds to
+    /// be available when placed.
+    CardAuthorization,
+    /// A deposited cheque that hasn't cleared yet.
+    ChequeClearing,
+    /// Ordered by a court or regulator. May exceed what is available.
+    Legal,
+}
+
+impl HoldKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HoldKind::CardAuthorization => "Card authorization",
+            HoldKind::ChequeClearing => "Cheque clearing",
+            HoldKind::Legal => "Legal hold",
+        }
+    }
+}
+
+impl fmt::Display for HoldKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Money set aside on an account. A hold lowers the available balance but
+/// posts nothing until it is captured.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hold {
+    pub id: HoldId,
+    pub kind: HoldKind,
+    pub amount: Money,
+    pub placed_at: DateTime<Utc>,
+    /// When the hold lapses on its own, if ever.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl Hold {
+    pub fn is_expired(&self, at: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires| expires <= at)
+    }
+}
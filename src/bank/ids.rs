@@ -0,0 +1,127 @@
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+use serde::{Serialize, Deserialize};
+
+macro_rules! id_type {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(pub u64);
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseIntError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                s.trim().parse().map($name)
+            }
+        }
+    };
+}
+
+id_type!(
+    /// Identifies a [`Customer`](super::Customer).
+    CustomerId
+);
+
+id_type!(
+    /// Identifies an [`Account`](super::Account).
+    AccountId
+);
+
+id_type!(
+    /// Identifies a [`Transaction`](super::Transaction). Zero means the
+    /// transaction was never assigned one by a bank.
+    TransactionId
+);
+
+id_type!(
+    /// Identifies a [`Hold`](super::Hold) on an account.
+    HoldId
+);
+
+id_type!(
+    /// Identifies a [`ScheduledTransfer`](super::schedule::ScheduledTransfer).
+    ScheduleId
+);
+
+/// The next ID to hand out for each kind of record, and the last journal
+/// event applied. Every kind counts up from 1 on its own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdSequences {
+    #[serde(default = "first_id")]
+    pub next_customer_id: u64,
+    #[serde(default = "first_id")]
+    pub next_account_id: u64,
+    #[serde(default = "first_id")]
+    pub next_transaction_id: u64,
+    #[serde(default = "first_id")]
+    pub next_hold_id: u64,
+    #[serde(default = "first_id")]
+    pub next_schedule_id: u64,
+    /// The sequence number of the last event applied, 0 before the first.
+    /// A journal replayed onto stored data skips the events it already
+    /// holds.
+    #[serde(default)]
+    pub last_event: u64,
+}
+
+fn first_id() -> u64 {
+    1
+}
+
+impl IdSequences {
+    pub fn new() -> Self {
+        Self {
+            next_customer_id: 1,
+            next_account_id: 1,
+            next_transaction_id: 1,
+            next_hold_id: 1,
+            next_schedule_id: 1,
+            last_event: 0,
+        }
+    }
+
+    pub fn allocate_customer_id(&mut self) -> CustomerId {
+        let id = CustomerId(self.next_customer_id);
+        self.next_customer_id += 1;
+        id
+    }
+
+    pub fn allocate_account_id(&mut self) -> AccountId {
+        let id = AccountId(self.next_account_id);
+        self.next_account_id += 1;
+        id
+    }
+
+    pub fn allocate_transaction_id(&mut self) -> TransactionId {
+        let id = TransactionId(self.next_transaction_id);
+        self.next_transaction_id += 1;
+        id
+    }
+
+    pub fn allocate_hold_id(&mut self) -> HoldId {
+        let id = HoldId(self.next_hold_id);
+        self.next_hold_id += 1;
+        id
+    }
+
+    pub fn allocate_schedule_id(&mut self) -> ScheduleId {
+        let id = ScheduleId(self.next_schedule_id);
+        self.next_schedule_id += 1;
+        id
+    }
+}
+
+impl Default for IdSequences {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,334 @@
+use std::fmt;
+use chrono::{Datelike, NaiveDate};
+use serde::{Serialize, Deserialize, Deserializer};
+use super::{BankError, Money, MoneyError, RoundingMode};
+
+/// Accrued interest is kept in millionths of a minor unit so daily amounts
+/// far smaller than a cent still add up.
+pub const MICROS_PER_MINOR: i64 = 1_000_000;
+
+/// An annual interest rate, held in parts per million: 1.5% is 15,000.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Rate(i64);
+
+impl Rate {
+    pub const ZERO: Rate = Rate(0);
+
+    pub fn from_ppm(ppm: i64) -> Self {
+        Rate(ppm)
+    }
+
+    /// A rate in basis points, so 150 is 1.5%. Any `i32` fits once scaled
+    /// to ppm, so this can't overflow.
+    pub fn from_bps(bps: i32) -> Self {
+        Rate(i64::from(bps) * 100)
+    }
+
+    pub fn ppm(&self) -> i64 {
+        self.0
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        let whole = abs / 10_000;
+        let fraction = format!("{:04}", abs % 10_000);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            write!(f, "{}{}%", sign, whole)
+        } else {
+            write!(f, "{}{}.{}%", sign, whole, fraction)
+        }
+    }
+}
+
+/// How days between two dates are counted and how many make a year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DayCount {
+    /// Calendar days over a 365-day year.
+    #[default]
+    Actual365,
+    /// Calendar days over a 360-day year.
+    Actual360,
+    /// Every month has 30 days and the year 360 (the European 30E/360
+    /// convention: the 31st counts as the 30th).
+    Thirty360,
+}
+
+impl DayCount {
+    /// Days from `start` to `end` under this convention.
+    pub fn days_between(&self, start: NaiveDate, end: NaiveDate) -> i64 {
+        match self {
+            DayCount::Actual365 | DayCount::Actual360 => (end - start).num_days(),
+            DayCount::Thirty360 => thirty_360_serial(end) - thirty_360_serial(start),
+        }
+    }
+
+    pub fn days_in_year(&self) -> i64 {
+        match self {
+            DayCount::Actual365 => 365,
+            DayCount::Actual360 | DayCount::Thirty360 => 360,
+        }
+    }
+}
+
+fn thirty_360_serial(date: NaiveDate) -> i64 {
+    i64::from(date.year()) * 360 + i64::from(date.month()) * 30 + i64::from(date.day().min(30))
+}
+
+/// How often accrued interest is paid into the account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PostingFrequency {
+    /// On the last day of every month.
+    #[default]
+    Monthly,
+    /// On the last day of March, June, September and December.
+    Quarterly,
+}
+
+impl PostingFrequency {
+    pub fn is_posting_date(&self, date: NaiveDate) -> bool {
+        let month_end = date.succ_opt().is_none_or(|next| next.month() != date.month());
+        match self {
+            PostingFrequency::Monthly => month_end,
+            PostingFrequency::Quarterly => month_end && date.month().is_multiple_of(3),
+        }
+    }
+}
+
+/// One balance band: the part of a balance above `floor` (in the account
+/// currency's minor units) earns `rate`, up to the next band's floor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateTier {
+    pub floor: i64,
+    pub rate: Rate,
+}
+
+/// The rates paid across balance bands. With tiers of 0.5% from 0 and
+/// 1.5% from 10,000, a 12,000 balance earns 0.5% on 10,000 and 1.5% on
+/// 2,000.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RateTable {
+    tiers: Vec<RateTier>,
+}
+
+impl RateTable {
+    /// One rate for the whole balance.
+    pub fn flat(rate: Rate) -> Self {
+        Self { tiers: vec![RateTier { floor: 0, rate }] }
+    }
+
+    /// Bands given lowest first. The first must start at zero and each
+    /// later one strictly above the last.
+    pub fn tiered(tiers: Vec<RateTier>) -> Result<Self, BankError> {
+        let invalid = |reason: &str| BankError::InvalidField { field: "rate tiers", reason: reason.to_string() };
+        match tiers.first() {
+            None => return Err(invalid("need at least one tier")),
+            Some(first) if first.floor != 0 => return Err(invalid("the first tier must start at zero")),
+            Some(_) => {}
+        }
+        if tiers.windows(2).any(|pair| pair[1].floor <= pair[0].floor) {
+            return Err(invalid("floors must increase"));
+        }
+        let table = Self { tiers };
+        table.validate()?;
+        Ok(table)
+    }
+
+    /// Checks that no band pays a negative rate. [`RateTable::tiered`] does
+    /// this itself; a [`RateTable::flat`] table is checked when it's set.
+    pub fn validate(&self) -> Result<(), BankError> {
+        if self.tiers.iter().any(|tier| tier.rate.ppm() < 0) {
+            return Err(BankError::InvalidField { field: "rate tiers", reason: "rates must not be negative".to_string() });
+        }
+        Ok(())
+    }
+
+    pub fn tiers(&self) -> &[RateTier] {
+        &self.tiers
+    }
+
+    /// The sum of each band's portion of `balance_minor` times its rate in
+    /// ppm.
+    fn weighted_ppm(&self, balance_minor: i64) -> i128 {
+        let mut total = 0i128;
+        for (i, tier) in self.tiers.iter().enumerate() {
+            let ceiling = self.tiers.get(i + 1).map_or(balance_minor, |next| next.floor.min(balance_minor));
+            if ceiling <= tier.floor {
+                break;
+            }
+            total += i128::from(ceiling - tier.floor) * i128::from(tier.rate.ppm());
+        }
+        total
+    }
+}
+
+impl<'de> Deserialize<'de> for RateTable {
+    /// Goes through [`RateTable::tiered`], so saved tiers are checked like
+    /// new ones.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Fields {
+            tiers: Vec<RateTier>,
+        }
+
+        let fields = Fields::deserialize(deserializer)?;
+        Self::tiered(fields.tiers).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Rate tables by the date they take effect. A change applies to every day
+/// from its date on, so interest already accrued is never recalculated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateSchedule {
+    initial: RateTable,
+    changes: Vec<(NaiveDate, RateTable)>,
+}
+
+impl RateSchedule {
+    pub fn new(initial: RateTable) -> Self {
+        Self { initial, changes: vec![] }
+    }
+
+    /// Uses `table` from `effective` on, replacing any change already set
+    /// for that date.
+    pub fn change_from(&mut self, effective: NaiveDate, table: RateTable) {
+        match self.changes.binary_search_by_key(&effective, |(date, _)| *date) {
+            Ok(i) => self.changes[i].1 = table,
+            Err(i) => self.changes.insert(i, (effective, table)),
+        }
+    }
+
+    /// Checks the initial table and every change.
+    pub fn validate(&self) -> Result<(), BankError> {
+        self.initial.validate()?;
+        self.changes.iter().try_for_each(|(_, table)| table.validate())
+    }
+
+    pub fn table_on(&self, date: NaiveDate) -> &RateTable {
+        self.changes
+            .iter()
+            .rev()
+            .find(|(effective, _)| *effective <= date)
+            .map_or(&self.initial, |(_, table)| table)
+    }
+}
+
+/// A teaser rate paid on the whole balance for the first `days` days after
+/// an account is opened, instead of the schedule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Promotion {
+    pub rate: Rate,
+    pub days: i64,
+}
+
+/// How a product earns interest.
+///
+/// Each day's rate comes from the promotion while it lasts, then from the
+/// table in effect that day. Each day's interest is worked out on the
+/// end-of-day balance and rounded to a millionth of a minor unit with
+/// `rounding`. On a posting date the whole minor units accrued are paid;
+/// the sub-unit remainder stays accrued for the next period, so rounding
+/// never gains or loses money over time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterestPolicy {
+    pub rates: RateSchedule,
+    pub promotion: Option<Promotion>,
+    pub day_count: DayCount,
+    pub frequency: PostingFrequency,
+    pub rounding: RoundingMode,
+}
+
+impl InterestPolicy {
+    /// A flat annual rate.
+    pub fn new(annual_rate: Rate) -> Self {
+        Self::with_rates(RateSchedule::new(RateTable::flat(annual_rate)))
+    }
+
+    pub fn with_rates(rates: RateSchedule) -> Self {
+        Self {
+            rates,
+            promotion: None,
+            day_count: DayCount::default(),
+            frequency: PostingFrequency::default(),
+            rounding: RoundingMode::default(),
+        }
+    }
+
+    /// Checks the rate tables and the promotion, if any.
+    pub fn validate(&self) -> Result<(), BankError> {
+        self.rates.validate()?;
+        if let Some(promotion) = self.promotion {
+            let invalid = |reason: &str| BankError::InvalidField { field: "promotion", reason: reason.to_string() };
+            if promotion.rate.ppm() < 0 {
+                return Err(invalid("rate must not be negative"));
+            }
+            if promotion.days < 0 {
+                return Err(invalid("days must not be negative"));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn with_promotion(mut self, promotion: Promotion) -> Self {
+        self.promotion = Some(promotion);
+        self
+    }
+
+    pub fn with_day_count(mut self, day_count: DayCount) -> Self {
+        self.day_count = day_count;
+        self
+    }
+
+    pub fn with_frequency(mut self, frequency: PostingFrequency) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    pub fn with_rounding(mut self, rounding: RoundingMode) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Interest in micro-minor units earned by `balance` over the single day
+    /// starting on `date`, for an account opened on `opened`. Balances at or
+    /// below zero earn nothing.
+    pub fn daily_micros(&self, balance: Money, date: NaiveDate, opened: Option<NaiveDate>) -> Result<i64, BankError> {
+        if !balance.is_positive() {
+            return Ok(0);
+        }
+        let next = date.succ_opt().ok_or(BankError::Money(MoneyError::Overflow))?;
+        let days = self.day_count.days_between(date, next);
+        let promoted = match (self.promotion, opened) {
+            (Some(promotion), Some(opened)) => (date - opened).num_days() < promotion.days,
+            _ => false,
+        };
+        let weighted_ppm = match self.promotion {
+            Some(promotion) if promoted => i128::from(balance.minor_units()) * i128::from(promotion.rate.ppm()),
+            _ => self.rates.table_on(date).weighted_ppm(balance.minor_units()),
+        };
+        // minor * ppm / 1e6 is minor units; times 1e6 for micros cancels out.
+        let num = weighted_ppm * i128::from(days);
+        let micros = self.rounding.divide(num, i128::from(self.day_count.days_in_year()));
+        i64::try_from(micros).map_err(|_| BankError::Money(MoneyError::Overflow))
+    }
+
+    /// The whole minor units to pay out of `accrued_micros`. Always rounds
+    /// down: paying a part unit that hasn't been earned yet would leave the
+    /// accrual negative.
+    pub fn payable_minor(&self, accrued_micros: i64) -> i64 {
+        accrued_micros / MICROS_PER_MINOR
+    }
+}
+
+/// Interest an account has earned but not yet been paid.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterestAccrual {
+    /// Unpaid interest in millionths of the account's minor unit.
+    pub accrued_micros: i64,
+    /// The last day interest has been worked out for.
+    pub accrued_through: Option<NaiveDate>,
+}
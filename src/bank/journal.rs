@@ -0,0 +1,280 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Serialize, Deserialize};
+use super::storage::storage_error;
+use super::card::CardTerms;
+use super::certificate::CertificateTerms;
+use super::fees::{FeeReason, FeeSchedule};
+use super::interest::{InterestPolicy, RateTable};
+use super::hold::HoldKind;
+use super::loan::{LoanTerms, Recalculation};
+use super::schedule::{Outcome, StandingOrder};
+use super::{AccountId, AccountNumber, AccountNumberFormat, AccountProduct, AccountStatus, BankError, Channel, CustomerId, HoldId, LifecyclePolicy, Money, PartyRole, ScheduleId, SigningRule, TransactionId};
+
+/// Money moved from the protecting `savings_id` ahead of a debit that would
+/// otherwise fail or overdraw the account.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OverdraftCover {
+    pub savings_id: AccountId,
+    pub amount: Money,
+}
+
+/// A state change to the bank, recorded before it is applied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Event {
+    CustomerCreated { id: CustomerId, name: String, address: String, phone: String, email: String },
+    CustomerUpdated {
+        id: CustomerId,
+        name: Option<String>,
+        address: Option<String>,
+        phone: Option<String>,
+        email: Option<String>,
+    },
+    AccountOpened {
+        id: AccountId,
+        /// Absent in journals written before accounts had numbers; the
+        /// number is then derived from `id` when replayed.
+        #[serde(default)]
+        number: Option<AccountNumber>,
+        customer_id: CustomerId,
+        #[serde(alias = "account_type", deserialize_with = "super::product::deserialize_stored")]
+        product: AccountProduct,
+    },
+    /// Accounts opened from now on are numbered in `format`.
+    AccountNumberFormatSet { format: AccountNumberFormat },
+    Deposit {
+        account_id: AccountId,
+        amount: Money,
+        #[serde(default)]
+        memo: Option<String>,
+        #[serde(default)]
+        reference: Option<String>,
+    },
+    /// Pays cash out, after moving `cover` in from overdraft protection and
+    /// before charging `overdraft_fee`, all or nothing.
+    Withdrawal {
+        account_id: AccountId,
+        amount: Money,
+        #[serde(default)]
+        memo: Option<String>,
+        #[serde(default)]
+        reference: Option<String>,
+        #[serde(default)]
+        cover: Option<OverdraftCover>,
+        #[serde(default)]
+        overdraft_fee: Option<Money>,
+    },
+    /// Moves money between accounts, with any cover and overdraft fee on
+    /// `from_id` as for a withdrawal.
+    Transfer {
+        from_id: AccountId,
+        to_id: AccountId,
+        amount: Money,
+        memo: Option<String>,
+        #[serde(default)]
+        reference: Option<String>,
+        #[serde(default)]
+        cover: Option<OverdraftCover>,
+        #[serde(default)]
+        overdraft_fee: Option<Money>,
+    },
+    /// Accounts of `product` earn interest under `policy` from the next
+    /// accrual run on.
+    InterestPolicySet { product: AccountProduct, policy: InterestPolicy },
+    /// `product` pays `table` for every day from `effective` on.
+    RateChangeScheduled { product: AccountProduct, effective: NaiveDate, table: RateTable },
+    /// Interest worked out for every day up to and including `through`, in
+    /// millionths of a minor unit.
+    InterestAccrued { account_id: AccountId, through: NaiveDate, micros: i64 },
+    /// Accrued interest paid into the account at the end of `on`.
+    InterestPosted { account_id: AccountId, on: NaiveDate, amount: Money },
+    /// A new arranged overdraft; zero removes it.
+    OverdraftLimitSet { account_id: AccountId, limit: Money },
+    /// Links (or with `None` unlinks) the savings account that covers
+    /// shortfalls on `account_id`.
+    OverdraftProtectionSet { account_id: AccountId, savings_id: Option<AccountId> },
+    /// Moves `amount` from the protecting `savings_id` into `account_id`
+    /// ahead of a debit that would otherwise fail or overdraw it. Older
+    /// journals record this apart from the debit; it is now part of the
+    /// `Withdrawal` or `Transfer`.
+    OverdraftCovered { account_id: AccountId, savings_id: AccountId, amount: Money },
+    /// Debits that leave an account of `product` below zero are charged
+    /// `fee` from now on.
+    OverdraftFeeSet { product: AccountProduct, fee: Money },
+    /// Recorded apart from the debit that caused it by older journals; it
+    /// is now part of the `Withdrawal` or `Transfer`.
+    OverdraftFeeCharged { account_id: AccountId, amount: Money },
+    /// Accounts of `product` are charged `schedule` from the next
+    /// assessment on.
+    FeeScheduleSet { product: AccountProduct, schedule: FeeSchedule },
+    /// A scheduled fee charged at the end of `on`.
+    FeeCharged { account_id: AccountId, reason: FeeReason, amount: Money, on: NaiveDate },
+    /// Fees for every month up to `through` have been dealt with.
+    FeesAssessed { account_id: AccountId, through: NaiveDate },
+    FeeWaiverSet { customer_id: CustomerId, reason: FeeReason, waived: bool },
+    FeeReversed { account_id: AccountId, fee_id: TransactionId },
+    /// Undoes a deposit, withdrawal, interest payment or both legs of a
+    /// transfer, with `reason` as the memo of the compensating entries.
+    TransactionReversed { transaction_id: TransactionId, reason: String },
+    AccountStatusChanged { account_id: AccountId, status: AccountStatus },
+    /// Dormancy and reopening follow `policy` from now on.
+    LifecyclePolicySet { policy: LifecyclePolicy },
+    AccountPartyAdded { account_id: AccountId, customer_id: CustomerId, role: PartyRole },
+    AccountPartyRemoved { account_id: AccountId, customer_id: CustomerId, role: PartyRole },
+    SigningRuleSet { account_id: AccountId, rule: SigningRule },
+    HoldPlaced {
+        account_id: AccountId,
+        hold_id: HoldId,
+        kind: HoldKind,
+        amount: Money,
+        expires_at: Option<DateTime<Utc>>,
+    },
+    HoldReleased { account_id: AccountId, hold_id: HoldId },
+    /// Posts `amount`, at most the held amount, and ends the hold.
+    HoldCaptured { account_id: AccountId, hold_id: HoldId, amount: Money },
+    HoldExpired { account_id: AccountId, hold_id: HoldId },
+    TransferScheduled { id: ScheduleId, order: StandingOrder },
+    ScheduledTransferCancelled { id: ScheduleId },
+    /// The result of trying the transfer due on `due`. An executed
+    /// transfer's own events come just before.
+    ScheduledTransferRun { id: ScheduleId, due: NaiveDate, ran_on: NaiveDate, outcome: Outcome },
+    /// Pays a new loan on `loan_id` out to `linked_id`, drawing up its
+    /// schedule from the record's date.
+    LoanDisbursed { loan_id: AccountId, linked_id: AccountId, terms: LoanTerms },
+    /// Installment `installment` paid from the loan's linked account.
+    LoanRepaid { loan_id: AccountId, installment: u32, interest: Money, principal: Money },
+    /// Principal paid ahead of schedule; the rest of the schedule is redrawn.
+    LoanPrepaid { loan_id: AccountId, amount: Money, recalculation: Recalculation },
+    CreditCardOpened { account_id: AccountId, credit_limit: Money, terms: CardTerms },
+    /// Interest on a card balance, charged at the end of `on`.
+    CardInterestCharged { account_id: AccountId, amount: Money, on: NaiveDate },
+    /// Closes the cycle ending on `closing_date` and snapshots its
+    /// statement from the account's history.
+    StatementClosed { account_id: AccountId, closing_date: NaiveDate },
+    /// Statement `number`'s due date has passed with `paid` received.
+    StatementDue { account_id: AccountId, number: u32, paid: Money },
+    /// Moves `amount` from `funding_id` into a new certificate of deposit,
+    /// whose first term starts on the record's date.
+    CertificateOpened { account_id: AccountId, funding_id: AccountId, amount: Money, terms: CertificateTerms },
+    /// The current term has ended, earning `interest`; the maturity action
+    /// decides what follows.
+    CertificateMatured { account_id: AccountId, interest: Money },
+    /// Moves `amount` from a certificate that has matured into `to_id`, the
+    /// account its terms pay out to.
+    CertificatePaidOut { account_id: AccountId, to_id: AccountId, amount: Money },
+    /// The grace period is over and whatever is left starts a new term.
+    CertificateGraceEnded { account_id: AccountId },
+    /// Takes `amount` out before maturity, keeping `penalty` of it back.
+    CertificateBroken { account_id: AccountId, amount: Money, penalty: Money },
+}
+
+/// One line of the journal file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalRecord {
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    /// Where the request came from; absent in older journals.
+    #[serde(default)]
+    pub channel: Option<Channel>,
+    pub event: Event,
+}
+
+/// An append-only file of [`JournalRecord`]s, one JSON object per line.
+/// Every append is synced to disk before it returns.
+#[derive(Debug)]
+pub struct EventJournal {
+    path: PathBuf,
+    file: File,
+    last_sequence: u64,
+}
+
+impl EventJournal {
+    /// Opens `path` for appending, creating it if needed. A partially written
+    /// last line, left by a crash mid-append, is cut off.
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Self, BankError> {
+        let path = path.into();
+        let (records, valid_len) = read_records(&path)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(storage_error)?;
+        if file.metadata().map_err(storage_error)?.len() > valid_len {
+            file.set_len(valid_len).map_err(storage_error)?;
+            file.sync_all().map_err(storage_error)?;
+        }
+        Ok(Self {
+            path,
+            file,
+            last_sequence: records.last().map_or(0, |r| r.sequence),
+        })
+    }
+
+    /// Every complete record in the file at `path`, oldest first. A missing
+    /// file is an empty journal.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Vec<JournalRecord>, BankError> {
+        read_records(path.as_ref()).map(|(records, _)| records)
+    }
+
+    pub fn append(&mut self, record: &JournalRecord) -> Result<(), BankError> {
+        if record.sequence <= self.last_sequence {
+            return Err(BankError::Storage(format!(
+                "journal record {} is not after {}",
+                record.sequence, self.last_sequence
+            )));
+        }
+        let mut line = serde_json::to_vec(record).map_err(storage_error)?;
+        line.push(b'\n');
+        self.file.write_all(&line).map_err(storage_error)?;
+        self.file.sync_data().map_err(storage_error)?;
+        self.last_sequence = record.sequence;
+        Ok(())
+    }
+
+    pub fn last_sequence(&self) -> u64 {
+        self.last_sequence
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Parses the journal, returning its records and the byte length of the
+/// complete lines. Only the final line may be damaged; anything earlier is
+/// corruption.
+fn read_records(path: &Path) -> Result<(Vec<JournalRecord>, u64), BankError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok((vec![], 0)),
+        Err(err) => return Err(storage_error(err)),
+    };
+
+    let mut reader = BufReader::new(file);
+    let mut records = vec![];
+    let mut valid_len = 0u64;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line).map_err(storage_error)?;
+        if read == 0 {
+            break;
+        }
+        // A torn write can only be the last thing in the file.
+        if !line.ends_with('\n') {
+            break;
+        }
+        let record: JournalRecord = serde_json::from_str(line.trim_end()).map_err(|err| {
+            BankError::Storage(format!(
+                "corrupt journal record after sequence {}: {}",
+                records.last().map_or(0, |r: &JournalRecord| r.sequence),
+                err
+            ))
+        })?;
+        records.push(record);
+        valid_len += read as u64;
+    }
+    Ok((records, valid_len))
+}
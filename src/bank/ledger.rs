@@ -0,0 +1,248 @@
+use std::collections::{BTreeMap, HashMap};
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use super::{AccountId, BankError, Currency, Money, MoneyError};
+
+/// An account in the bank's general ledger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum LedgerAccount {
+    /// The bank's liability to the holder of a customer account.
+    Customer(AccountId),
+    /// Cash held by the bank; the other side of deposits and withdrawals.
+    Cash,
+    /// Clearing account for amounts whose origin isn't known, such as
+    /// balances loaded from data saved before the ledger existed.
+    Suspense,
+    /// Interest the bank has paid on customer balances.
+    InterestExpense,
+    /// Fees the bank has charged its customers.
+    FeeIncome,
+    /// Interest the bank has earned on loans.
+    InterestIncome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Side {
+    Debit,
+    Credit,
+}
+
+impl LedgerAccount {
+    /// The side on which this account's balance is normally carried. Assets
+    /// increase with debits, liabilities with credits.
+    pub fn normal_side(&self) -> Side {
+        match self {
+            LedgerAccount::Customer(_) | LedgerAccount::FeeIncome | LedgerAccount::InterestIncome => Side::Credit,
+            LedgerAccount::Cash | LedgerAccount::Suspense | LedgerAccount::InterestExpense => Side::Debit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Posting {
+    pub account: LedgerAccount,
+    pub side: Side,
+    pub amount: Money,
+}
+
+impl Posting {
+    pub fn debit(account: LedgerAccount, amount: Money) -> Self {
+        Self { account, side: Side::Debit, amount }
+    }
+
+    pub fn credit(account: LedgerAccount, amount: Money) -> Self {
+        Self { account, side: Side::Credit, amount }
+    }
+
+    /// The posting's effect on a debit-normal balance, in minor units.
+    fn signed_minor(&self) -> i64 {
+        match self.side {
+            Side::Debit => self.amount.minor_units(),
+            Side::Credit => -self.amount.minor_units(),
+        }
+    }
+}
+
+/// A balanced set of postings recorded together.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: u64,
+    pub timestamp: DateTime<Utc>,
+    pub description: String,
+    pub postings: Vec<Posting>,
+}
+
+impl JournalEntry {
+    /// Checks that every amount is positive and that debits equal credits in
+    /// each currency.
+    pub fn validate(&self) -> Result<(), BankError> {
+        validate_postings(self.id, &self.postings)
+    }
+}
+
+fn validate_postings(id: u64, postings: &[Posting]) -> Result<(), BankError> {
+    if postings.len() < 2 {
+        return Err(BankError::UnbalancedEntry(format!("entry {} needs at least two postings", id)));
+    }
+    let mut totals: BTreeMap<Currency, i64> = BTreeMap::new();
+    for posting in postings {
+        if !posting.amount.is_positive() {
+            return Err(BankError::InvalidAmount(posting.amount));
+        }
+        let total = totals.entry(posting.amount.currency()).or_insert(0);
+        *total = total.checked_add(posting.signed_minor()).ok_or(BankError::Money(MoneyError::Overflow))?;
+    }
+    match totals.iter().find(|(_, total)| **total != 0) {
+        Some((currency, _)) => Err(BankError::UnbalancedEntry(format!(
+            "entry {} debits and credits differ in {}",
+            id, currency
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// The bank-wide journal. Entries can only be appended, and only if they
+/// balance, so the sum of all debits always equals the sum of all credits.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(try_from = "Vec<JournalEntry>", into = "Vec<JournalEntry>")]
+pub struct Ledger {
+    entries: Vec<JournalEntry>,
+    /// Debit-normal running balances in minor units, rebuilt from `entries`.
+    balances: HashMap<(LedgerAccount, Currency), i64>,
+}
+
+impl Ledger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validates and appends a new entry, returning its ID.
+    pub fn post(&mut self, timestamp: DateTime<Utc>, description: String, postings: Vec<Posting>) -> Result<u64, BankError> {
+        let entry = JournalEntry {
+            id: self.entries.len() as u64 + 1,
+            timestamp,
+            description,
+            postings,
+        };
+        self.append(entry)
+    }
+
+    /// Checks that entries with these postings, posted in order, would all
+    /// be accepted, without posting anything.
+    pub fn check<'a>(&self, entries: impl IntoIterator<Item = &'a [Posting]>) -> Result<(), BankError> {
+        let mut updated = HashMap::new();
+        for (id, postings) in (self.entries.len() as u64 + 1..).zip(entries) {
+            validate_postings(id, postings)?;
+            self.stage(postings, &mut updated)?;
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, entry: JournalEntry) -> Result<u64, BankError> {
+        entry.validate()?;
+
+        // Compute every new balance first so an overflow leaves the ledger untouched.
+        let mut updated = HashMap::new();
+        self.stage(&entry.postings, &mut updated)?;
+        self.balances.extend(updated);
+
+        let id = entry.id;
+        self.entries.push(entry);
+        Ok(id)
+    }
+
+    /// Adds postings to the balances in `updated`, falling back to the
+    /// ledger's own balances for accounts not yet touched.
+    fn stage(&self, postings: &[Posting], updated: &mut HashMap<(LedgerAccount, Currency), i64>) -> Result<(), BankError> {
+        for posting in postings {
+            let key = (posting.account, posting.amount.currency());
+            let current = updated.get(&key).or(self.balances.get(&key)).copied().unwrap_or(0);
+            let next = current
+                .checked_add(posting.signed_minor())
+                .ok_or(BankError::Money(MoneyError::Overflow))?;
+            updated.insert(key, next);
+        }
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// The account's balance on its normal side, so a customer account in
+    /// credit and cash on hand are both positive.
+    pub fn balance(&self, account: LedgerAccount, currency: Currency) -> Money {
+        let debit_normal = self.balances.get(&(account, currency)).copied().unwrap_or(0);
+        let minor = match account.normal_side() {
+            Side::Debit => debit_normal,
+            Side::Credit => -debit_normal,
+        };
+        Money::from_minor(minor, currency)
+    }
+
+    pub fn trial_balance(&self) -> TrialBalance {
+        let mut lines: Vec<TrialBalanceLine> = self.balances
+            .iter()
+            .map(|(&(account, currency), &minor)| TrialBalanceLine {
+                account,
+                debit: Money::from_minor(minor.max(0), currency),
+                credit: Money::from_minor((-minor).max(0), currency),
+            })
+            .collect();
+        lines.sort_by_key(|line| (line.debit.currency(), line.account));
+        TrialBalance { lines }
+    }
+}
+
+impl TryFrom<Vec<JournalEntry>> for Ledger {
+    type Error = BankError;
+
+    fn try_from(entries: Vec<JournalEntry>) -> Result<Self, Self::Error> {
+        let mut ledger = Ledger::new();
+        for entry in entries {
+            ledger.append(entry)?;
+        }
+        Ok(ledger)
+    }
+}
+
+impl From<Ledger> for Vec<JournalEntry> {
+    fn from(ledger: Ledger) -> Self {
+        ledger.entries
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrialBalanceLine {
+    pub account: LedgerAccount,
+    pub debit: Money,
+    pub credit: Money,
+}
+
+/// Closing balance of every ledger account, split into debit and credit columns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrialBalance {
+    pub lines: Vec<TrialBalanceLine>,
+}
+
+impl TrialBalance {
+    /// Total debits and credits per currency.
+    pub fn totals(&self) -> BTreeMap<Currency, (Money, Money)> {
+        let mut totals: BTreeMap<Currency, (i64, i64)> = BTreeMap::new();
+        for line in &self.lines {
+            let total = totals.entry(line.debit.currency()).or_insert((0, 0));
+            total.0 += line.debit.minor_units();
+            total.1 += line.credit.minor_units();
+        }
+        totals
+            .into_iter()
+            .map(|(currency, (debit, credit))| {
+                (currency, (Money::from_minor(debit, currency), Money::from_minor(credit, currency)))
+            })
+            .collect()
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.totals().values().all(|(debit, credit)| debit == credit)
+    }
+}
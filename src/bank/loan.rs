@@ -0,0 +1,300 @@
+use chrono::{Months, NaiveDate};
+use serde::{Serialize, Deserialize};
+use super::interest::Rate;
+use super::{AccountId, BankError, Money, MoneyError, RoundingMode};
+
+/// Fixed-point scale for the growth factor (1 + r)^n in annuity payments.
+const GROWTH_SCALE: i128 = 1_000_000_000_000_000_000;
+
+/// How often a loan is repaid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RepaymentFrequency {
+    #[default]
+    Monthly,
+    Quarterly,
+    SemiAnnually,
+    Annually,
+}
+
+impl RepaymentFrequency {
+    pub fn periods_per_year(&self) -> u32 {
+        12 / self.months()
+    }
+
+    pub fn months(&self) -> u32 {
+        match self {
+            RepaymentFrequency::Monthly => 1,
+            RepaymentFrequency::Quarterly => 3,
+            RepaymentFrequency::SemiAnnually => 6,
+            RepaymentFrequency::Annually => 12,
+        }
+    }
+}
+
+/// How each repayment is divided between interest and principal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Amortization {
+    /// French or annuity: every repayment is the same, with the interest
+    /// share shrinking as the principal is paid down.
+    #[default]
+    Annuity,
+    /// The same principal every time plus interest on what is left, so
+    /// repayments fall over the term.
+    EqualPrincipal,
+}
+
+/// What an extra payment changes about the repayments still to come.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Recalculation {
+    /// Keep the number of repayments and lower each one.
+    #[default]
+    ReducePayment,
+    /// Keep the repayment amount and finish sooner.
+    ShortenTerm,
+}
+
+/// What a customer borrowed and on what terms.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoanTerms {
+    pub principal: Money,
+    pub annual_rate: Rate,
+    /// The number of repayments.
+    pub term: u32,
+    pub frequency: RepaymentFrequency,
+    pub amortization: Amortization,
+}
+
+impl LoanTerms {
+    pub fn new(principal: Money, annual_rate: Rate, term: u32, frequency: RepaymentFrequency) -> Self {
+        Self { principal, annual_rate, term, frequency, amortization: Amortization::default() }
+    }
+
+    pub fn with_amortization(mut self, amortization: Amortization) -> Self {
+        self.amortization = amortization;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), BankError> {
+        if !self.principal.is_positive() {
+            return Err(BankError::InvalidAmount(self.principal));
+        }
+        if self.annual_rate.ppm() < 0 {
+            return Err(BankError::InvalidField { field: "loan rate", reason: "must not be negative".to_string() });
+        }
+        if self.term == 0 {
+            return Err(BankError::InvalidField { field: "loan term", reason: "must be at least one repayment".to_string() });
+        }
+        Ok(())
+    }
+
+    /// The full repayment schedule for a loan disbursed on `disbursed_on`,
+    /// with the first repayment one period later.
+    pub fn schedule(&self, disbursed_on: NaiveDate) -> Result<Vec<Installment>, BankError> {
+        self.validate()?;
+        let level = self.level(self.principal, self.term)?;
+        self.amortize(self.principal, level, disbursed_on, 1, Some(self.term))
+    }
+
+    /// Interest for one period on `outstanding`.
+    pub fn period_interest(&self, outstanding: Money) -> Result<Money, BankError> {
+        let per_year = 1_000_000 * i64::from(self.frequency.periods_per_year());
+        Ok(outstanding.mul_ratio(self.annual_rate.ppm(), per_year, RoundingMode::HalfEven)?)
+    }
+
+    /// What stays the same from one repayment to the next when `principal`
+    /// is repaid over `periods`: the whole repayment for annuities, the
+    /// principal share for equal-principal loans.
+    fn level(&self, principal: Money, periods: u32) -> Result<Money, BankError> {
+        let level = match self.amortization {
+            Amortization::EqualPrincipal => principal.mul_ratio(1, i64::from(periods), RoundingMode::Up)?,
+            Amortization::Annuity if self.annual_rate.ppm() == 0 => {
+                principal.mul_ratio(1, i64::from(periods), RoundingMode::Up)?
+            }
+            Amortization::Annuity => {
+                // P·r / (1 − (1 + r)^−n), or P·r·g / (g − 1) with g = (1 + r)^n,
+                // where r is the rate in ppm over a million periods a year.
+                // g is kept to 18 decimal places and the payment rounded once.
+                let overflow = || BankError::Money(MoneyError::Overflow);
+                let per_year = 1_000_000 * i128::from(self.frequency.periods_per_year());
+                let ppm = i128::from(self.annual_rate.ppm());
+                let mut growth = GROWTH_SCALE;
+                for _ in 0..periods {
+                    let grown = growth.checked_mul(per_year + ppm).ok_or_else(overflow)?;
+                    growth = RoundingMode::HalfEven.divide(grown, per_year);
+                }
+                let numerator = i128::from(principal.minor_units())
+                    .checked_mul(ppm)
+                    .and_then(|n| n.checked_mul(growth))
+                    .ok_or_else(overflow)?;
+                let denominator = per_year.checked_mul(growth - GROWTH_SCALE).ok_or_else(overflow)?;
+                let payment = RoundingMode::HalfEven.divide(numerator, denominator);
+                Money::from_minor(i64::try_from(payment).map_err(|_| overflow())?, principal.currency())
+            }
+        };
+        Ok(level)
+    }
+
+    /// Repays `principal` from installment `first_number` on, keeping
+    /// `level` fixed, until it is gone or `periods` have passed. The last
+    /// repayment takes whatever rounding left over.
+    fn amortize(&self, principal: Money, level: Money, disbursed_on: NaiveDate, first_number: u32, periods: Option<u32>) -> Result<Vec<Installment>, BankError> {
+        let mut outstanding = principal;
+        let mut installments = vec![];
+        let mut number = first_number;
+        while outstanding.is_positive() {
+            let interest = self.period_interest(outstanding)?;
+            let last = periods.is_some_and(|periods| installments.len() as u32 + 1 == periods);
+            let mut principal_part = match self.amortization {
+                Amortization::Annuity => level.checked_sub(interest)?,
+                Amortization::EqualPrincipal => level,
+            };
+            if !principal_part.is_positive() {
+                return Err(BankError::InvalidField {
+                    field: "loan",
+                    reason: format!("a repayment of {} does not cover the interest of {}", level, interest),
+                });
+            }
+            if last || principal_part.checked_cmp(&outstanding)?.is_ge() {
+                principal_part = outstanding;
+            }
+            outstanding = outstanding.checked_sub(principal_part)?;
+            installments.push(Installment {
+                number,
+                due: self.due_date(disbursed_on, number)?,
+                payment: interest.checked_add(principal_part)?,
+                interest,
+                principal: principal_part,
+                remaining: outstanding,
+            });
+            number += 1;
+        }
+        Ok(installments)
+    }
+
+    /// The due date of installment `number` of a loan disbursed on
+    /// `disbursed_on`. Counting from the disbursement keeps month ends from
+    /// drifting.
+    fn due_date(&self, disbursed_on: NaiveDate, number: u32) -> Result<NaiveDate, BankError> {
+        disbursed_on
+            .checked_add_months(Months::new(self.frequency.months() * number))
+            .ok_or_else(|| BankError::InvalidField { field: "loan term", reason: "runs past the last supported date".to_string() })
+    }
+}
+
+/// One repayment in a loan's schedule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Installment {
+    /// Counts from 1 over the life of the loan.
+    pub number: u32,
+    pub due: NaiveDate,
+    /// Interest plus principal.
+    pub payment: Money,
+    pub interest: Money,
+    pub principal: Money,
+    /// Principal still owed once this repayment is made.
+    pub remaining: Money,
+}
+
+/// Money paid back on a loan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Repayment {
+    pub on: NaiveDate,
+    /// The installment paid, or `None` for an extra payment.
+    pub installment: Option<u32>,
+    pub interest: Money,
+    pub principal: Money,
+}
+
+/// The state of a loan, kept on its loan account. The account's balance is
+/// minus the principal still owed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Loan {
+    pub terms: LoanTerms,
+    /// Where the loan was paid out to and where repayments come from.
+    pub linked_account: AccountId,
+    pub disbursed_on: NaiveDate,
+    pub outstanding_principal: Money,
+    /// The repayments still to make, next first.
+    pub schedule: Vec<Installment>,
+    pub repayments: Vec<Repayment>,
+}
+
+impl Loan {
+    pub(crate) fn new(terms: LoanTerms, linked_account: AccountId, disbursed_on: NaiveDate) -> Result<Self, BankError> {
+        Ok(Self {
+            schedule: terms.schedule(disbursed_on)?,
+            outstanding_principal: terms.principal,
+            terms,
+            linked_account,
+            disbursed_on,
+            repayments: vec![],
+        })
+    }
+
+    pub fn next_installment(&self) -> Option<&Installment> {
+        self.schedule.first()
+    }
+
+    pub fn is_repaid(&self) -> bool {
+        self.outstanding_principal.is_zero()
+    }
+
+    /// The principal still owed at the end of `date`.
+    pub fn outstanding_on(&self, date: NaiveDate) -> Result<Money, BankError> {
+        if date < self.disbursed_on {
+            return Ok(Money::zero(self.terms.principal.currency()));
+        }
+        self.repayments
+            .iter()
+            .filter(|repayment| repayment.on <= date)
+            .try_fold(self.terms.principal, |outstanding, repayment| Ok(outstanding.checked_sub(repayment.principal)?))
+    }
+
+    /// Interest paid so far.
+    pub fn interest_paid(&self) -> Result<Money, BankError> {
+        self.repayments
+            .iter()
+            .try_fold(Money::zero(self.terms.principal.currency()), |total, repayment| Ok(total.checked_add(repayment.interest)?))
+    }
+
+    /// Records the next installment as paid on `on`.
+    pub(crate) fn repay_installment(&mut self, on: NaiveDate) -> Installment {
+        let installment = self.schedule.remove(0);
+        self.outstanding_principal = installment.remaining;
+        self.repayments.push(Repayment {
+            on,
+            installment: Some(installment.number),
+            interest: installment.interest,
+            principal: installment.principal,
+        });
+        installment
+    }
+
+    /// Records `amount` of principal paid early on `on` and redraws the
+    /// schedule that is left.
+    pub(crate) fn prepay(&mut self, amount: Money, on: NaiveDate, recalculation: Recalculation) -> Result<(), BankError> {
+        let outstanding = self.outstanding_principal.checked_sub(amount)?;
+        let next_number = self.next_installment().map_or(1, |next| next.number);
+        let remaining_periods = self.schedule.len() as u32;
+        let schedule = if outstanding.is_zero() {
+            vec![]
+        } else {
+            let level = match (recalculation, self.terms.amortization) {
+                (Recalculation::ReducePayment, _) => self.terms.level(outstanding, remaining_periods)?,
+                (Recalculation::ShortenTerm, Amortization::Annuity) => self.schedule[0].payment,
+                (Recalculation::ShortenTerm, Amortization::EqualPrincipal) => self.schedule[0].principal,
+            };
+            let periods = Some(remaining_periods).filter(|_| recalculation == Recalculation::ReducePayment);
+            self.terms.amortize(outstanding, level, self.disbursed_on, next_number, periods)?
+        };
+        self.schedule = schedule;
+        self.outstanding_principal = outstanding;
+        self.repayments.push(Repayment {
+            on,
+            installment: None,
+            interest: Money::zero(amount.currency()),
+            principal: amount,
+        });
+        Ok(())
+    }
+}
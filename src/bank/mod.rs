@@ -1,77 +1,2459 @@
 mod account;
+mod account_number;
+pub mod card;
+pub mod certificate;
+mod clock;
 mod customer;
+mod error;
+pub mod fees;
+mod hold;
+mod ids;
+pub mod interest;
+pub mod journal;
+pub mod ledger;
+pub mod loan;
+mod money;
+mod party;
+mod product;
+pub mod schedule;
+pub mod settings;
+pub mod statement;
+mod status;
+pub mod storage;
 mod transaction;
 
 pub use account::Account;
+pub use account_number::{AccountNumber, AccountNumberFormat, CheckDigit};
+pub use clock::Clock;
 pub use customer::Customer;
-pub use transaction::{Transaction, TransactionType};
+pub use error::BankError;
+pub use hold::{Hold, HoldKind};
+pub use ids::{AccountId, CustomerId, HoldId, IdSequences, ScheduleId, TransactionId};
+pub use money::{Currency, Money, MoneyError, RoundingMode};
+pub use party::{AccountParty, PartyRole, SigningRule};
+pub use product::{AccountProduct, ProductRules};
+pub use status::{AccountStatus, LifecyclePolicy};
+pub use storage::{JsonFileStorage, MemoryStorage, Storage};
+pub use transaction::{Channel, Counterparty, Transaction, TransactionDetails, TransactionType};
 
-#[derive(Default)]
-pub struct Bank {
-    pub accounts: Vec<Account>,
-    customers: Vec<Customer>,
-    next_id: u64,
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveTime, Utc};
+use serde::{Serialize, Deserialize};
+use card::{CardTerms, CreditCard};
+use certificate::{Certificate, CertificatePhase, CertificateTerms, MaturityAction};
+use fees::{FeeReason, FeeSchedule};
+use interest::{InterestPolicy, RateTable, MICROS_PER_MINOR};
+use journal::{Event, EventJournal, JournalRecord, OverdraftCover};
+use loan::{Installment, Loan, LoanTerms, Recalculation};
+use statement::AccountStatement;
+use schedule::{Execution, InsufficientFunds, Outcome, ScheduledTransfer, StandingOrder};
+use ledger::{Ledger, LedgerAccount, Posting, TrialBalance};
+use storage::{storage_error, write_json_atomic};
+
+/// The bank's operations, on top of a pluggable [`Storage`] backend. Plain
+/// `Bank` keeps everything in memory.
+///
+/// Whatever takes money out of an account checks who signs for it. The
+/// `_signed` and `_with_details` forms name the signers; the plain forms
+/// name none and act for the primary holder alone, which only accounts any
+/// one holder may sign for allow.
+#[derive(Default, Serialize)]
+#[serde(transparent)]
+pub struct Bank<S = MemoryStorage> {
+    storage: S,
+    #[serde(skip)]
+    journal: Option<EventJournal>,
+    /// Account IDs per customer, for every role, oldest first. Rebuilt from
+    /// storage on open.
+    #[serde(skip)]
+    customer_accounts: HashMap<CustomerId, Vec<AccountId>>,
+    #[serde(skip)]
+    account_numbers: HashMap<AccountNumber, AccountId>,
+    #[serde(skip)]
+    clock: Clock,
+    #[serde(skip)]
+    channel: Channel,
 }
 
 impl Bank {
     pub fn new() -> Self {
         Self {
-            accounts: vec![],
-            customers: vec![],
-            next_id: 1,
+            storage: MemoryStorage::new(),
+            journal: None,
+            customer_accounts: HashMap::new(),
+            account_numbers: HashMap::new(),
+            clock: Clock::System,
+            channel: Channel::default(),
+        }
+    }
+
+    /// Rebuilds a bank from scratch by replaying the journal at `path`.
+    pub fn replay<P: AsRef<Path>>(path: P) -> Result<Self, BankError> {
+        Self::replay_until(path, u64::MAX)
+    }
+
+    /// Rebuilds the bank as it was right after event `sequence`.
+    pub fn replay_until<P: AsRef<Path>>(path: P, sequence: u64) -> Result<Self, BankError> {
+        let mut bank = Self::new();
+        for record in EventJournal::read(path)? {
+            if record.sequence > sequence {
+                break;
+            }
+            bank.apply(&record)?;
+        }
+        Ok(bank)
+    }
+
+    /// Reads a bank previously written by [`Bank::save`].
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BankError> {
+        let json = fs::read(path).map_err(storage_error)?;
+        let storage: MemoryStorage = serde_json::from_slice(&json).map_err(storage_error)?;
+        Self::with_storage(storage)
+    }
+}
+
+impl<S: Storage + Serialize> Bank<S> {
+    /// Writes the bank to `path` as JSON. The data goes to a temporary file
+    /// that is synced and then renamed over `path`, so a crash mid-save never
+    /// leaves a truncated file behind.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), BankError> {
+        write_json_atomic(path.as_ref(), &self.storage)
+    }
+}
+
+/// Deserializes the storage and opens it with [`Bank::with_storage`], so the
+/// indexes and sequences are rebuilt as they are by [`Bank::load`].
+impl<'de, S: Storage + Deserialize<'de>> Deserialize<'de> for Bank<S> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let storage = S::deserialize(deserializer)?;
+        Self::with_storage(storage).map_err(serde::de::Error::custom)
+    }
+}
+
+impl<S: Storage> Bank<S> {
+    /// Runs the bank on top of existing storage.
+    pub fn with_storage(storage: S) -> Result<Self, BankError> {
+        let mut bank = Self {
+            storage,
+            journal: None,
+            customer_accounts: HashMap::new(),
+            account_numbers: HashMap::new(),
+            clock: Clock::System,
+            channel: Channel::default(),
+        };
+        bank.rebuild_indexes();
+        bank.catch_up_sequences()?;
+        bank.post_opening_balances()?;
+        Ok(bank)
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Writes out whatever changes the storage is still holding back.
+    pub fn sync(&mut self) -> Result<(), BankError> {
+        self.storage.sync()
+    }
+
+    fn rebuild_indexes(&mut self) {
+        self.customer_accounts.clear();
+        self.account_numbers.clear();
+        let parties: Vec<(CustomerId, AccountId)> = self.storage.accounts()
+            .iter()
+            .flat_map(|account| account.all_parties().into_iter().map(|p| (p.customer_id, account.id)))
+            .collect();
+        for (customer_id, account_id) in parties {
+            self.index_party(customer_id, account_id);
+        }
+        for account in self.storage.accounts() {
+            if !account.number.is_empty() {
+                self.account_numbers.insert(account.number.clone(), account.id);
+            }
+        }
+    }
+
+    /// Lists `account_id` under `customer_id`, keeping each customer's
+    /// accounts in ID order.
+    fn index_party(&mut self, customer_id: CustomerId, account_id: AccountId) {
+        let ids = self.customer_accounts.entry(customer_id).or_default();
+        if let Err(position) = ids.binary_search(&account_id) {
+            ids.insert(position, account_id);
+        }
+    }
+
+    /// Moves every ID sequence past the IDs already in storage and numbers
+    /// transactions that have no ID, as in files saved before customers,
+    /// accounts and transactions were counted separately.
+    fn catch_up_sequences(&mut self) -> Result<(), BankError> {
+        let max_customer = self.storage.customers().iter().map(|c| c.id.0).max().unwrap_or(0);
+        let max_account = self.storage.accounts().iter().map(|a| a.id.0).max().unwrap_or(0);
+        let max_transaction = self.storage.accounts()
+            .iter()
+            .flat_map(|a| a.history.iter().map(|tx| tx.id.0))
+            .max()
+            .unwrap_or(0);
+        let max_hold = self.storage.accounts()
+            .iter()
+            .flat_map(|a| a.holds.iter().map(|hold| hold.id.0))
+            .max()
+            .unwrap_or(0);
+        let max_schedule = self.storage.scheduled_transfers().iter().map(|s| s.id.0).max().unwrap_or(0);
+        let sequences = self.storage.sequences_mut();
+        sequences.next_customer_id = sequences.next_customer_id.max(max_customer + 1);
+        sequences.next_account_id = sequences.next_account_id.max(max_account + 1);
+        sequences.next_transaction_id = sequences.next_transaction_id.max(max_transaction + 1);
+        sequences.next_hold_id = sequences.next_hold_id.max(max_hold + 1);
+        sequences.next_schedule_id = sequences.next_schedule_id.max(max_schedule + 1);
+
+        let unnumbered: Vec<AccountId> = self.storage.accounts()
+            .iter()
+            .filter(|a| a.history.iter().any(|tx| tx.id == TransactionId::default()))
+            .map(|a| a.id)
+            .collect();
+        for account_id in &unnumbered {
+            let mut history = std::mem::take(&mut self.storage.account_mut(*account_id).expect("listed above").history);
+            for tx in history.iter_mut().filter(|tx| tx.id == TransactionId::default()) {
+                tx.id = self.storage.sequences_mut().allocate_transaction_id();
+            }
+            self.storage.account_mut(*account_id).expect("listed above").history = history;
+        }
+
+        let without_number: Vec<AccountId> = self.storage.accounts()
+            .iter()
+            .filter(|a| a.number.is_empty())
+            .map(|a| a.id)
+            .collect();
+        for account_id in &without_number {
+            let number = self.storage.settings().account_number_format.number_for(*account_id)?;
+            self.account_numbers.insert(number.clone(), *account_id);
+            self.storage.account_mut(*account_id).expect("listed above").number = number;
+        }
+
+        if !unnumbered.is_empty() || !without_number.is_empty() {
+            self.storage.sync()?;
+        }
+        Ok(())
+    }
+
+    /// Brings the ledger in line with account balances that have no journal
+    /// entries behind them, as in files saved before the ledger existed. The
+    /// difference is posted against the suspense account.
+    fn post_opening_balances(&mut self) -> Result<(), BankError> {
+        let mut postings = vec![];
+        for account in self.storage.accounts() {
+            let ledger_account = LedgerAccount::Customer(account.id);
+            let booked = self.storage.ledger().balance(ledger_account, account.currency());
+            let difference = account.balance.checked_sub(booked)?;
+            if difference.is_positive() {
+                postings.push(Posting::debit(LedgerAccount::Suspense, difference));
+                postings.push(Posting::credit(ledger_account, difference));
+            } else if difference.is_negative() {
+                let shortfall = difference.checked_abs()?;
+                postings.push(Posting::debit(ledger_account, shortfall));
+                postings.push(Posting::credit(LedgerAccount::Suspense, shortfall));
+            }
+        }
+        if !postings.is_empty() {
+            let now = self.clock.now();
+            self.storage.ledger_mut().post(now, "Opening balances".to_string(), postings)?;
+            self.storage.sync()?;
+        }
+        Ok(())
+    }
+
+    pub fn create_customer(&mut self, name: String, address: String, phone: String, email: String) -> Result<CustomerId, BankError> {
+        let id = CustomerId(self.storage.sequences().next_customer_id);
+        self.execute(Event::CustomerCreated { id, name, address, phone, email })?;
+        Ok(id)
+    }
+
+    /// Changes the given customer fields. Nothing is changed if any value is
+    /// invalid.
+    pub fn update_customer(&mut self, id: CustomerId, name: Option<String>, address: Option<String>, phone: Option<String>, email: Option<String>) -> Result<(), BankError> {
+        self.execute(Event::CustomerUpdated { id, name, address, phone, email })
+    }
+
+    /// Opens a checking, savings or money market account, numbered with the
+    /// bank's [`AccountNumberFormat`]. Every later operation on it follows
+    /// the product's [`ProductRules`]. Loans, certificates of deposit and
+    /// credit cards need terms, and are opened with [`Bank::open_loan`],
+    /// [`Bank::open_certificate`] and [`Bank::open_credit_card`].
+    pub fn create_account(&mut self, customer_id: CustomerId, product: AccountProduct) -> Result<AccountId, BankError> {
+        let opener = match product {
+            AccountProduct::Loan => Some("open_loan"),
+            AccountProduct::CertificateOfDeposit => Some("open_certificate"),
+            AccountProduct::CreditCard => Some("open_credit_card"),
+            AccountProduct::Checking | AccountProduct::Savings | AccountProduct::MoneyMarket => None,
+        };
+        if let Some(opener) = opener {
+            return Err(BankError::InvalidField {
+                field: "account type",
+                reason: format!("{} accounts are opened with {}", product, opener),
+            });
+        }
+        self.open_account(customer_id, product)
+    }
+
+    /// The account [`Bank::open_account`] would open next for `customer_id`,
+    /// so that what has to follow the opening can be checked before
+    /// anything is journaled.
+    fn unopened_account(&self, customer_id: CustomerId, product: AccountProduct) -> Result<Account, BankError> {
+        if self.get_customer(customer_id).is_none() {
+            return Err(BankError::CustomerNotFound(customer_id));
+        }
+        let id = AccountId(self.storage.sequences().next_account_id);
+        Ok(Account::new(id, customer_id, format!("{} Account", product), product).with_opened_at(self.clock.now()))
+    }
+
+    /// Opens an account of any product, leaving its terms to the caller.
+    fn open_account(&mut self, customer_id: CustomerId, product: AccountProduct) -> Result<AccountId, BankError> {
+        let id = AccountId(self.storage.sequences().next_account_id);
+        let number = Some(self.storage.settings().account_number_format.number_for(id)?);
+        self.execute(Event::AccountOpened { id, number, customer_id, product })?;
+        Ok(id)
+    }
+
+    /// Pays cash into an account, posting Dr Cash / Cr customer.
+    pub fn deposit(&mut self, account_id: AccountId, amount: Money) -> Result<(), BankError> {
+        self.deposit_with_details(account_id, amount, TransactionDetails::default())
+    }
+
+    /// Like [`Bank::deposit`], with a memo and external reference.
+    pub fn deposit_with_details(&mut self, account_id: AccountId, amount: Money, details: TransactionDetails) -> Result<(), BankError> {
+        let TransactionDetails { memo, reference, .. } = details;
+        self.execute(Event::Deposit { account_id, amount, memo, reference })
+    }
+
+    /// Pays cash out of an account, posting Dr customer / Cr Cash. A
+    /// shortfall is first covered from the account's overdraft protection,
+    /// then from its arranged overdraft, which costs the product's fee.
+    pub fn withdraw(&mut self, account_id: AccountId, amount: Money) -> Result<(), BankError> {
+        self.withdraw_with_details(account_id, amount, TransactionDetails::default())
+    }
+
+    /// Like [`Bank::withdraw`], with a memo, external reference and the
+    /// parties signing for it.
+    pub fn withdraw_with_details(&mut self, account_id: AccountId, amount: Money, details: TransactionDetails) -> Result<(), BankError> {
+        let TransactionDetails { memo, reference, signers } = details;
+        self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?.check_signatures(&signers)?;
+        let cover = self.shortfall_cover(account_id, None, amount, &signers)?;
+        let overdraft_fee = self.overdraft_fee_after(account_id, amount, cover)?;
+        self.execute(Event::Withdrawal { account_id, amount, memo, reference, cover, overdraft_fee })
+    }
+
+    /// Moves money between two accounts by ID, covering a shortfall the same
+    /// way as [`Bank::withdraw`]. On error neither account is changed.
+    pub fn transfer(&mut self, from_id: AccountId, to_id: AccountId, amount: Money, memo: Option<String>) -> Result<(), BankError> {
+        self.transfer_with_details(from_id, to_id, amount, TransactionDetails { memo, ..TransactionDetails::default() })
+    }
+
+    /// Like [`Bank::transfer`], with an external reference and the
+    /// parties signing for it as well as a memo.
+    pub fn transfer_with_details(&mut self, from_id: AccountId, to_id: AccountId, amount: Money, details: TransactionDetails) -> Result<(), BankError> {
+        let TransactionDetails { memo, reference, signers } = details;
+        self.get_account(from_id).ok_or(BankError::AccountNotFound(from_id))?.check_signatures(&signers)?;
+        let cover = if from_id == to_id { None } else { self.shortfall_cover(from_id, Some(to_id), amount, &signers)? };
+        let overdraft_fee = self.overdraft_fee_after(from_id, amount, cover)?;
+        self.execute(Event::Transfer { from_id, to_id, amount, memo, reference, cover, overdraft_fee })
+    }
+
+    /// Sets up a one-off or recurring transfer, made by
+    /// [`Bank::run_due_transfers`] once it falls due. The order's signers
+    /// must satisfy the paying account's signing rule now, and sign each
+    /// transfer it makes.
+    pub fn schedule_transfer(&mut self, order: StandingOrder) -> Result<ScheduleId, BankError> {
+        self.get_account(order.from_id).ok_or(BankError::AccountNotFound(order.from_id))?.check_signatures(&order.signers)?;
+        let id = ScheduleId(self.storage.sequences().next_schedule_id);
+        self.execute(Event::TransferScheduled { id, order })?;
+        Ok(id)
+    }
+
+    /// Stops a scheduled transfer before its next due date.
+    pub fn cancel_scheduled_transfer(&mut self, id: ScheduleId) -> Result<(), BankError> {
+        self.execute(Event::ScheduledTransferCancelled { id })
+    }
+
+    pub fn scheduled_transfer(&self, id: ScheduleId) -> Option<&ScheduledTransfer> {
+        self.storage.scheduled_transfer(id)
+    }
+
+    pub fn scheduled_transfers(&self) -> Vec<&ScheduledTransfer> {
+        self.storage.scheduled_transfers()
+    }
+
+    /// Makes every scheduled transfer due on or before `today`, catching up
+    /// on any missed since the last run, and returns what happened to each.
+    /// A transfer the payer can't cover is retried on later runs or skipped,
+    /// as its order says; any other failure skips it.
+    pub fn run_due_transfers(&mut self, today: NaiveDate) -> Result<Vec<Execution>, BankError> {
+        self.as_scheduler(|bank| {
+            let ids: Vec<ScheduleId> = bank.storage.scheduled_transfers().iter().map(|s| s.id).collect();
+            let mut executions = vec![];
+            for id in ids {
+                while let Some(schedule) = bank.storage.scheduled_transfer(id).filter(|s| s.is_due(today)) {
+                    let due = schedule.next_due.expect("due schedules have a date");
+                    let attempts = schedule.attempts;
+                    let order = schedule.order.clone();
+                    let details = TransactionDetails {
+                        memo: Some(order.memo.clone().unwrap_or_else(|| format!("Standing order {}", id))),
+                        reference: None,
+                        signers: order.signers.clone(),
+                    };
+                    let outcome = match bank.transfer_with_details(order.from_id, order.to_id, order.amount, details) {
+                        Ok(()) => Outcome::Executed,
+                        Err(err @ BankError::InsufficientFunds { .. }) => match order.on_insufficient_funds {
+                            InsufficientFunds::Retry { attempts: allowed } if attempts + 1 < allowed => {
+                                Outcome::Retrying { attempt: attempts + 1 }
+                            }
+                            _ => Outcome::Skipped { reason: err.to_string() },
+                        },
+                        Err(err) => Outcome::Skipped { reason: err.to_string() },
+                    };
+                    bank.execute(Event::ScheduledTransferRun { id, due, ran_on: today, outcome: outcome.clone() })?;
+                    executions.push(Execution { schedule_id: id, due, ran_on: today, outcome });
+                }
+            }
+            Ok(executions)
+        })
+    }
+
+    fn find_scheduled_transfer(&self, id: ScheduleId) -> Result<&ScheduledTransfer, BankError> {
+        self.storage.scheduled_transfer(id).ok_or_else(|| BankError::InvalidField {
+            field: "scheduled transfer",
+            reason: format!("no scheduled transfer {}", id),
+        })
+    }
+
+    /// Opens a loan account for `customer_id` and pays `terms.principal`
+    /// into `linked_id`, an account the customer holds alone or jointly,
+    /// which later repayments are taken from. Returns the loan account's ID.
+    pub fn open_loan(&mut self, customer_id: CustomerId, linked_id: AccountId, terms: LoanTerms) -> Result<AccountId, BankError> {
+        let unopened = self.unopened_account(customer_id, AccountProduct::Loan)?;
+        let (_, entry) = self.loan_disbursal(&unopened, linked_id, terms.clone(), self.clock.now())?;
+        self.storage.ledger().check([entry.postings.as_slice()])?;
+        let loan_id = self.open_account(customer_id, AccountProduct::Loan)?;
+        self.execute(Event::LoanDisbursed { loan_id, linked_id, terms })?;
+        Ok(loan_id)
+    }
+
+    /// Pays the loan's next installment from its linked account and returns
+    /// it, split into interest and principal.
+    pub fn repay_loan(&mut self, loan_id: AccountId) -> Result<Installment, BankError> {
+        self.repay_loan_signed(loan_id, &[])
+    }
+
+    /// Like [`Bank::repay_loan`], with the parties signing for the linked
+    /// account.
+    pub fn repay_loan_signed(&mut self, loan_id: AccountId, signers: &[CustomerId]) -> Result<Installment, BankError> {
+        self.check_signatures(self.find_loan(loan_id)?.linked_account, signers)?;
+        let loan = self.find_loan(loan_id)?;
+        let next = loan.next_installment().cloned().ok_or_else(|| BankError::InvalidField {
+            field: "loan",
+            reason: format!("loan {} is repaid", loan_id),
+        })?;
+        self.execute(Event::LoanRepaid {
+            loan_id,
+            installment: next.number,
+            interest: next.interest,
+            principal: next.principal,
+        })?;
+        Ok(next)
+    }
+
+    /// Pays `amount` of principal ahead of schedule from the loan's linked
+    /// account and redraws the repayments still to come.
+    pub fn prepay_loan(&mut self, loan_id: AccountId, amount: Money, recalculation: Recalculation) -> Result<(), BankError> {
+        self.prepay_loan_signed(loan_id, amount, recalculation, &[])
+    }
+
+    /// Like [`Bank::prepay_loan`], with the parties signing for the linked
+    /// account.
+    pub fn prepay_loan_signed(&mut self, loan_id: AccountId, amount: Money, recalculation: Recalculation, signers: &[CustomerId]) -> Result<(), BankError> {
+        self.check_signatures(self.find_loan(loan_id)?.linked_account, signers)?;
+        self.execute(Event::LoanPrepaid { loan_id, amount, recalculation })
+    }
+
+    pub fn loan(&self, loan_id: AccountId) -> Option<&Loan> {
+        self.get_account(loan_id).and_then(|account| account.loan.as_ref())
+    }
+
+    /// The principal still owed on a loan.
+    pub fn outstanding_principal(&self, loan_id: AccountId) -> Result<Money, BankError> {
+        Ok(self.find_loan(loan_id)?.outstanding_principal)
+    }
+
+    fn find_loan(&self, loan_id: AccountId) -> Result<&Loan, BankError> {
+        let account = self.get_account(loan_id).ok_or(BankError::AccountNotFound(loan_id))?;
+        account.loan.as_ref().ok_or_else(|| BankError::InvalidField {
+            field: "loan",
+            reason: format!("account {} is not a disbursed loan", loan_id),
+        })
+    }
+
+    /// Checks that `terms.principal` can be paid out of `account`, a loan
+    /// account not yet disbursed, into `linked_id` at `at`, and works out the
+    /// loan and its entry.
+    fn loan_disbursal(&self, account: &Account, linked_id: AccountId, terms: LoanTerms, at: DateTime<Utc>) -> Result<(Loan, Entry), BankError> {
+        let loan_id = account.id;
+        let invalid = |reason: String| BankError::InvalidField { field: "loan", reason };
+        if account.product != AccountProduct::Loan {
+            return Err(invalid(format!("account {} is not a loan account", loan_id)));
+        }
+        if account.loan.is_some() || !account.history.is_empty() {
+            return Err(invalid(format!("loan {} has already been disbursed", loan_id)));
+        }
+        if account.status != AccountStatus::Active {
+            return Err(BankError::AccountStatus { account_id: loan_id, status: account.status });
+        }
+        terms.validate()?;
+        if terms.principal.currency() != account.currency() {
+            return Err(invalid(format!("must be in {}", account.currency())));
+        }
+        let linked = self.get_account(linked_id).ok_or(BankError::AccountNotFound(linked_id))?;
+        if !linked.is_held_by(account.customer_id) {
+            return Err(BankError::InvalidField {
+                field: "linked account",
+                reason: format!("account {} belongs to another customer", linked_id),
+            });
+        }
+        linked.check_credit(terms.principal)?;
+        let amount = terms.principal;
+        let loan = Loan::new(terms, linked_id, at.date_naive())?;
+        let (debit, credit) = account::transfer_legs(loan_id, linked_id, amount, Some("Loan disbursement".to_string()), at);
+        let entry = Entry::new(
+            format!("Loan {} disbursed to account {}", loan_id, linked_id),
+            vec![
+                Posting::debit(LedgerAccount::Customer(loan_id), amount),
+                Posting::credit(LedgerAccount::Customer(linked_id), amount),
+            ],
+            vec![(loan_id, debit.as_internal()), (linked_id, credit.as_internal())],
+        );
+        Ok((loan, entry))
+    }
+
+    /// Opens a credit card account for `customer_id` that may owe up to
+    /// `credit_limit`. The limit can later be changed with
+    /// [`Bank::set_overdraft_limit`]. Purchases are debits and payments
+    /// credits, as on any other account.
+    pub fn open_credit_card(&mut self, customer_id: CustomerId, credit_limit: Money, terms: CardTerms) -> Result<AccountId, BankError> {
+        let unopened = self.unopened_account(customer_id, AccountProduct::CreditCard)?;
+        card_opening(&unopened, credit_limit, terms.clone(), self.clock.now())?;
+        let account_id = self.open_account(customer_id, AccountProduct::CreditCard)?;
+        self.execute(Event::CreditCardOpened { account_id, credit_limit, terms })?;
+        Ok(account_id)
+    }
+
+    /// Closes every card statement cycle ending on or before `through` and
+    /// reviews every due date passed along the way. A due date charges the
+    /// late fee if less than the minimum payment came in; a closing date
+    /// charges interest on the cycle's daily balances if the previous
+    /// statement wasn't paid in full. Running it again for the same date
+    /// does nothing.
+    pub fn close_card_cycles(&mut self, through: NaiveDate) -> Result<(), BankError> {
+        self.as_scheduler(|bank| {
+            let ids: Vec<AccountId> = bank.storage.accounts()
+                .iter()
+                .filter(|a| a.card.is_some() && a.status != AccountStatus::Closed)
+                .map(|a| a.id)
+                .collect();
+            for account_id in ids {
+                bank.close_account_card_cycles(account_id, through)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn close_account_card_cycles(&mut self, account_id: AccountId, through: NaiveDate) -> Result<(), BankError> {
+        loop {
+            let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+            let card = self.find_card(account_id)?;
+            let unreviewed = card.last_statement().filter(|s| s.paid_by_due.is_none() && s.due_date <= through);
+            if let Some(statement) = unreviewed {
+                let number = statement.number;
+                let paid = card::payments_between(account, statement.closing_date, statement.due_date)?;
+                let late = paid.checked_cmp(&statement.minimum_payment)?.is_lt();
+                let due_date = statement.due_date;
+                let late_fee = card.terms.late_fee.filter(|_| late);
+                let waived = self.get_customer(account.customer_id).is_some_and(|c| c.fee_waivers.contains(&FeeReason::LatePayment));
+                self.execute(Event::StatementDue { account_id, number, paid })?;
+                if let Some(amount) = late_fee.filter(|_| !waived) {
+                    self.execute(Event::FeeCharged { account_id, reason: FeeReason::LatePayment, amount, on: due_date })?;
+                }
+                continue;
+            }
+
+            let closing_date = card.next_closing_date();
+            if closing_date > through {
+                return Ok(());
+            }
+            if let Some(previous) = card.last_statement().filter(|s| s.paid_in_full() == Some(false)) {
+                let start = previous.closing_date.succ_opt().ok_or(BankError::Money(MoneyError::Overflow))?;
+                let amount = card.terms.interest_between(account, start, closing_date)?;
+                if amount.is_positive() {
+                    self.execute(Event::CardInterestCharged { account_id, amount, on: closing_date })?;
+                }
+            }
+            self.execute(Event::StatementClosed { account_id, closing_date })?;
+        }
+    }
+
+    pub fn credit_card(&self, account_id: AccountId) -> Option<&CreditCard> {
+        self.get_account(account_id).and_then(|account| account.card.as_ref())
+    }
+
+    fn find_card(&self, account_id: AccountId) -> Result<&CreditCard, BankError> {
+        let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+        account.card.as_ref().ok_or_else(|| BankError::InvalidField {
+            field: "credit card",
+            reason: format!("account {} is not a credit card", account_id),
+        })
+    }
+
+    /// Opens a certificate of deposit for `customer_id`, funded with
+    /// `amount` from `funding_id`, an account the customer holds alone or
+    /// jointly. The money is locked until maturity. Returns the
+    /// certificate's account ID.
+    pub fn open_certificate(&mut self, customer_id: CustomerId, funding_id: AccountId, amount: Money, terms: CertificateTerms) -> Result<AccountId, BankError> {
+        self.open_certificate_signed(customer_id, funding_id, amount, terms, &[])
+    }
+
+    /// Like [`Bank::open_certificate`], with the parties signing for the
+    /// funding account.
+    pub fn open_certificate_signed(
+        &mut self,
+        customer_id: CustomerId,
+        funding_id: AccountId,
+        amount: Money,
+        terms: CertificateTerms,
+        signers: &[CustomerId],
+    ) -> Result<AccountId, BankError> {
+        self.check_signatures(funding_id, signers)?;
+        let unopened = self.unopened_account(customer_id, AccountProduct::CertificateOfDeposit)?;
+        let (_, entry) = self.certificate_funding(&unopened, funding_id, amount, terms, self.clock.now())?;
+        self.storage.ledger().check([entry.postings.as_slice()])?;
+        let account_id = self.open_account(customer_id, AccountProduct::CertificateOfDeposit)?;
+        self.execute(Event::CertificateOpened { account_id, funding_id, amount, terms })?;
+        Ok(account_id)
+    }
+
+    /// Checks that `account`, a certificate of deposit not yet funded, can
+    /// take `amount` from `funding_id` at `at` on `terms`, and works out the
+    /// certificate and its entry.
+    fn certificate_funding(
+        &self,
+        account: &Account,
+        funding_id: AccountId,
+        amount: Money,
+        terms: CertificateTerms,
+        at: DateTime<Utc>,
+    ) -> Result<(Certificate, Entry), BankError> {
+        let account_id = account.id;
+        let invalid = |reason: String| BankError::InvalidField { field: "certificate", reason };
+        if account.product != AccountProduct::CertificateOfDeposit {
+            return Err(invalid(format!("account {} is not a certificate of deposit account", account_id)));
+        }
+        if account.certificate.is_some() || !account.history.is_empty() {
+            return Err(invalid(format!("account {} has already been funded", account_id)));
+        }
+        terms.validate()?;
+        if funding_id == account_id {
+            return Err(BankError::SameAccount(account_id));
+        }
+        let funding = self.get_account(funding_id).ok_or(BankError::AccountNotFound(funding_id))?;
+        if !funding.is_held_by(account.customer_id) {
+            return Err(BankError::InvalidField {
+                field: "funding account",
+                reason: format!("account {} belongs to another customer", funding_id),
+            });
+        }
+        funding.check_debit(amount, at)?;
+        if let MaturityAction::PayOut { to } = terms.maturity_action {
+            if to == account_id {
+                return Err(BankError::SameAccount(account_id));
+            }
+            let payee = self.get_account(to).ok_or(BankError::AccountNotFound(to))?;
+            if !payee.is_held_by(account.customer_id) {
+                return Err(invalid(format!("account {} belongs to another customer", to)));
+            }
+        }
+        account.check_credit(amount)?;
+        let certificate = Certificate::new(terms, at.date_naive())?;
+        let (debit, credit) = account::transfer_legs(funding_id, account_id, amount, Some("Certificate of deposit".to_string()), at);
+        let entry = Entry::new(
+            format!("Transfer from account {} to account {}", funding_id, account_id),
+            vec![
+                Posting::debit(LedgerAccount::Customer(funding_id), amount),
+                Posting::credit(LedgerAccount::Customer(account_id), amount),
+            ],
+            vec![(funding_id, debit.as_internal()), (account_id, credit.as_internal())],
+        );
+        Ok((certificate, entry))
+    }
+
+    /// Matures every certificate whose term ends on or before `through`,
+    /// paying in the term's interest and then renewing it, paying it out or
+    /// starting its grace period, and renews whatever is left when a grace
+    /// period runs out. A payout the other account can't take leaves the
+    /// money on the matured certificate. Running it again for the same date
+    /// does nothing.
+    pub fn mature_certificates(&mut self, through: NaiveDate) -> Result<(), BankError> {
+        self.as_scheduler(|bank| {
+            let ids: Vec<AccountId> = bank.storage.accounts()
+                .iter()
+                .filter(|a| a.certificate.is_some() && a.status != AccountStatus::Closed)
+                .map(|a| a.id)
+                .collect();
+            for account_id in ids {
+                loop {
+                    let account = bank.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                    let certificate = bank.find_certificate(account_id)?;
+                    match certificate.phase {
+                        CertificatePhase::Locked if certificate.maturity <= through => {
+                            let interest = certificate.term_interest(account)?;
+                            let action = certificate.terms.maturity_action;
+                            bank.execute(Event::CertificateMatured { account_id, interest })?;
+                            if let MaturityAction::PayOut { to } = action {
+                                bank.pay_out_certificate(account_id, to)?;
+                            }
+                        }
+                        CertificatePhase::Grace { until } if until <= through => {
+                            bank.execute(Event::CertificateGraceEnded { account_id })?;
+                        }
+                        _ => break,
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn pay_out_certificate(&mut self, account_id: AccountId, to_id: AccountId) -> Result<(), BankError> {
+        let amount = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?.balance;
+        let payable = self.get_account(to_id).is_some_and(|to| to.check_credit(amount).is_ok());
+        if !payable {
+            return Ok(());
+        }
+        self.execute(Event::CertificatePaidOut { account_id, to_id, amount })
+    }
+
+    /// The penalty for taking `amount` out of a certificate before it
+    /// matures.
+    pub fn early_withdrawal_penalty(&self, account_id: AccountId, amount: Money) -> Result<Money, BankError> {
+        self.find_certificate(account_id)?.terms.early_withdrawal_penalty(amount)
+    }
+
+    /// Takes `amount` out of a certificate before it matures. The penalty
+    /// of [`CertificateTerms::penalty_days`] days' interest on the amount
+    /// is kept back and the rest paid out. Returns the penalty.
+    pub fn withdraw_early(&mut self, account_id: AccountId, amount: Money) -> Result<Money, BankError> {
+        self.withdraw_early_signed(account_id, amount, &[])
+    }
+
+    /// Like [`Bank::withdraw_early`], with the parties signing for it.
+    pub fn withdraw_early_signed(&mut self, account_id: AccountId, amount: Money, signers: &[CustomerId]) -> Result<Money, BankError> {
+        self.check_signatures(account_id, signers)?;
+        let penalty = self.early_withdrawal_penalty(account_id, amount)?;
+        self.execute(Event::CertificateBroken { account_id, amount, penalty })?;
+        Ok(penalty)
+    }
+
+    pub fn certificate(&self, account_id: AccountId) -> Option<&Certificate> {
+        self.get_account(account_id).and_then(|account| account.certificate.as_ref())
+    }
+
+    fn find_certificate(&self, account_id: AccountId) -> Result<&Certificate, BankError> {
+        let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+        account.certificate.as_ref().ok_or_else(|| BankError::InvalidField {
+            field: "certificate",
+            reason: format!("account {} is not a certificate of deposit", account_id),
+        })
+    }
+
+    /// The statement of an account for the calendar month `month` of
+    /// `year`.
+    pub fn monthly_statement(&self, account_id: AccountId, year: i32, month: u32) -> Result<AccountStatement, BankError> {
+        let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+        AccountStatement::for_month(account, year, month)
+    }
+
+    /// Sets `amount` aside on an account until the hold is released,
+    /// captured or expires. Card authorizations need the money to be
+    /// available; cheque clearing and legal holds don't.
+    pub fn place_hold(&mut self, account_id: AccountId, kind: HoldKind, amount: Money, expires_at: Option<DateTime<Utc>>) -> Result<HoldId, BankError> {
+        self.place_hold_signed(account_id, kind, amount, expires_at, &[])
+    }
+
+    /// Like [`Bank::place_hold`], with the parties signing for it. Only card
+    /// authorizations are the customer's doing and need signatures; the bank
+    /// places the other kinds itself.
+    pub fn place_hold_signed(
+        &mut self,
+        account_id: AccountId,
+        kind: HoldKind,
+        amount: Money,
+        expires_at: Option<DateTime<Utc>>,
+        signers: &[CustomerId],
+    ) -> Result<HoldId, BankError> {
+        if kind == HoldKind::CardAuthorization {
+            self.check_signatures(account_id, signers)?;
+        }
+        let hold_id = HoldId(self.storage.sequences().next_hold_id);
+        self.execute(Event::HoldPlaced { account_id, hold_id, kind, amount, expires_at })?;
+        Ok(hold_id)
+    }
+
+    /// Lifts a hold without posting anything.
+    pub fn release_hold(&mut self, account_id: AccountId, hold_id: HoldId) -> Result<(), BankError> {
+        self.execute(Event::HoldReleased { account_id, hold_id })
+    }
+
+    /// Debits `amount`, at most what was held, and lifts the hold. Any
+    /// difference becomes available again.
+    pub fn capture_hold(&mut self, account_id: AccountId, hold_id: HoldId, amount: Money) -> Result<(), BankError> {
+        self.capture_hold_signed(account_id, hold_id, amount, &[])
+    }
+
+    /// Like [`Bank::capture_hold`], with the parties signing for it. As when
+    /// placing a hold, only card authorizations need signatures.
+    pub fn capture_hold_signed(&mut self, account_id: AccountId, hold_id: HoldId, amount: Money, signers: &[CustomerId]) -> Result<(), BankError> {
+        if self.find_hold(account_id, hold_id)?.kind == HoldKind::CardAuthorization {
+            self.check_signatures(account_id, signers)?;
+        }
+        self.execute(Event::HoldCaptured { account_id, hold_id, amount })
+    }
+
+    /// Lifts every hold whose expiry has passed, returning their IDs.
+    pub fn expire_holds(&mut self) -> Result<Vec<HoldId>, BankError> {
+        self.as_scheduler(|bank| {
+            let now = bank.clock.now();
+            let expired: Vec<(AccountId, HoldId)> = bank.storage.accounts()
+                .iter()
+                .flat_map(|a| a.holds.iter().filter(|hold| hold.is_expired(now)).map(|hold| (a.id, hold.id)))
+                .collect();
+            for (account_id, hold_id) in &expired {
+                bank.execute(Event::HoldExpired { account_id: *account_id, hold_id: *hold_id })?;
+            }
+            Ok(expired.into_iter().map(|(_, hold_id)| hold_id).collect())
+        })
+    }
+
+    fn find_hold(&self, account_id: AccountId, hold_id: HoldId) -> Result<&Hold, BankError> {
+        let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+        account.holds.iter().find(|hold| hold.id == hold_id).ok_or_else(|| BankError::InvalidField {
+            field: "hold",
+            reason: format!("account {} has no hold {}", account_id, hold_id),
+        })
+    }
+
+    /// Arranges an overdraft of up to `limit` on an account whose product
+    /// allows one. A zero limit removes it.
+    pub fn set_overdraft_limit(&mut self, account_id: AccountId, limit: Money) -> Result<(), BankError> {
+        self.execute(Event::OverdraftLimitSet { account_id, limit })
+    }
+
+    /// Has `savings_id`, a savings or money market account of the same
+    /// customer, cover shortfalls on `account_id`. `None` unlinks it.
+    pub fn set_overdraft_protection(&mut self, account_id: AccountId, savings_id: Option<AccountId>) -> Result<(), BankError> {
+        self.execute(Event::OverdraftProtectionSet { account_id, savings_id })
+    }
+
+    /// Sets the fee charged each time a debit leaves an account of
+    /// `product` below zero.
+    pub fn set_overdraft_fee(&mut self, product: AccountProduct, fee: Money) -> Result<(), BankError> {
+        self.execute(Event::OverdraftFeeSet { product, fee })
+    }
+
+    pub fn overdraft_fee(&self, product: AccountProduct) -> Option<Money> {
+        self.storage.settings().overdraft_fees.get(&product).copied()
+    }
+
+    /// What to move from `account_id`'s protecting savings account before
+    /// debiting `amount`: as much as the balance lacks, as far as that
+    /// account can pay and `signers` may take money out of it. With no
+    /// signers named the debited account's primary holder is acting, and
+    /// must be able to sign for the savings account too. Nothing is moved
+    /// unless the debit will then go through.
+    fn shortfall_cover(&self, account_id: AccountId, to_id: Option<AccountId>, amount: Money, signers: &[CustomerId]) -> Result<Option<OverdraftCover>, BankError> {
+        let at = self.clock.now();
+        let Some(account) = self.get_account(account_id) else {
+            return Ok(None);
+        };
+        let Some(savings_id) = account.overdraft_protection.filter(|id| Some(*id) != to_id) else {
+            return Ok(None);
+        };
+        let Some(savings) = self.get_account(savings_id) else {
+            return Ok(None);
+        };
+        if !amount.is_positive() || amount.currency() != account.currency() {
+            return Ok(None);
+        }
+        let unheld = account.balance.checked_sub(account.held_amount()?)?;
+        let shortfall = amount.checked_sub(unheld)?;
+        if !shortfall.is_positive() {
+            return Ok(None);
+        }
+        let spare = savings.available_balance()?;
+        let pull = if spare.checked_cmp(&shortfall)?.is_lt() { spare } else { shortfall };
+        let primary = [account.customer_id];
+        let signers = if signers.is_empty() { &primary[..] } else { signers };
+        if !pull.is_positive() || savings.check_signatures(signers).is_err() || savings.check_debit(pull, at).is_err() {
+            return Ok(None);
+        }
+        let cover = OverdraftCover { savings_id, amount: pull };
+        if check_covered_debit(account, amount, Some(cover), at).is_err() {
+            return Ok(None);
+        }
+        Ok(Some(cover))
+    }
+
+    fn check_signatures(&self, account_id: AccountId, signers: &[CustomerId]) -> Result<(), BankError> {
+        self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?.check_signatures(signers)
+    }
+
+    /// The product's overdraft fee, if debiting `amount` after `cover` would
+    /// leave the account below zero.
+    fn overdraft_fee_after(&self, account_id: AccountId, amount: Money, cover: Option<OverdraftCover>) -> Result<Option<Money>, BankError> {
+        let Some(account) = self.get_account(account_id) else {
+            return Ok(None);
+        };
+        match self.storage.settings().overdraft_fees.get(&account.product) {
+            Some(&fee) if fee.currency() == account.currency() && amount.currency() == account.currency() => {
+                let covered = match cover {
+                    Some(cover) => account.balance.checked_add(cover.amount)?,
+                    None => account.balance,
+                };
+                Ok(covered.checked_sub(amount)?.is_negative().then_some(fee))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The entry moving `amount` from the protecting `savings_id` into
+    /// `account_id`, checked against both accounts.
+    fn cover_entry(&self, account_id: AccountId, savings_id: AccountId, amount: Money, at: DateTime<Utc>) -> Result<Entry, BankError> {
+        if savings_id == account_id {
+            return Err(BankError::SameAccount(account_id));
+        }
+        let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+        let savings = self.get_account(savings_id).ok_or(BankError::AccountNotFound(savings_id))?;
+        if account.overdraft_protection != Some(savings_id) {
+            return Err(BankError::InvalidField {
+                field: "overdraft protection",
+                reason: format!("account {} does not protect account {}", savings_id, account_id),
+            });
+        }
+        savings.check_debit(amount, at)?;
+        account.check_credit(amount)?;
+
+        let (debit, credit) = account::transfer_legs(savings_id, account_id, amount, Some("Overdraft protection".to_string()), at);
+        Ok(Entry::new(
+            format!("Overdraft protection from account {} to account {}", savings_id, account_id),
+            vec![
+                Posting::debit(LedgerAccount::Customer(savings_id), amount),
+                Posting::credit(LedgerAccount::Customer(account_id), amount),
+            ],
+            vec![(savings_id, debit.as_internal()), (account_id, credit.as_internal())],
+        ))
+    }
+
+    /// The entry charging an overdraft fee of `amount` to `account_id`.
+    /// Fees may take the balance past the arranged limit.
+    fn overdraft_fee_entry(&self, account_id: AccountId, amount: Money, at: DateTime<Utc>) -> Result<Entry, BankError> {
+        let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+        if account.status == AccountStatus::Closed {
+            return Err(BankError::AccountStatus { account_id, status: account.status });
+        }
+        if !amount.is_positive() {
+            return Err(BankError::InvalidAmount(amount));
+        }
+        let tx = Transaction::new(TransactionType::OverdraftFee, amount).with_timestamp(at);
+        Ok(Entry::new(
+            format!("Overdraft fee on account {}", account_id),
+            vec![
+                Posting::debit(LedgerAccount::Customer(account_id), amount),
+                Posting::credit(LedgerAccount::FeeIncome, amount),
+            ],
+            vec![(account_id, tx)],
+        ))
+    }
+
+    /// Stamps an event with the next sequence number and the current time,
+    /// then applies it.
+    fn execute(&mut self, event: Event) -> Result<(), BankError> {
+        let record = JournalRecord {
+            sequence: self.last_sequence() + 1,
+            timestamp: self.clock.now(),
+            channel: Some(self.channel),
+            event,
+        };
+        self.apply(&record)
+    }
+
+    /// Validates a record and works out every posting it makes, writes it
+    /// to the journal (if any), then changes state. Nothing after the journal
+    /// write can fail, so the journal never holds an event that wasn't
+    /// applied. Replaying the same records in order rebuilds the same bank.
+    ///
+    /// With a journal attached, the event is committed once it is written
+    /// there, and `Ok` is returned even if flushing the storage afterwards
+    /// fails: the storage catches up on a later flush or sync. Without a
+    /// journal the storage is the only record, so it is synced straight
+    /// away and a failure is returned, though the change stays applied in
+    /// memory.
+    fn apply(&mut self, record: &JournalRecord) -> Result<(), BankError> {
+        let at = record.timestamp;
+        match record.event.clone() {
+            Event::CustomerCreated { id, name, address, phone, email } => {
+                customer::validate_fields(Some(&name), Some(&address), Some(&phone), Some(&email))?;
+                check_next_id("customer", id.0, self.storage.sequences().next_customer_id)?;
+                self.write_journal(record)?;
+                self.storage.sequences_mut().allocate_customer_id();
+                self.storage.insert_customer(Customer::new(id, name, address, phone, email)).expect("ID checked above");
+            }
+            Event::CustomerUpdated { id, name, address, phone, email } => {
+                customer::validate_fields(name.as_deref(), address.as_deref(), phone.as_deref(), email.as_deref())?;
+                let customer = self.storage.customer(id).ok_or(BankError::CustomerNotFound(id))?;
+                let mut updated = customer.clone();
+                updated.update(name, address, phone, email)?;
+                self.write_journal(record)?;
+                *self.storage.customer_mut(id).expect("customer checked above") = updated;
+            }
+            Event::AccountOpened { id, number, customer_id, product } => {
+                if self.get_customer(customer_id).is_none() {
+                    return Err(BankError::CustomerNotFound(customer_id));
+                }
+                check_next_id("account", id.0, self.storage.sequences().next_account_id)?;
+                let number = match number {
+                    Some(number) => number,
+                    None => self.storage.settings().account_number_format.number_for(id)?,
+                };
+                if self.account_numbers.contains_key(&number) {
+                    return Err(BankError::InvalidField {
+                        field: "account number",
+                        reason: format!("{} is already in use", number),
+                    });
+                }
+                self.write_journal(record)?;
+                self.storage.sequences_mut().allocate_account_id();
+                self.storage.insert_account(Account::new(
+                    id,
+                    customer_id,
+                    format!("{} Account", product),
+                    product,
+                ).with_number(number.clone()).with_opened_at(at)).expect("ID checked above");
+                self.index_party(customer_id, id);
+                self.account_numbers.insert(number, id);
+            }
+            Event::AccountNumberFormatSet { format } => {
+                self.write_journal(record)?;
+                self.storage.settings_mut().account_number_format = format;
+            }
+            Event::Deposit { account_id, amount, memo, reference } => {
+                self.get_account(account_id)
+                    .ok_or(BankError::AccountNotFound(account_id))?
+                    .check_credit(amount)?;
+                let tx = Transaction::new(TransactionType::Deposit, amount)
+                    .with_timestamp(at)
+                    .with_memo(memo)
+                    .with_reference(reference);
+                let entries = vec![Entry::new(
+                    format!("Deposit to account {}", account_id),
+                    vec![
+                        Posting::debit(LedgerAccount::Cash, amount),
+                        Posting::credit(LedgerAccount::Customer(account_id), amount),
+                    ],
+                    vec![(account_id, tx)],
+                )];
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.post(record, entries);
+            }
+            Event::Withdrawal { account_id, amount, memo, reference, cover, overdraft_fee } => {
+                let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                check_covered_debit(account, amount, cover, at)?;
+                let tx = Transaction::new(TransactionType::Withdrawal, amount)
+                    .with_timestamp(at)
+                    .with_memo(memo)
+                    .with_reference(reference);
+                let mut entries = match cover {
+                    Some(cover) => vec![self.cover_entry(account_id, cover.savings_id, cover.amount, at)?],
+                    None => vec![],
+                };
+                entries.push(Entry::new(
+                    format!("Withdrawal from account {}", account_id),
+                    vec![
+                        Posting::debit(LedgerAccount::Customer(account_id), amount),
+                        Posting::credit(LedgerAccount::Cash, amount),
+                    ],
+                    vec![(account_id, tx)],
+                ));
+                if let Some(fee) = overdraft_fee {
+                    entries.push(self.overdraft_fee_entry(account_id, fee, at)?);
+                }
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.post(record, entries);
+            }
+            Event::Transfer { from_id, to_id, amount, memo, reference, cover, overdraft_fee } => {
+                if from_id == to_id {
+                    return Err(BankError::SameAccount(from_id));
+                }
+                let from = self.get_account(from_id).ok_or(BankError::AccountNotFound(from_id))?;
+                let to = self.get_account(to_id).ok_or(BankError::AccountNotFound(to_id))?;
+                check_covered_debit(from, amount, cover, at)?;
+                to.check_credit(amount)?;
+
+                let (debit, credit) = account::transfer_legs(from_id, to_id, amount, memo, at);
+                let (debit, credit) = (debit.with_reference(reference.clone()), credit.with_reference(reference));
+                let mut entries = match cover {
+                    Some(cover) => vec![self.cover_entry(from_id, cover.savings_id, cover.amount, at)?],
+                    None => vec![],
+                };
+                entries.push(Entry::new(
+                    format!("Transfer from account {} to account {}", from_id, to_id),
+                    vec![
+                        Posting::debit(LedgerAccount::Customer(from_id), amount),
+                        Posting::credit(LedgerAccount::Customer(to_id), amount),
+                    ],
+                    vec![(from_id, debit), (to_id, credit)],
+                ));
+                if let Some(fee) = overdraft_fee {
+                    entries.push(self.overdraft_fee_entry(from_id, fee, at)?);
+                }
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.post(record, entries);
+            }
+            Event::OverdraftCovered { account_id, savings_id, amount } => {
+                let entries = vec![self.cover_entry(account_id, savings_id, amount, at)?];
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.post(record, entries);
+            }
+            Event::InterestPolicySet { product, policy } => {
+                if !product.rules().earns_interest {
+                    return Err(BankError::InvalidField {
+                        field: "interest policy",
+                        reason: format!("{} accounts do not earn interest", product),
+                    });
+                }
+                policy.validate()?;
+                self.write_journal(record)?;
+                self.storage.settings_mut().interest_policies.insert(product, policy);
+            }
+            Event::RateChangeScheduled { product, effective, table } => {
+                if !self.storage.settings().interest_policies.contains_key(&product) {
+                    return Err(BankError::InvalidField {
+                        field: "interest policy",
+                        reason: format!("{} accounts have no interest policy", product),
+                    });
+                }
+                table.validate()?;
+                self.write_journal(record)?;
+                let policies = &mut self.storage.settings_mut().interest_policies;
+                policies.get_mut(&product).expect("policy checked above").rates.change_from(effective, table);
+            }
+            Event::InterestAccrued { account_id, through, micros } => {
+                let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                if account.status == AccountStatus::Closed {
+                    return Err(BankError::AccountStatus { account_id, status: account.status });
+                }
+                if micros < 0 {
+                    return Err(BankError::InvalidField { field: "accrued interest", reason: "must not be negative".to_string() });
+                }
+                if account.interest.accrued_through.is_some_and(|done| through <= done) {
+                    return Err(BankError::InvalidField {
+                        field: "accrual date",
+                        reason: format!("account {} is already accrued through {}", account_id, through),
+                    });
+                }
+                let accrued = account.interest.accrued_micros
+                    .checked_add(micros)
+                    .ok_or(BankError::Money(MoneyError::Overflow))?;
+                self.write_journal(record)?;
+                let interest = &mut self.storage.account_mut(account_id).expect("account checked above").interest;
+                interest.accrued_micros = accrued;
+                interest.accrued_through = Some(through);
+            }
+            Event::InterestPosted { account_id, on, amount } => {
+                let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                account.check_credit(amount)?;
+                let paid_micros = amount.minor_units()
+                    .checked_mul(MICROS_PER_MINOR)
+                    .ok_or(BankError::Money(MoneyError::Overflow))?;
+                // Only interest already accrued can be paid.
+                let remaining = account.interest.accrued_micros
+                    .checked_sub(paid_micros)
+                    .filter(|remaining| *remaining >= 0)
+                    .ok_or(BankError::InvalidAmount(amount))?;
+                let tx = Transaction::new(TransactionType::Interest, amount).with_timestamp(end_of_day(on));
+                let entries = vec![Entry::new(
+                    format!("Interest paid to account {}", account_id),
+                    vec![
+                        Posting::debit(LedgerAccount::InterestExpense, amount),
+                        Posting::credit(LedgerAccount::Customer(account_id), amount),
+                    ],
+                    vec![(account_id, tx)],
+                )];
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.storage.account_mut(account_id).expect("account checked above").interest.accrued_micros = remaining;
+                self.post(record, entries);
+            }
+            Event::OverdraftLimitSet { account_id, limit } => {
+                let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                if account.status == AccountStatus::Closed {
+                    return Err(BankError::AccountStatus { account_id, status: account.status });
+                }
+                let invalid = |reason: String| BankError::InvalidField { field: "overdraft limit", reason };
+                if !account.product.rules().overdraft_allowed {
+                    return Err(invalid(format!("{} accounts cannot be overdrawn", account.product)));
+                }
+                if limit.is_negative() {
+                    return Err(invalid("must not be negative".to_string()));
+                }
+                if limit.currency() != account.currency() {
+                    return Err(invalid(format!("must be in {}", account.currency())));
+                }
+                self.write_journal(record)?;
+                let account = self.storage.account_mut(account_id).expect("account checked above");
+                account.overdraft_limit = Some(limit).filter(|limit| limit.is_positive());
+            }
+            Event::OverdraftProtectionSet { account_id, savings_id } => {
+                let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                if account.status == AccountStatus::Closed {
+                    return Err(BankError::AccountStatus { account_id, status: account.status });
+                }
+                if let Some(savings_id) = savings_id {
+                    let savings = self.get_account(savings_id).ok_or(BankError::AccountNotFound(savings_id))?;
+                    let invalid = |reason: String| BankError::InvalidField { field: "overdraft protection", reason };
+                    if savings_id == account_id {
+                        return Err(BankError::SameAccount(account_id));
+                    }
+                    if savings.status == AccountStatus::Closed {
+                        return Err(BankError::AccountStatus { account_id: savings_id, status: savings.status });
+                    }
+                    if !matches!(savings.product, AccountProduct::Savings | AccountProduct::MoneyMarket) {
+                        return Err(invalid(format!("{} accounts cannot cover overdrafts", savings.product)));
+                    }
+                    // Joint accounts qualify as long as someone holds both.
+                    let shared = account.all_parties().iter().any(|p| account.is_held_by(p.customer_id) && savings.is_held_by(p.customer_id));
+                    if !shared {
+                        return Err(invalid(format!("account {} belongs to another customer", savings_id)));
+                    }
+                    if savings.currency() != account.currency() {
+                        return Err(invalid(format!("account {} is not in {}", savings_id, account.currency())));
+                    }
+                }
+                self.write_journal(record)?;
+                self.storage.account_mut(account_id).expect("account checked above").overdraft_protection = savings_id;
+            }
+            Event::OverdraftFeeSet { product, fee } => {
+                if !product.rules().overdraft_allowed {
+                    return Err(BankError::InvalidField {
+                        field: "overdraft fee",
+                        reason: format!("{} accounts cannot be overdrawn", product),
+                    });
+                }
+                if !fee.is_positive() {
+                    return Err(BankError::InvalidAmount(fee));
+                }
+                self.write_journal(record)?;
+                self.storage.settings_mut().overdraft_fees.insert(product, fee);
+            }
+            Event::OverdraftFeeCharged { account_id, amount } => {
+                let entries = vec![self.overdraft_fee_entry(account_id, amount, at)?];
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.post(record, entries);
+            }
+            Event::FeeScheduleSet { product, schedule } => {
+                schedule.validate()?;
+                self.write_journal(record)?;
+                self.storage.settings_mut().fee_schedules.insert(product, schedule);
+            }
+            Event::FeeCharged { account_id, reason, amount, on } => {
+                let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                if account.status == AccountStatus::Closed {
+                    return Err(BankError::AccountStatus { account_id, status: account.status });
+                }
+                if !amount.is_positive() {
+                    return Err(BankError::InvalidAmount(amount));
+                }
+                account.balance.checked_sub(amount)?;
+                let tx = Transaction::new(TransactionType::Fee { reason }, amount).with_timestamp(end_of_day(on));
+                let entries = vec![Entry::new(
+                    format!("{} fee on account {}", reason, account_id),
+                    vec![
+                        Posting::debit(LedgerAccount::Customer(account_id), amount),
+                        Posting::credit(LedgerAccount::FeeIncome, amount),
+                    ],
+                    vec![(account_id, tx)],
+                )];
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.post(record, entries);
+            }
+            Event::FeesAssessed { account_id, through } => {
+                let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                if account.fees_assessed_through.is_some_and(|done| through <= done) {
+                    return Err(BankError::InvalidField {
+                        field: "fee assessment date",
+                        reason: format!("account {} is already assessed through {}", account_id, through),
+                    });
+                }
+                self.write_journal(record)?;
+                self.storage.account_mut(account_id).expect("account checked above").fees_assessed_through = Some(through);
+            }
+            Event::FeeWaiverSet { customer_id, reason, waived } => {
+                if self.get_customer(customer_id).is_none() {
+                    return Err(BankError::CustomerNotFound(customer_id));
+                }
+                self.write_journal(record)?;
+                let waivers = &mut self.storage.customer_mut(customer_id).expect("customer checked above").fee_waivers;
+                if waived {
+                    waivers.insert(reason);
+                } else {
+                    waivers.remove(&reason);
+                }
+            }
+            Event::FeeReversed { account_id, fee_id } => {
+                let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                let invalid = |reason: String| BankError::InvalidField { field: "fee", reason };
+                let fee = account.history
+                    .iter()
+                    .find(|tx| tx.id == fee_id && tx.tx_type.is_fee())
+                    .ok_or_else(|| invalid(format!("account {} has no fee {}", account_id, fee_id)))?;
+                if account.history.iter().any(|tx| tx.tx_type == TransactionType::FeeReversal { fee_id }) {
+                    return Err(invalid(format!("fee {} is already reversed", fee_id)));
+                }
+                let amount = fee.amount;
+                account.check_credit(amount)?;
+                let reversal_id = TransactionId(self.storage.sequences().next_transaction_id);
+                let tx = Transaction::new(TransactionType::FeeReversal { fee_id }, amount).with_timestamp(at);
+                let entries = vec![Entry::new(
+                    format!("Fee {} reversed on account {}", fee_id, account_id),
+                    vec![
+                        Posting::debit(LedgerAccount::FeeIncome, amount),
+                        Posting::credit(LedgerAccount::Customer(account_id), amount),
+                    ],
+                    vec![(account_id, tx)],
+                )];
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.post(record, entries);
+                self.mark_reversed(account_id, fee_id, reversal_id);
+            }
+            Event::TransactionReversed { transaction_id, reason } => {
+                let invalid = |reason: String| BankError::InvalidField { field: "transaction", reason };
+                if reason.trim().is_empty() {
+                    return Err(BankError::InvalidField { field: "reversal reason", reason: "must not be empty".to_string() });
+                }
+                let (account_id, original) = self.find_transaction(transaction_id)
+                    .ok_or_else(|| invalid(format!("no transaction {}", transaction_id)))?;
+                // The other side of the original entry: a second customer leg
+                // for transfers, a ledger account for everything else. Older
+                // transfer legs aren't linked, but were posted together, debit
+                // first, so their IDs are consecutive.
+                let pair = |other_id: AccountId, adjacent_id: Option<u64>, tx_type: TransactionType| {
+                    let pair_id = original.paired_with.map(|id| id.0).or(adjacent_id);
+                    self.get_account(other_id)
+                        .and_then(|other| other.history.iter().find(|tx| {
+                            Some(tx.id.0) == pair_id
+                                && tx.paired_with.is_none_or(|id| id == transaction_id)
+                                && tx.tx_type == tx_type
+                                && tx.amount == original.amount
+                        }))
+                        .map(|tx| (other_id, tx.clone()))
+                        .ok_or_else(|| invalid(format!("transfer {} has no matching leg on account {}", transaction_id, other_id)))
+                };
+                let (legs, counter) = match original.tx_type {
+                    TransactionType::Deposit | TransactionType::Withdrawal => (vec![(account_id, original.clone())], Some(LedgerAccount::Cash)),
+                    TransactionType::Interest => (vec![(account_id, original.clone())], Some(LedgerAccount::InterestExpense)),
+                    TransactionType::Transfer { to_account } => {
+                        let credit = pair(to_account, transaction_id.0.checked_add(1), TransactionType::TransferIn { from_account: account_id })?;
+                        (vec![credit, (account_id, original.clone())], None)
+                    }
+                    TransactionType::TransferIn { from_account } => {
+                        let debit = pair(from_account, transaction_id.0.checked_sub(1), TransactionType::Transfer { to_account: account_id })?;
+                        (vec![(account_id, original.clone()), debit], None)
+                    }
+                    ref fee if fee.is_fee() => return Err(invalid(format!("transaction {} is a fee; refund it with a fee reversal", transaction_id))),
+                    _ => return Err(invalid(format!("transaction {} cannot be reversed", transaction_id))),
+                };
+                for (leg_account, leg) in &legs {
+                    if leg.reversed_by.is_some() {
+                        return Err(invalid(format!("transaction {} is already reversed", leg.id)));
+                    }
+                    let account = self.get_account(*leg_account).ok_or(BankError::AccountNotFound(*leg_account))?;
+                    if account.loan.is_some() || account.certificate.is_some() {
+                        return Err(invalid(format!("account {} moves money only through its loan or certificate", leg_account)));
+                    }
+                    // A correction skips the monthly withdrawal limit but still
+                    // needs the money to be there.
+                    if leg.tx_type.is_credit() {
+                        if !account.status.allows_debits() {
+                            return Err(BankError::AccountStatus { account_id: *leg_account, status: account.status });
+                        }
+                        let available = account.available_balance()?;
+                        if available.checked_cmp(&leg.amount)?.is_lt() {
+                            return Err(BankError::InsufficientFunds { account_id: *leg_account, requested: leg.amount, available });
+                        }
+                    } else {
+                        account.check_credit(leg.amount)?;
+                    }
+                }
+
+                let amount = original.amount;
+                let mut postings = vec![];
+                let mut reversals = vec![];
+                for (leg_account, leg) in &legs {
+                    let credit = !leg.tx_type.is_credit();
+                    postings.push(if credit {
+                        Posting::credit(LedgerAccount::Customer(*leg_account), amount)
+                    } else {
+                        Posting::debit(LedgerAccount::Customer(*leg_account), amount)
+                    });
+                    let tx = Transaction::new(TransactionType::Reversal { original: leg.id, credit }, amount)
+                        .with_timestamp(at)
+                        .with_memo(Some(reason.clone()))
+                        .with_reference(leg.reference.clone())
+                        .with_counterparty(leg.counterparty.clone());
+                    reversals.push((*leg_account, tx));
+                }
+                if let Some(counter) = counter {
+                    if original.tx_type.is_credit() {
+                        postings.push(Posting::credit(counter, amount));
+                    } else {
+                        postings.insert(0, Posting::debit(counter, amount));
+                    }
+                }
+                let entries = vec![Entry::new(format!("Transaction {} reversed: {}", transaction_id, reason), postings, reversals)];
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                let first_id = self.storage.sequences().next_transaction_id;
+                self.post(record, entries);
+                for (n, (leg_account, leg)) in (0..).zip(&legs) {
+                    self.mark_reversed(*leg_account, leg.id, TransactionId(first_id + n));
+                }
+            }
+            Event::AccountStatusChanged { account_id, status } => {
+                let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                let invalid = |reason: String| BankError::InvalidField { field: "account status", reason };
+                if !account.status.can_change_to(status) {
+                    return Err(invalid(format!("account {} cannot go from {} to {}", account_id, account.status, status)));
+                }
+                if status == AccountStatus::Closed && !account.balance.is_zero() {
+                    return Err(invalid(format!("account {} must have a zero balance to close, not {}", account_id, account.balance)));
+                }
+                if status == AccountStatus::Closed && !account.holds.is_empty() {
+                    return Err(invalid(format!("account {} has open holds", account_id)));
+                }
+                // The final statement runs from the start of the closing month
+                // to the day the account closed.
+                let closing_statement = match status {
+                    AccountStatus::Closed => {
+                        let closed_on = at.date_naive();
+                        let month_start = closed_on.with_day(1).expect("every month has a first day");
+                        Some(AccountStatement::for_period(account, month_start, closed_on)?)
+                    }
+                    _ => None,
+                };
+                self.write_journal(record)?;
+                let account = self.storage.account_mut(account_id).expect("account checked above");
+                account.status = status;
+                if let Some(statement) = closing_statement {
+                    account.closed_at = Some(at);
+                    account.closing_statement = Some(statement);
+                }
+            }
+            Event::LifecyclePolicySet { policy } => {
+                policy.validate()?;
+                self.write_journal(record)?;
+                self.storage.settings_mut().lifecycle_policy = policy;
+            }
+            Event::AccountPartyAdded { account_id, customer_id, role } => {
+                let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                let invalid = |reason: String| BankError::InvalidField { field: "account party", reason };
+                if account.status == AccountStatus::Closed {
+                    return Err(BankError::AccountStatus { account_id, status: account.status });
+                }
+                self.storage.customer(customer_id).ok_or(BankError::CustomerNotFound(customer_id))?;
+                if role == PartyRole::PrimaryHolder {
+                    return Err(invalid("an account's primary holder is set when it is opened".to_string()));
+                }
+                let roles = account.roles_of(customer_id);
+                if roles.contains(&role) {
+                    return Err(invalid(format!("customer {} is already {} on account {}", customer_id, role.name().to_lowercase(), account_id)));
+                }
+                if role.is_holder() && roles.iter().any(PartyRole::is_holder) {
+                    return Err(invalid(format!("customer {} already holds account {}", customer_id, account_id)));
+                }
+                self.write_journal(record)?;
+                self.storage.account_mut(account_id).expect("account checked above").parties.push(AccountParty { customer_id, role });
+                self.index_party(customer_id, account_id);
+            }
+            Event::AccountPartyRemoved { account_id, customer_id, role } => {
+                let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                let invalid = |reason: String| BankError::InvalidField { field: "account party", reason };
+                if role == PartyRole::PrimaryHolder {
+                    return Err(invalid("an account's primary holder cannot be removed".to_string()));
+                }
+                if !account.roles_of(customer_id).contains(&role) {
+                    return Err(invalid(format!("customer {} is not {} on account {}", customer_id, role.name().to_lowercase(), account_id)));
+                }
+                self.write_journal(record)?;
+                let account = self.storage.account_mut(account_id).expect("account checked above");
+                account.parties.retain(|p| *p != AccountParty { customer_id, role });
+                if account.roles_of(customer_id).is_empty() {
+                    if let Some(ids) = self.customer_accounts.get_mut(&customer_id) {
+                        ids.retain(|id| *id != account_id);
+                    }
+                }
+            }
+            Event::SigningRuleSet { account_id, rule } => {
+                let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                if account.status == AccountStatus::Closed {
+                    return Err(BankError::AccountStatus { account_id, status: account.status });
+                }
+                self.write_journal(record)?;
+                self.storage.account_mut(account_id).expect("account checked above").signing_rule = rule;
+            }
+            Event::HoldPlaced { account_id, hold_id, kind, amount, expires_at } => {
+                let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                check_next_id("hold", hold_id.0, self.storage.sequences().next_hold_id)?;
+                if kind == HoldKind::CardAuthorization {
+                    account.check_debit(amount, at)?;
+                } else if account.status == AccountStatus::Closed {
+                    return Err(BankError::AccountStatus { account_id, status: account.status });
+                } else if !amount.is_positive() {
+                    return Err(BankError::InvalidAmount(amount));
+                }
+                account.held_amount()?.checked_add(amount)?;
+                if expires_at.is_some_and(|expires| expires <= at) {
+                    return Err(BankError::InvalidField { field: "hold expiry", reason: "must be in the future".to_string() });
+                }
+                self.write_journal(record)?;
+                self.storage.sequences_mut().allocate_hold_id();
+                self.storage.account_mut(account_id).expect("account checked above").holds.push(Hold {
+                    id: hold_id,
+                    kind,
+                    amount,
+                    placed_at: at,
+                    expires_at,
+                });
+            }
+            Event::HoldReleased { account_id, hold_id } => {
+                self.find_hold(account_id, hold_id)?;
+                self.write_journal(record)?;
+                self.storage.account_mut(account_id).expect("account checked above").holds.retain(|hold| hold.id != hold_id);
+            }
+            Event::HoldExpired { account_id, hold_id } => {
+                if !self.find_hold(account_id, hold_id)?.is_expired(at) {
+                    return Err(BankError::InvalidField { field: "hold", reason: format!("hold {} has not expired", hold_id) });
+                }
+                self.write_journal(record)?;
+                self.storage.account_mut(account_id).expect("account checked above").holds.retain(|hold| hold.id != hold_id);
+            }
+            Event::HoldCaptured { account_id, hold_id, amount } => {
+                let hold = self.find_hold(account_id, hold_id)?;
+                let status = self.get_account(account_id).expect("hold found above").status;
+                // Legal holds are settled whatever the account's status.
+                if hold.kind != HoldKind::Legal && !status.allows_debits() {
+                    return Err(BankError::AccountStatus { account_id, status });
+                }
+                if !amount.is_positive() {
+                    return Err(BankError::InvalidAmount(amount));
+                }
+                if amount.checked_cmp(&hold.amount)?.is_gt() {
+                    return Err(BankError::InvalidField {
+                        field: "capture amount",
+                        reason: format!("{} is more than the {} held", amount, hold.amount),
+                    });
+                }
+                let tx = Transaction::new(TransactionType::HoldCapture { hold_id }, amount).with_timestamp(at);
+                let entries = vec![Entry::new(
+                    format!("Hold {} captured on account {}", hold_id, account_id),
+                    vec![
+                        Posting::debit(LedgerAccount::Customer(account_id), amount),
+                        Posting::credit(LedgerAccount::Cash, amount),
+                    ],
+                    vec![(account_id, tx)],
+                )];
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.storage.account_mut(account_id).expect("account checked above").holds.retain(|hold| hold.id != hold_id);
+                self.post(record, entries);
+            }
+            Event::TransferScheduled { id, order } => {
+                check_next_id("scheduled transfer", id.0, self.storage.sequences().next_schedule_id)?;
+                order.validate()?;
+                for account_id in [order.from_id, order.to_id] {
+                    self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                }
+                let first_due = order.recurrence
+                    .first_on_or_after(order.start)
+                    .filter(|first| order.end.is_none_or(|end| *first <= end))
+                    .ok_or_else(|| BankError::InvalidField {
+                        field: "end date",
+                        reason: "no transfer would fall due before it".to_string(),
+                    })?;
+                self.write_journal(record)?;
+                self.storage.sequences_mut().allocate_schedule_id();
+                self.storage.insert_scheduled_transfer(ScheduledTransfer {
+                    id,
+                    order,
+                    next_due: Some(first_due),
+                    attempts: 0,
+                    cancelled: false,
+                    history: vec![],
+                }).expect("ID checked above");
+            }
+            Event::ScheduledTransferCancelled { id } => {
+                let schedule = self.find_scheduled_transfer(id)?;
+                if schedule.next_due.is_none() {
+                    return Err(BankError::InvalidField {
+                        field: "scheduled transfer",
+                        reason: format!("scheduled transfer {} has already finished", id),
+                    });
+                }
+                self.write_journal(record)?;
+                let schedule = self.storage.scheduled_transfer_mut(id).expect("schedule checked above");
+                schedule.cancelled = true;
+                schedule.next_due = None;
+            }
+            Event::ScheduledTransferRun { id, due, ran_on, outcome } => {
+                let schedule = self.find_scheduled_transfer(id)?;
+                if schedule.next_due != Some(due) {
+                    return Err(BankError::InvalidField {
+                        field: "scheduled transfer",
+                        reason: format!("scheduled transfer {} is not due on {}", id, due),
+                    });
+                }
+                self.write_journal(record)?;
+                let schedule = self.storage.scheduled_transfer_mut(id).expect("schedule checked above");
+                match outcome {
+                    Outcome::Retrying { attempt } => schedule.attempts = attempt,
+                    Outcome::Executed | Outcome::Skipped { .. } => {
+                        schedule.attempts = 0;
+                        schedule.next_due = schedule.order.next_due_after(due);
+                    }
+                }
+                schedule.history.push(Execution { schedule_id: id, due, ran_on, outcome });
+            }
+            Event::LoanDisbursed { loan_id, linked_id, terms } => {
+                let account = self.get_account(loan_id).ok_or(BankError::AccountNotFound(loan_id))?;
+                let (loan, entry) = self.loan_disbursal(account, linked_id, terms, at)?;
+                let entries = vec![entry];
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.storage.account_mut(loan_id).expect("account checked above").loan = Some(loan);
+                self.post(record, entries);
+            }
+            Event::LoanRepaid { loan_id, installment, interest, principal } => {
+                let loan = self.find_loan(loan_id)?;
+                let next = loan.next_installment().ok_or_else(|| BankError::InvalidField {
+                    field: "loan",
+                    reason: format!("loan {} is repaid", loan_id),
+                })?;
+                if (next.number, next.interest, next.principal) != (installment, interest, principal) {
+                    return Err(BankError::InvalidField {
+                        field: "loan installment",
+                        reason: format!("installment {} of loan {} is not the one due", installment, loan_id),
+                    });
+                }
+                let linked_id = loan.linked_account;
+                let amount = interest.checked_add(principal)?;
+                self.get_account(linked_id)
+                    .ok_or(BankError::AccountNotFound(linked_id))?
+                    .check_debit(amount, at)?;
+                let mut postings = vec![
+                    Posting::debit(LedgerAccount::Customer(linked_id), amount),
+                    Posting::credit(LedgerAccount::Customer(loan_id), principal),
+                ];
+                if interest.is_positive() {
+                    postings.push(Posting::credit(LedgerAccount::InterestIncome, interest));
+                }
+                let memo = Some(format!("Installment {}", installment));
+                let entries = vec![Entry::new(
+                    format!("Installment {} of loan {} paid from account {}", installment, loan_id, linked_id),
+                    postings,
+                    vec![
+                        (linked_id, Transaction::new(TransactionType::LoanRepayment { loan_account: loan_id }, amount)
+                            .with_timestamp(at)
+                            .with_memo(memo.clone())),
+                        (loan_id, Transaction::new(TransactionType::PrincipalRepaid { from_account: linked_id }, principal)
+                            .with_timestamp(at)
+                            .with_memo(memo)),
+                    ],
+                )];
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                let loan = self.storage.account_mut(loan_id).expect("loan checked above").loan.as_mut().expect("loan checked above");
+                loan.repay_installment(at.date_naive());
+                self.post(record, entries);
+            }
+            Event::LoanPrepaid { loan_id, amount, recalculation } => {
+                let loan = self.find_loan(loan_id)?;
+                if !amount.is_positive() {
+                    return Err(BankError::InvalidAmount(amount));
+                }
+                if amount.checked_cmp(&loan.outstanding_principal)?.is_gt() {
+                    return Err(BankError::InvalidField {
+                        field: "extra payment",
+                        reason: format!("{} is more than the {} still owed", amount, loan.outstanding_principal),
+                    });
+                }
+                let linked_id = loan.linked_account;
+                let mut updated = loan.clone();
+                updated.prepay(amount, at.date_naive(), recalculation)?;
+                self.get_account(linked_id)
+                    .ok_or(BankError::AccountNotFound(linked_id))?
+                    .check_debit(amount, at)?;
+                let memo = Some("Extra payment".to_string());
+                let entries = vec![Entry::new(
+                    format!("Extra payment on loan {} from account {}", loan_id, linked_id),
+                    vec![
+                        Posting::debit(LedgerAccount::Customer(linked_id), amount),
+                        Posting::credit(LedgerAccount::Customer(loan_id), amount),
+                    ],
+                    vec![
+                        (linked_id, Transaction::new(TransactionType::LoanRepayment { loan_account: loan_id }, amount)
+                            .with_timestamp(at)
+                            .with_memo(memo.clone())),
+                        (loan_id, Transaction::new(TransactionType::PrincipalRepaid { from_account: linked_id }, amount)
+                            .with_timestamp(at)
+                            .with_memo(memo)),
+                    ],
+                )];
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.storage.account_mut(loan_id).expect("loan checked above").loan = Some(updated);
+                self.post(record, entries);
+            }
+            Event::CreditCardOpened { account_id, credit_limit, terms } => {
+                let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                let card = card_opening(account, credit_limit, terms, at)?;
+                self.write_journal(record)?;
+                let account = self.storage.account_mut(account_id).expect("account checked above");
+                account.overdraft_limit = Some(credit_limit).filter(|limit| limit.is_positive());
+                account.card = Some(card);
+            }
+            Event::CardInterestCharged { account_id, amount, on } => {
+                self.find_card(account_id)?;
+                let account = self.get_account(account_id).expect("card found above");
+                if account.status == AccountStatus::Closed {
+                    return Err(BankError::AccountStatus { account_id, status: account.status });
+                }
+                if !amount.is_positive() {
+                    return Err(BankError::InvalidAmount(amount));
+                }
+                account.balance.checked_sub(amount)?;
+                let tx = Transaction::new(TransactionType::InterestCharged, amount).with_timestamp(end_of_day(on));
+                let entries = vec![Entry::new(
+                    format!("Interest charged on card account {}", account_id),
+                    vec![
+                        Posting::debit(LedgerAccount::Customer(account_id), amount),
+                        Posting::credit(LedgerAccount::InterestIncome, amount),
+                    ],
+                    vec![(account_id, tx)],
+                )];
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.post(record, entries);
+            }
+            Event::StatementClosed { account_id, closing_date } => {
+                let card = self.find_card(account_id)?;
+                if closing_date != card.next_closing_date() {
+                    return Err(BankError::InvalidField {
+                        field: "statement closing date",
+                        reason: format!("the cycle on account {} closes on {}, not {}", account_id, card.next_closing_date(), closing_date),
+                    });
+                }
+                if card.last_statement().is_some_and(|s| s.paid_by_due.is_none()) {
+                    return Err(BankError::InvalidField {
+                        field: "statement closing date",
+                        reason: format!("the last statement on account {} has not reached its due date", account_id),
+                    });
+                }
+                let statement = card.statement_for(self.get_account(account_id).expect("card found above"), closing_date)?;
+                self.write_journal(record)?;
+                let account = self.storage.account_mut(account_id).expect("card found above");
+                account.card.as_mut().expect("card found above").statements.push(statement);
+            }
+            Event::StatementDue { account_id, number, paid } => {
+                let card = self.find_card(account_id)?;
+                if !card.last_statement().is_some_and(|s| s.number == number && s.paid_by_due.is_none()) {
+                    return Err(BankError::InvalidField {
+                        field: "statement",
+                        reason: format!("statement {} on account {} is not awaiting its due date", number, account_id),
+                    });
+                }
+                self.write_journal(record)?;
+                let account = self.storage.account_mut(account_id).expect("card found above");
+                let card = account.card.as_mut().expect("card found above");
+                card.statements.last_mut().expect("statement checked above").paid_by_due = Some(paid);
+            }
+            Event::CertificateOpened { account_id, funding_id, amount, terms } => {
+                let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+                let (certificate, entry) = self.certificate_funding(account, funding_id, amount, terms, at)?;
+                let entries = vec![entry];
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.storage.account_mut(account_id).expect("account checked above").certificate = Some(certificate);
+                self.post(record, entries);
+            }
+            Event::CertificateMatured { account_id, interest } => {
+                let certificate = self.find_certificate(account_id)?;
+                if !certificate.is_locked() {
+                    return Err(BankError::InvalidField {
+                        field: "certificate",
+                        reason: format!("certificate {} has already matured", account_id),
+                    });
+                }
+                let account = self.get_account(account_id).expect("certificate found above");
+                if interest.is_negative() {
+                    return Err(BankError::InvalidAmount(interest));
+                }
+                if interest.is_positive() {
+                    account.check_credit_to_certificate(interest)?;
+                }
+                let maturity = certificate.maturity;
+                let mut updated = certificate.clone();
+                updated.terms_completed += 1;
+                match updated.terms.maturity_action {
+                    MaturityAction::AutoRenew => updated.renew(maturity)?,
+                    MaturityAction::PayOut { .. } => updated.phase = CertificatePhase::Ended,
+                    MaturityAction::GracePeriod { days } => {
+                        let until = maturity.checked_add_days(Days::new(u64::from(days))).ok_or(BankError::Money(MoneyError::Overflow))?;
+                        updated.phase = CertificatePhase::Grace { until };
+                    }
+                }
+                let mut entries = vec![];
+                if interest.is_positive() {
+                    let tx = Transaction::new(TransactionType::Interest, interest).with_timestamp(end_of_day(maturity));
+                    entries.push(Entry::new(
+                        format!("Interest paid to account {}", account_id),
+                        vec![
+                            Posting::debit(LedgerAccount::InterestExpense, interest),
+                            Posting::credit(LedgerAccount::Customer(account_id), interest),
+                        ],
+                        vec![(account_id, tx)],
+                    ));
+                }
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.storage.account_mut(account_id).expect("certificate found above").certificate = Some(updated);
+                self.post(record, entries);
+            }
+            Event::CertificatePaidOut { account_id, to_id, amount } => {
+                let certificate = self.find_certificate(account_id)?;
+                if certificate.terms.maturity_action != (MaturityAction::PayOut { to: to_id }) {
+                    return Err(BankError::InvalidField {
+                        field: "certificate",
+                        reason: format!("certificate {} does not pay out to account {}", account_id, to_id),
+                    });
+                }
+                if to_id == account_id {
+                    return Err(BankError::SameAccount(account_id));
+                }
+                let to = self.get_account(to_id).ok_or(BankError::AccountNotFound(to_id))?;
+                self.get_account(account_id).expect("certificate found above").check_debit(amount, at)?;
+                to.check_credit(amount)?;
+
+                let (debit, credit) = account::transfer_legs(account_id, to_id, amount, Some("Certificate of deposit payout".to_string()), at);
+                let entries = vec![Entry::new(
+                    format!("Certificate {} paid out to account {}", account_id, to_id),
+                    vec![
+                        Posting::debit(LedgerAccount::Customer(account_id), amount),
+                        Posting::credit(LedgerAccount::Customer(to_id), amount),
+                    ],
+                    vec![(account_id, debit.as_internal()), (to_id, credit.as_internal())],
+                )];
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.post(record, entries);
+            }
+            Event::CertificateGraceEnded { account_id } => {
+                let certificate = self.find_certificate(account_id)?;
+                let CertificatePhase::Grace { until } = certificate.phase else {
+                    return Err(BankError::InvalidField {
+                        field: "certificate",
+                        reason: format!("certificate {} is not in a grace period", account_id),
+                    });
+                };
+                let mut updated = certificate.clone();
+                if self.get_account(account_id).expect("certificate found above").balance.is_positive() {
+                    updated.renew(until)?;
+                } else {
+                    updated.phase = CertificatePhase::Ended;
+                }
+                self.write_journal(record)?;
+                self.storage.account_mut(account_id).expect("certificate found above").certificate = Some(updated);
+            }
+            Event::CertificateBroken { account_id, amount, penalty } => {
+                let certificate = self.find_certificate(account_id)?;
+                if !certificate.is_locked() {
+                    return Err(BankError::InvalidField {
+                        field: "certificate",
+                        reason: format!("certificate {} has matured and can be withdrawn from without penalty", account_id),
+                    });
+                }
+                let account = self.get_account(account_id).expect("certificate found above");
+                if !account.status.allows_debits() {
+                    return Err(BankError::AccountStatus { account_id, status: account.status });
+                }
+                if !amount.is_positive() {
+                    return Err(BankError::InvalidAmount(amount));
+                }
+                if penalty.is_negative() || penalty.checked_cmp(&amount)?.is_gt() {
+                    return Err(BankError::InvalidField {
+                        field: "early withdrawal penalty",
+                        reason: format!("must be between zero and the {} withdrawn", amount),
+                    });
+                }
+                let available = account.balance.checked_sub(account.held_amount()?)?;
+                if available.checked_cmp(&amount)?.is_lt() {
+                    return Err(BankError::InsufficientFunds { account_id, requested: amount, available });
+                }
+                let paid_out = amount.checked_sub(penalty)?;
+                let mut entries = vec![];
+                if penalty.is_positive() {
+                    let tx = Transaction::new(TransactionType::Fee { reason: FeeReason::EarlyWithdrawal }, penalty).with_timestamp(at);
+                    entries.push(Entry::new(
+                        format!("{} fee on account {}", FeeReason::EarlyWithdrawal, account_id),
+                        vec![
+                            Posting::debit(LedgerAccount::Customer(account_id), penalty),
+                            Posting::credit(LedgerAccount::FeeIncome, penalty),
+                        ],
+                        vec![(account_id, tx)],
+                    ));
+                }
+                if paid_out.is_positive() {
+                    let tx = Transaction::new(TransactionType::Withdrawal, paid_out)
+                        .with_timestamp(at)
+                        .with_memo(Some("Early withdrawal".to_string()));
+                    entries.push(Entry::new(
+                        format!("Withdrawal from account {}", account_id),
+                        vec![
+                            Posting::debit(LedgerAccount::Customer(account_id), paid_out),
+                            Posting::credit(LedgerAccount::Cash, paid_out),
+                        ],
+                        vec![(account_id, tx)],
+                    ));
+                }
+                self.check_entries(&entries)?;
+                self.write_journal(record)?;
+                self.post(record, entries);
+                let account = self.storage.account_mut(account_id).expect("certificate found above");
+                if account.balance.is_zero() {
+                    account.certificate.as_mut().expect("certificate found above").phase = CertificatePhase::Ended;
+                }
+            }
+        }
+        self.storage.sequences_mut().last_event = record.sequence;
+        match self.journal {
+            Some(_) => {
+                let _ = self.storage.flush();
+                Ok(())
+            }
+            None => self.storage.sync(),
+        }
+    }
+
+    /// Works out daily interest on every account whose product has an
+    /// interest policy, up to and including `through`, and pays it in on
+    /// each posting date along the way. Running it again for the same date
+    /// does nothing. Certificates of deposit earn their own fixed rate
+    /// instead; see [`Bank::mature_certificates`].
+    pub fn accrue_interest(&mut self, through: NaiveDate) -> Result<(), BankError> {
+        self.as_scheduler(|bank| {
+            let ids: Vec<AccountId> = bank.storage.accounts().iter().map(|a| a.id).collect();
+            for account_id in ids {
+                bank.accrue_account_interest(account_id, through)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn accrue_account_interest(&mut self, account_id: AccountId, through: NaiveDate) -> Result<(), BankError> {
+        let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+        let Some(policy) = self.storage.settings().interest_policies.get(&account.product).cloned() else {
+            return Ok(());
+        };
+        if account.status == AccountStatus::Closed || account.certificate.is_some() {
+            return Ok(());
+        }
+        let start = match account.interest.accrued_through {
+            Some(done) => done.succ_opt().ok_or(BankError::Money(MoneyError::Overflow))?,
+            None => match account.opened_at.or(account.history.first().map(|tx| tx.timestamp)) {
+                Some(opened) => opened.date_naive(),
+                None => through,
+            },
+        };
+
+        let opened = account.opened_at.map(|at| at.date_naive());
+        let mut micros: i64 = 0;
+        for day in start.iter_days().take_while(|day| *day <= through) {
+            let account = self.get_account(account_id).expect("account checked above");
+            let daily = policy.daily_micros(account.balance_at_end_of(day)?, day, opened)?;
+            micros = micros.checked_add(daily).ok_or(BankError::Money(MoneyError::Overflow))?;
+
+            let posting_date = policy.frequency.is_posting_date(day);
+            if posting_date || day == through {
+                self.execute(Event::InterestAccrued { account_id, through: day, micros })?;
+                micros = 0;
+            }
+            if posting_date {
+                let account = self.get_account(account_id).expect("account checked above");
+                let payable = policy.payable_minor(account.interest.accrued_micros);
+                if payable > 0 {
+                    let amount = Money::from_minor(payable, account.currency());
+                    self.execute(Event::InterestPosted { account_id, on: day, amount })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Interest earned but not yet paid, in whole minor units rounded
+    /// towards zero.
+    pub fn accrued_interest(&self, account_id: AccountId) -> Result<Money, BankError> {
+        let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+        let minor = RoundingMode::Down.divide(i128::from(account.interest.accrued_micros), i128::from(MICROS_PER_MINOR));
+        Ok(Money::from_minor(minor as i64, account.currency()))
+    }
+
+    /// Sets how accounts of `product` earn interest from the next accrual
+    /// run on. Only products whose rules allow interest can have a policy.
+    pub fn set_interest_policy(&mut self, product: AccountProduct, policy: InterestPolicy) -> Result<(), BankError> {
+        self.execute(Event::InterestPolicySet { product, policy })
+    }
+
+    pub fn interest_policy(&self, product: AccountProduct) -> Option<&InterestPolicy> {
+        self.storage.settings().interest_policies.get(&product)
+    }
+
+    /// Pays `table` on accounts of `product` for every day from `effective`
+    /// on. Interest already accrued stays as it was.
+    pub fn schedule_rate_change(&mut self, product: AccountProduct, effective: NaiveDate, table: RateTable) -> Result<(), BankError> {
+        self.execute(Event::RateChangeScheduled { product, effective, table })
+    }
+
+    /// Sets the fees accounts of `product` are charged from the next
+    /// assessment on.
+    pub fn set_fee_schedule(&mut self, product: AccountProduct, schedule: FeeSchedule) -> Result<(), BankError> {
+        self.execute(Event::FeeScheduleSet { product, schedule })
+    }
+
+    pub fn fee_schedule(&self, product: AccountProduct) -> Option<&FeeSchedule> {
+        self.storage.settings().fee_schedules.get(&product)
+    }
+
+    /// Stops (or with `waived` false, resumes) charging a customer fees
+    /// for `reason` on any of their accounts.
+    pub fn set_fee_waiver(&mut self, customer_id: CustomerId, reason: FeeReason, waived: bool) -> Result<(), BankError> {
+        self.execute(Event::FeeWaiverSet { customer_id, reason, waived })
+    }
+
+    /// Charges each account its product's scheduled fees for every whole
+    /// month ending on or before `through` that hasn't been assessed yet,
+    /// skipping fees the customer has had waived. Running it again for the
+    /// same date does nothing.
+    pub fn assess_fees(&mut self, through: NaiveDate) -> Result<(), BankError> {
+        self.as_scheduler(|bank| {
+            let ids: Vec<AccountId> = bank.storage.accounts().iter().map(|a| a.id).collect();
+            for account_id in ids {
+                bank.assess_account_fees(account_id, through)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn assess_account_fees(&mut self, account_id: AccountId, through: NaiveDate) -> Result<(), BankError> {
+        let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+        let Some(schedule) = self.storage.settings().fee_schedules.get(&account.product).cloned() else {
+            return Ok(());
+        };
+        if account.status == AccountStatus::Closed {
+            return Ok(());
+        }
+        let start = match account.fees_assessed_through {
+            Some(done) => done.succ_opt().ok_or(BankError::Money(MoneyError::Overflow))?,
+            None => match account.opened_at.or(account.history.first().map(|tx| tx.timestamp)) {
+                Some(opened) => opened.date_naive(),
+                None => through,
+            },
+        };
+
+        let mut month_end = fees::month_end(start);
+        while month_end <= through {
+            let account = self.get_account(account_id).expect("account checked above");
+            let waivers = self.get_customer(account.customer_id).map(|c| c.fee_waivers.clone()).unwrap_or_default();
+            for fee in schedule.assess(account, month_end)? {
+                if !waivers.contains(&fee.reason) {
+                    self.execute(Event::FeeCharged { account_id, reason: fee.reason, amount: fee.amount, on: month_end })?;
+                }
+            }
+            self.execute(Event::FeesAssessed { account_id, through: month_end })?;
+            let next = month_end.succ_opt().ok_or(BankError::Money(MoneyError::Overflow))?;
+            month_end = fees::month_end(next);
+        }
+        Ok(())
+    }
+
+    /// Refunds a fee or overdraft fee charged to `account_id`. Each fee can
+    /// only be reversed once.
+    pub fn reverse_fee(&mut self, account_id: AccountId, fee_id: TransactionId) -> Result<(), BankError> {
+        self.execute(Event::FeeReversed { account_id, fee_id })
+    }
+
+    /// Undoes a deposit, withdrawal or interest payment made by mistake,
+    /// posting a compensating entry that points back at it and marking the
+    /// original as reversed. Reversing either leg of a transfer reverses
+    /// both. Nothing can be reversed twice, and fees are refunded with
+    /// [`Bank::reverse_fee`] instead.
+    pub fn reverse_transaction(&mut self, transaction_id: TransactionId, reason: String) -> Result<(), BankError> {
+        self.execute(Event::TransactionReversed { transaction_id, reason })
+    }
+
+    /// The account `account_id` as the other side of a transaction.
+    fn counterparty(&self, account_id: AccountId) -> Option<Counterparty> {
+        let account = self.get_account(account_id)?;
+        let holder = self.get_customer(account.customer_id).map(|customer| customer.name.clone());
+        Some(account.counterparty(holder))
+    }
+
+    /// The account a transaction was posted to, and the transaction.
+    fn find_transaction(&self, transaction_id: TransactionId) -> Option<(AccountId, Transaction)> {
+        // Transactions from before IDs were assigned all share the default.
+        if transaction_id == TransactionId::default() {
+            return None;
+        }
+        self.storage.accounts().into_iter().find_map(|account| {
+            account.history.iter().find(|tx| tx.id == transaction_id).map(|tx| (account.id, tx.clone()))
+        })
+    }
+
+    fn mark_reversed(&mut self, account_id: AccountId, transaction_id: TransactionId, reversal_id: TransactionId) {
+        let account = self.storage.account_mut(account_id).expect("account checked before posting");
+        if let Some(tx) = account.history.iter_mut().find(|tx| tx.id == transaction_id) {
+            tx.reversed_by = Some(reversal_id);
+        }
+    }
+
+    /// Gives a customer a role on an account. The primary holder is set
+    /// when the account is opened; a customer can hold other roles as well,
+    /// but not two holder roles.
+    pub fn add_account_party(&mut self, account_id: AccountId, customer_id: CustomerId, role: PartyRole) -> Result<(), BankError> {
+        self.execute(Event::AccountPartyAdded { account_id, customer_id, role })
+    }
+
+    /// Takes a role away from a customer. The primary holder stays.
+    pub fn remove_account_party(&mut self, account_id: AccountId, customer_id: CustomerId, role: PartyRole) -> Result<(), BankError> {
+        self.execute(Event::AccountPartyRemoved { account_id, customer_id, role })
+    }
+
+    /// Sets who has to sign for withdrawals and transfers out of an account.
+    pub fn set_signing_rule(&mut self, account_id: AccountId, rule: SigningRule) -> Result<(), BankError> {
+        self.execute(Event::SigningRuleSet { account_id, rule })
+    }
+
+    /// Blocks debits on an account; credits still go through.
+    pub fn freeze_account(&mut self, account_id: AccountId) -> Result<(), BankError> {
+        self.execute(Event::AccountStatusChanged { account_id, status: AccountStatus::Frozen })
+    }
+
+    pub fn unfreeze_account(&mut self, account_id: AccountId) -> Result<(), BankError> {
+        self.change_status_from(account_id, AccountStatus::Frozen, AccountStatus::Active)
+    }
+
+    /// Returns a dormant account to active use, as once the customer has
+    /// been in touch.
+    pub fn reactivate_account(&mut self, account_id: AccountId) -> Result<(), BankError> {
+        self.change_status_from(account_id, AccountStatus::Dormant, AccountStatus::Active)
+    }
+
+    /// Closes an account with a zero balance and issues its final
+    /// statement, kept on the account. Its history stays as the final
+    /// record and nothing more can be posted to it.
+    pub fn close_account(&mut self, account_id: AccountId) -> Result<(), BankError> {
+        self.execute(Event::AccountStatusChanged { account_id, status: AccountStatus::Closed })
+    }
+
+    /// Reopens a closed account, within the lifecycle policy's window after
+    /// closing.
+    pub fn reopen_account(&mut self, account_id: AccountId) -> Result<(), BankError> {
+        let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+        if let Some(closed_at) = account.closed_at.filter(|_| account.status == AccountStatus::Closed) {
+            let days = (self.clock.now() - closed_at).num_days();
+            if days > self.storage.settings().lifecycle_policy.reopen_within_days {
+                return Err(BankError::InvalidField {
+                    field: "account status",
+                    reason: format!(
+                        "account {} was closed {} days ago; accounts can only be reopened within {} days",
+                        account_id, days, self.storage.settings().lifecycle_policy.reopen_within_days
+                    ),
+                });
+            }
+        }
+        self.change_status_from(account_id, AccountStatus::Closed, AccountStatus::Active)
+    }
+
+    /// Marks dormant every active account whose customer hasn't deposited,
+    /// withdrawn or transferred for the policy's number of months before
+    /// `as_of`. Returns the accounts marked.
+    pub fn flag_dormant_accounts(&mut self, as_of: NaiveDate) -> Result<Vec<AccountId>, BankError> {
+        self.as_scheduler(|bank| {
+            let cutoff = as_of
+                .checked_sub_months(Months::new(bank.storage.settings().lifecycle_policy.dormant_after_months))
+                .ok_or(BankError::Money(MoneyError::Overflow))?;
+            let dormant: Vec<AccountId> = bank.storage.accounts()
+                .iter()
+                .filter(|a| a.status == AccountStatus::Active)
+                .filter(|a| a.last_customer_activity().is_some_and(|last| last.date_naive() <= cutoff))
+                .map(|a| a.id)
+                .collect();
+            for account_id in &dormant {
+                bank.execute(Event::AccountStatusChanged { account_id: *account_id, status: AccountStatus::Dormant })?;
+            }
+            Ok(dormant)
+        })
+    }
+
+    pub fn lifecycle_policy(&self) -> LifecyclePolicy {
+        self.storage.settings().lifecycle_policy
+    }
+
+    pub fn set_lifecycle_policy(&mut self, policy: LifecyclePolicy) -> Result<(), BankError> {
+        self.execute(Event::LifecyclePolicySet { policy })
+    }
+
+    fn change_status_from(&mut self, account_id: AccountId, from: AccountStatus, to: AccountStatus) -> Result<(), BankError> {
+        let account = self.get_account(account_id).ok_or(BankError::AccountNotFound(account_id))?;
+        if account.status != from {
+            return Err(BankError::InvalidField {
+                field: "account status",
+                reason: format!("account {} is {}, not {}", account_id, account.status, from),
+            });
+        }
+        self.execute(Event::AccountStatusChanged { account_id, status: to })
+    }
+
+    pub fn clock(&self) -> Clock {
+        self.clock
+    }
+
+    /// Pins or releases the time stamped on new events.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+
+    /// Sets the channel stamped on new events and their transactions.
+    /// Batch runs always use [`Channel::Scheduler`].
+    pub fn set_channel(&mut self, channel: Channel) {
+        self.channel = channel;
+    }
+
+    /// Runs a batch job with the scheduler as its channel.
+    fn as_scheduler<T>(&mut self, job: impl FnOnce(&mut Self) -> Result<T, BankError>) -> Result<T, BankError> {
+        let channel = std::mem::replace(&mut self.channel, Channel::Scheduler);
+        let result = job(self);
+        self.channel = channel;
+        result
+    }
+
+    fn write_journal(&mut self, record: &JournalRecord) -> Result<(), BankError> {
+        match self.journal.as_mut() {
+            Some(journal) => journal.append(record),
+            None => Ok(()),
         }
     }
 
-    pub fn create_customer(&mut self, name: String, address: String, phone: String, email: String) -> u64 {
-        let id = self.next_id;
-        self.customers.push(Customer::new(id, name, address, phone, email));
-        self.next_id += 1;
-        id
+    /// Sequence number of the last event applied.
+    pub fn last_sequence(&self) -> u64 {
+        self.storage.sequences().last_event
     }
 
-    pub fn create_account(&mut self, customer_id: u64, account_type: String) -> u64 {
-        let id = self.next_id;
-        if let Some(_customer) = self.customers.iter().find(|c| c.id == customer_id) {
-            self.accounts.push(Account::new(
-                id,
-                customer_id,
-                format!("{} Account", account_type),
-                account_type,
-            ));
-            self.next_id += 1;
-            id
-        } else {
-            panic!("Customer not found");
+    /// Rebuilds state from the journal at `path` on top of `storage`, then
+    /// keeps recording every change there. `storage` is either empty or was
+    /// last written while this journal was attached; events it already
+    /// holds are skipped.
+    pub fn with_journal<P: Into<PathBuf>>(storage: S, path: P) -> Result<Self, BankError> {
+        let path = path.into();
+        let mut bank = Self::with_storage(storage)?;
+        let applied = bank.last_sequence();
+        for record in EventJournal::read(&path)?.into_iter().filter(|record| record.sequence > applied) {
+            bank.apply(&record)?;
         }
+        bank.journal = Some(EventJournal::open(path)?);
+        Ok(bank)
     }
 
-    pub fn get_customer(&self, id: u64) -> Option<&Customer> {
-        self.customers.iter().find(|c| c.id == id)
+    /// Checks that entries would post cleanly, in order, and that every
+    /// leg's account exists, so an event is never journaled only to fail
+    /// halfway through being applied.
+    fn check_entries(&self, entries: &[Entry]) -> Result<(), BankError> {
+        self.storage.ledger().check(entries.iter().map(|entry| entry.postings.as_slice()))?;
+        for (account_id, _) in entries.iter().flat_map(|entry| &entry.legs) {
+            if self.get_account(*account_id).is_none() {
+                return Err(BankError::AccountNotFound(*account_id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Posts each balanced journal entry, then records its customer-facing
+    /// transactions on their accounts with the balance derived from the
+    /// ledger. The two legs of a transfer are linked to each other. Callers
+    /// pass the entries through [`Bank::check_entries`] beforehand.
+    fn post(&mut self, record: &JournalRecord, entries: Vec<Entry>) {
+        for Entry { description, postings, mut legs } in entries {
+            let timestamp = legs.first().map(|(_, tx)| tx.timestamp).unwrap_or_else(|| self.clock.now());
+            self.storage.ledger_mut().post(timestamp, description, postings).expect("entries checked before journaling");
+            for (_, tx) in legs.iter_mut() {
+                tx.id = self.storage.sequences_mut().allocate_transaction_id();
+            }
+            if let [(from_id, debit), (to_id, credit)] = legs.as_mut_slice() {
+                if debit.tx_type == (TransactionType::Transfer { to_account: *to_id })
+                    && credit.tx_type == (TransactionType::TransferIn { from_account: *from_id })
+                {
+                    debit.paired_with = Some(credit.id);
+                    credit.paired_with = Some(debit.id);
+                }
+            }
+            for (account_id, mut tx) in legs {
+                tx.booking_date = Some(record.timestamp.date_naive());
+                tx.channel = record.channel;
+                if tx.counterparty.is_none() {
+                    tx.counterparty = tx.tx_type.counterparty_account().and_then(|id| self.counterparty(id));
+                }
+                let currency = tx.amount.currency();
+                let balance = self.storage.ledger().balance(LedgerAccount::Customer(account_id), currency);
+                self.storage.record_transaction(account_id, tx, balance).expect("entries checked before journaling");
+            }
+        }
     }
 
-    pub fn get_customer_mut(&mut self, id: u64) -> Option<&mut Customer> {
-        self.customers.iter_mut().find(|c| c.id == id)
+    pub fn ledger(&self) -> &Ledger {
+        self.storage.ledger()
     }
 
-    pub fn get_account(&self, id: u64) -> Option<&Account> {
-        self.accounts.iter().find(|a| a.id == id)
+    pub fn trial_balance(&self) -> TrialBalance {
+        self.storage.ledger().trial_balance()
     }
 
-    pub fn get_account_mut(&mut self, id: u64) -> Option<&mut Account> {
-        self.accounts.iter_mut().find(|a| a.id == id)
+    pub fn get_customer(&self, id: CustomerId) -> Option<&Customer> {
+        self.storage.customer(id)
     }
 
-    pub fn list_accounts(&self) -> &Vec<Account> {
-        &self.accounts
+    pub fn get_account(&self, id: AccountId) -> Option<&Account> {
+        self.storage.account(id)
     }
 
-    pub fn list_customer_accounts(&self, customer_id: u64) -> Vec<&Account> {
-        self.accounts.iter()
-            .filter(|a| a.customer_id == customer_id)
+    /// Looks up an account by its number, ignoring spaces and hyphens.
+    /// Numbers issued under an earlier format are found too. When nothing
+    /// is found, [`AccountNumberFormat::parse`] tells a typo from an account
+    /// that doesn't exist.
+    pub fn find_account_by_number(&self, number: &str) -> Option<&Account> {
+        let number = AccountNumber::strip_separators(number);
+        self.account_numbers.get(&number).and_then(|id| self.storage.account(*id))
+    }
+
+    pub fn account_number_format(&self) -> &AccountNumberFormat {
+        &self.storage.settings().account_number_format
+    }
+
+    /// Sets how accounts opened from now on are numbered. Existing numbers
+    /// stay as they are.
+    pub fn set_account_number_format(&mut self, format: AccountNumberFormat) -> Result<(), BankError> {
+        self.execute(Event::AccountNumberFormatSet { format })
+    }
+
+    pub fn list_accounts(&self) -> Vec<&Account> {
+        self.storage.accounts()
+    }
+
+    /// Every account the customer has any role on, in ID order.
+    pub fn list_customer_accounts(&self, customer_id: CustomerId) -> Vec<&Account> {
+        self.customer_accounts
+            .get(&customer_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.storage.account(*id))
             .collect()
     }
 
-    pub fn list_customers(&self) -> &Vec<Customer> {
-        &self.customers
+    pub fn list_customers(&self) -> Vec<&Customer> {
+        self.storage.customers()
+    }
+}
+
+/// A ledger entry and the customer transactions it produces, worked out in
+/// full before its event is journaled.
+struct Entry {
+    description: String,
+    postings: Vec<Posting>,
+    legs: Vec<(AccountId, Transaction)>,
+}
+
+impl Entry {
+    fn new(description: String, postings: Vec<Posting>, legs: Vec<(AccountId, Transaction)>) -> Self {
+        Self { description, postings, legs }
+    }
+}
+
+/// Checks that `account`, a credit card account without terms yet, can take
+/// `terms` and `credit_limit` at `at`, and sets up the card.
+fn card_opening(account: &Account, credit_limit: Money, terms: CardTerms, at: DateTime<Utc>) -> Result<CreditCard, BankError> {
+    let account_id = account.id;
+    let invalid = |reason: String| BankError::InvalidField { field: "credit card", reason };
+    if account.product != AccountProduct::CreditCard {
+        return Err(invalid(format!("account {} is not a credit card account", account_id)));
+    }
+    if account.card.is_some() {
+        return Err(invalid(format!("account {} already has card terms", account_id)));
+    }
+    if account.status == AccountStatus::Closed {
+        return Err(BankError::AccountStatus { account_id, status: account.status });
+    }
+    terms.validate()?;
+    if credit_limit.is_negative() {
+        return Err(BankError::InvalidAmount(credit_limit));
+    }
+    if credit_limit.currency() != account.currency() {
+        return Err(invalid(format!("credit limit must be in {}", account.currency())));
+    }
+    Ok(CreditCard::new(terms, at.date_naive()))
+}
+
+/// Checks that `amount` could be debited from `account` at `at` once `cover`
+/// has been moved in from overdraft protection.
+fn check_covered_debit(account: &Account, amount: Money, cover: Option<OverdraftCover>, at: DateTime<Utc>) -> Result<(), BankError> {
+    match (account.check_debit(amount, at), cover) {
+        (Err(BankError::InsufficientFunds { available, .. }), Some(cover)) if available.checked_add(cover.amount)? >= amount => Ok(()),
+        (result, _) => result,
+    }
+}
+
+/// The last second of `date`, when end-of-day postings such as interest
+/// and monthly fees are stamped.
+fn end_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_time(NaiveTime::from_hms_opt(23, 59, 59).expect("valid time")).and_utc()
+}
+
+/// Rejects a creation event whose ID doesn't match the storage's sequence,
+/// as when a journal is replayed onto the wrong storage.
+fn check_next_id(kind: &str, id: u64, expected: u64) -> Result<(), BankError> {
+    if id != expected {
+        return Err(BankError::Storage(format!("event creates {} {} but the next {} ID is {}", kind, id, kind, expected)));
     }
+    Ok(())
 }
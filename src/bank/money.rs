@@ -0,0 +1,361 @@
+use std::cmp::Ordering;
+use std::fmt;
+use serde::{Serialize, Deserialize, Deserializer};
+
+/// ISO 4217 currencies the bank can hold balances in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub enum Currency {
+    #[default]
+    USD,
+    EUR,
+    GBP,
+    CHF,
+    CAD,
+    AUD,
+    JPY,
+}
+
+impl Currency {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::USD => "USD",
+            Currency::EUR => "EUR",
+            Currency::GBP => "GBP",
+            Currency::CHF => "CHF",
+            Currency::CAD => "CAD",
+            Currency::AUD => "AUD",
+            Currency::JPY => "JPY",
+        }
+    }
+
+    /// Number of decimal places in the currency's minor unit.
+    pub fn exponent(&self) -> u32 {
+        match self {
+            Currency::JPY => 0,
+            _ => 2,
+        }
+    }
+
+    fn scale(&self) -> i64 {
+        10i64.pow(self.exponent())
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// How to resolve a result that falls between two minor units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RoundingMode {
+    /// Towards zero (truncate).
+    Down,
+    /// Away from zero.
+    Up,
+    /// Towards negative infinity.
+    Floor,
+    /// Towards positive infinity.
+    Ceiling,
+    /// Nearest, ties away from zero.
+    HalfUp,
+    /// Nearest, ties towards zero.
+    HalfDown,
+    /// Nearest, ties to the even neighbour (banker's rounding).
+    #[default]
+    HalfEven,
+}
+
+impl RoundingMode {
+    /// Divides `num` by `den` and rounds the quotient according to the mode.
+    pub fn divide(&self, num: i128, den: i128) -> i128 {
+        assert!(den != 0, "division by zero");
+        let (num, den) = if den < 0 { (-num, -den) } else { (num, den) };
+        let quotient = num / den;
+        let remainder = num % den;
+        if remainder == 0 {
+            return quotient;
+        }
+
+        let negative = num < 0;
+        let away = if negative { quotient - 1 } else { quotient + 1 };
+        let twice = remainder.abs() * 2;
+        let round_away = match self {
+            RoundingMode::Down => false,
+            RoundingMode::Up => true,
+            RoundingMode::Floor => negative,
+            RoundingMode::Ceiling => !negative,
+            RoundingMode::HalfUp => twice >= den,
+            RoundingMode::HalfDown => twice > den,
+            RoundingMode::HalfEven => twice > den || (twice == den && quotient % 2 != 0),
+        };
+
+        if round_away { away } else { quotient }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoneyError {
+    CurrencyMismatch { expected: Currency, found: Currency },
+    Overflow,
+    InvalidFormat(String),
+    /// The input has more decimal places than the currency allows.
+    Precision { currency: Currency, places: u32 },
+}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoneyError::CurrencyMismatch { expected, found } => {
+                write!(f, "currency mismatch: expected {}, found {}", expected, found)
+            }
+            MoneyError::Overflow => write!(f, "amount out of range"),
+            MoneyError::InvalidFormat(input) => write!(f, "invalid amount: {:?}", input),
+            MoneyError::Precision { currency, places } => {
+                write!(f, "{} amounts allow at most {} decimal places", currency, places)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoneyError {}
+
+/// A fixed-point monetary amount stored as an integer number of minor units
+/// (e.g. cents) together with its currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+pub struct Money {
+    minor: i64,
+    currency: Currency,
+}
+
+impl Money {
+    pub fn from_minor(minor: i64, currency: Currency) -> Self {
+        Self { minor, currency }
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Self::from_minor(0, currency)
+    }
+
+    /// Whole units of the currency, e.g. `from_major(5, USD)` is $5.00.
+    pub fn from_major(major: i64, currency: Currency) -> Result<Self, MoneyError> {
+        major
+            .checked_mul(currency.scale())
+            .map(|minor| Self::from_minor(minor, currency))
+            .ok_or(MoneyError::Overflow)
+    }
+
+    /// Parses a decimal string such as `"12.34"` or `"-0.5"`. Inputs with more
+    /// decimal places than the currency supports are rejected, unless the
+    /// extra places are all zeros.
+    pub fn parse(input: &str, currency: Currency) -> Result<Self, MoneyError> {
+        let (digits, places) = parse_decimal(input, None)?;
+        let exponent = currency.exponent();
+        if places > exponent {
+            return Err(MoneyError::Precision { currency, places: exponent });
+        }
+        let minor = digits
+            .checked_mul(10i128.pow(exponent - places))
+            .and_then(|m| i64::try_from(m).ok())
+            .ok_or(MoneyError::Overflow)?;
+        Ok(Self::from_minor(minor, currency))
+    }
+
+    /// Parses a decimal string, rounding any excess decimal places.
+    pub fn parse_rounded(input: &str, currency: Currency, mode: RoundingMode) -> Result<Self, MoneyError> {
+        let exponent = currency.exponent();
+        // One place past the currency's decides the rounding, along with
+        // whether anything non-zero follows it.
+        let (digits, places) = parse_decimal(input, Some(exponent + 1))?;
+        let minor = if places > exponent {
+            mode.divide(digits, 10i128.pow(places - exponent))
+        } else {
+            digits
+                .checked_mul(10i128.pow(exponent - places))
+                .ok_or(MoneyError::Overflow)?
+        };
+        let minor = i64::try_from(minor).map_err(|_| MoneyError::Overflow)?;
+        Ok(Self::from_minor(minor, currency))
+    }
+
+    /// Converts a floating point amount in major units. Only meant for
+    /// importing legacy data; the value is rounded to the nearest minor unit
+    /// representable from its shortest decimal form.
+    pub fn from_f64(value: f64, currency: Currency, mode: RoundingMode) -> Result<Self, MoneyError> {
+        if !value.is_finite() {
+            return Err(MoneyError::InvalidFormat(value.to_string()));
+        }
+        Self::parse_rounded(&value.to_string(), currency, mode)
+    }
+
+    pub fn minor_units(&self) -> i64 {
+        self.minor
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.minor == 0
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.minor > 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.minor < 0
+    }
+
+    pub fn checked_abs(self) -> Result<Money, MoneyError> {
+        self.minor
+            .checked_abs()
+            .map(|minor| Self::from_minor(minor, self.currency))
+            .ok_or(MoneyError::Overflow)
+    }
+
+    pub fn checked_add(self, rhs: Money) -> Result<Money, MoneyError> {
+        self.same_currency(&rhs)?;
+        self.minor
+            .checked_add(rhs.minor)
+            .map(|minor| Self::from_minor(minor, self.currency))
+            .ok_or(MoneyError::Overflow)
+    }
+
+    pub fn checked_sub(self, rhs: Money) -> Result<Money, MoneyError> {
+        self.same_currency(&rhs)?;
+        self.minor
+            .checked_sub(rhs.minor)
+            .map(|minor| Self::from_minor(minor, self.currency))
+            .ok_or(MoneyError::Overflow)
+    }
+
+    pub fn checked_neg(self) -> Result<Money, MoneyError> {
+        self.minor
+            .checked_neg()
+            .map(|minor| Self::from_minor(minor, self.currency))
+            .ok_or(MoneyError::Overflow)
+    }
+
+    pub fn checked_mul(self, factor: i64) -> Result<Money, MoneyError> {
+        self.minor
+            .checked_mul(factor)
+            .map(|minor| Self::from_minor(minor, self.currency))
+            .ok_or(MoneyError::Overflow)
+    }
+
+    /// Multiplies by `numerator / denominator`, rounding the result to a
+    /// whole minor unit with `mode`.
+    pub fn mul_ratio(self, numerator: i64, denominator: i64, mode: RoundingMode) -> Result<Money, MoneyError> {
+        if denominator == 0 {
+            return Err(MoneyError::Overflow);
+        }
+        let product = self.minor as i128 * numerator as i128;
+        let minor = mode.divide(product, denominator as i128);
+        i64::try_from(minor)
+            .map(|minor| Self::from_minor(minor, self.currency))
+            .map_err(|_| MoneyError::Overflow)
+    }
+
+    /// Compares two amounts, failing if their currencies differ.
+    pub fn checked_cmp(&self, other: &Money) -> Result<Ordering, MoneyError> {
+        self.same_currency(other)?;
+        Ok(self.minor.cmp(&other.minor))
+    }
+
+    fn same_currency(&self, other: &Money) -> Result<(), MoneyError> {
+        if self.currency == other.currency {
+            Ok(())
+        } else {
+            Err(MoneyError::CurrencyMismatch { expected: self.currency, found: other.currency })
+        }
+    }
+}
+
+impl PartialOrd for Money {
+    /// Amounts in different currencies are unordered.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.checked_cmp(other).ok()
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let exponent = self.currency.exponent();
+        let sign = if self.minor < 0 { "-" } else { "" };
+        let abs = self.minor.unsigned_abs();
+        if exponent == 0 {
+            write!(f, "{}{} {}", sign, abs, self.currency)
+        } else {
+            let scale = 10u64.pow(exponent);
+            write!(
+                f,
+                "{}{}.{:0width$} {}",
+                sign,
+                abs / scale,
+                abs % scale,
+                self.currency,
+                width = exponent as usize
+            )
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Minor { minor: i64, currency: Currency },
+            // Data saved before amounts were fixed-point stored bare dollar floats.
+            Legacy(f64),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Minor { minor, currency } => Ok(Money::from_minor(minor, currency)),
+            Repr::Legacy(value) => Money::from_f64(value, Currency::default(), RoundingMode::HalfEven)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Splits a decimal string into its digits as an integer and the number of
+/// decimal places, leaving out trailing zeros: `"-12.340"` becomes
+/// `(-1234, 2)`. Places past `keep` are cut off and, if any of them is
+/// non-zero, stand in as one more place holding a `1`, which rounds the same
+/// way they would: `"0.12501"` kept to 3 places becomes `(1251, 4)`.
+fn parse_decimal(input: &str, keep: Option<u32>) -> Result<(i128, u32), MoneyError> {
+    let invalid = || MoneyError::InvalidFormat(input.to_string());
+    let trimmed = input.trim();
+    let (negative, unsigned) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    let (whole, fraction) = match unsigned.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (unsigned, ""),
+    };
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(invalid());
+    }
+    if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    let fraction = fraction.trim_end_matches('0');
+    let (fraction, sticky) = match keep.and_then(|keep| usize::try_from(keep).ok()) {
+        Some(keep) if fraction.len() > keep => (&fraction[..keep], Some('1')),
+        _ => (fraction, None),
+    };
+    let mut digits: i128 = 0;
+    for c in whole.chars().chain(fraction.chars()).chain(sticky) {
+        digits = digits
+            .checked_mul(10)
+            .and_then(|d| d.checked_add(c.to_digit(10).unwrap() as i128))
+            .ok_or(MoneyError::Overflow)?;
+    }
+    let places = u32::try_from(fraction.len() + usize::from(sticky.is_some())).map_err(|_| invalid())?;
+    Ok((if negative { -digits } else { digits }, places))
+}
@@ -0,0 +1,86 @@
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use super::CustomerId;
+
+/// What a customer is to an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PartyRole {
+    /// The customer the account was opened for.
+    PrimaryHolder,
+    /// Owns the account together with the primary holder.
+    JointHolder,
+    /// May operate the account without owning it.
+    AuthorizedSigner,
+    /// Acts for a holder under a power of attorney.
+    PowerOfAttorney,
+    /// Receives the money if the holders die; cannot sign.
+    Beneficiary,
+}
+
+impl PartyRole {
+    pub const ALL: [PartyRole; 5] = [
+        PartyRole::PrimaryHolder,
+        PartyRole::JointHolder,
+        PartyRole::AuthorizedSigner,
+        PartyRole::PowerOfAttorney,
+        PartyRole::Beneficiary,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PartyRole::PrimaryHolder => "Primary holder",
+            PartyRole::JointHolder => "Joint holder",
+            PartyRole::AuthorizedSigner => "Authorized signer",
+            PartyRole::PowerOfAttorney => "Power of attorney",
+            PartyRole::Beneficiary => "Beneficiary",
+        }
+    }
+
+    /// Whether the role owns the account.
+    pub fn is_holder(&self) -> bool {
+        matches!(self, PartyRole::PrimaryHolder | PartyRole::JointHolder)
+    }
+
+    /// Whether the role may sign for money leaving the account.
+    pub fn can_sign(&self) -> bool {
+        *self != PartyRole::Beneficiary
+    }
+}
+
+impl fmt::Display for PartyRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A customer with a role on an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountParty {
+    pub customer_id: CustomerId,
+    pub role: PartyRole,
+}
+
+/// Who has to sign for money to leave an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SigningRule {
+    /// Any one party who can sign is enough.
+    #[default]
+    AnyOne,
+    /// Every holder, primary and joint, must sign.
+    AllMustSign,
+}
+
+impl SigningRule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SigningRule::AnyOne => "Any one",
+            SigningRule::AllMustSign => "All must sign",
+        }
+    }
+}
+
+impl fmt::Display for SigningRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
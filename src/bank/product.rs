@@ -0,0 +1,148 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{Serialize, Deserialize, Deserializer};
+use super::BankError;
+
+/// The kind of account a customer holds. Saved under its display name, so
+/// files written when the type was free text still load; see
+/// [`AccountProduct::from_legacy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum AccountProduct {
+    Checking,
+    Savings,
+    MoneyMarket,
+    CertificateOfDeposit,
+    Loan,
+    CreditCard,
+}
+
+/// What a product lets its holder do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProductRules {
+    /// Whether the balance may be taken below zero, up to whatever limit the
+    /// account has been given.
+    pub overdraft_allowed: bool,
+    /// The most withdrawals and outgoing transfers per calendar month, or
+    /// `None` for no limit.
+    pub monthly_withdrawal_limit: Option<u32>,
+    /// Whether positive balances earn interest.
+    pub earns_interest: bool,
+}
+
+impl AccountProduct {
+    pub const ALL: [AccountProduct; 6] = [
+        AccountProduct::Checking,
+        AccountProduct::Savings,
+        AccountProduct::MoneyMarket,
+        AccountProduct::CertificateOfDeposit,
+        AccountProduct::Loan,
+        AccountProduct::CreditCard,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccountProduct::Checking => "Checking",
+            AccountProduct::Savings => "Savings",
+            AccountProduct::MoneyMarket => "Money Market",
+            AccountProduct::CertificateOfDeposit => "Certificate of Deposit",
+            AccountProduct::Loan => "Loan",
+            AccountProduct::CreditCard => "Credit Card",
+        }
+    }
+
+    /// The product for an account type saved as free text. Types that match
+    /// no product were plain transaction accounts, with no limits or
+    /// interest, and load as checking.
+    pub fn from_legacy(account_type: &str) -> Self {
+        account_type.parse().unwrap_or(AccountProduct::Checking)
+    }
+
+    pub fn rules(&self) -> ProductRules {
+        match self {
+            AccountProduct::Checking => ProductRules {
+                overdraft_allowed: true,
+                monthly_withdrawal_limit: None,
+                earns_interest: false,
+            },
+            AccountProduct::Savings | AccountProduct::MoneyMarket => ProductRules {
+                overdraft_allowed: false,
+                monthly_withdrawal_limit: Some(6),
+                earns_interest: true,
+            },
+            // Locked until maturity.
+            AccountProduct::CertificateOfDeposit => ProductRules {
+                overdraft_allowed: false,
+                monthly_withdrawal_limit: Some(0),
+                earns_interest: true,
+            },
+            // Money reaches the customer through disbursement, not withdrawal.
+            AccountProduct::Loan => ProductRules {
+                overdraft_allowed: false,
+                monthly_withdrawal_limit: Some(0),
+                earns_interest: false,
+            },
+            AccountProduct::CreditCard => ProductRules {
+                overdraft_allowed: true,
+                monthly_withdrawal_limit: None,
+                earns_interest: false,
+            },
+        }
+    }
+}
+
+impl fmt::Display for AccountProduct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for AccountProduct {
+    type Err = BankError;
+
+    /// Accepts any product name regardless of case, spacing or hyphens,
+    /// plus a few common alternatives such as "chequing" and "CD".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let key: String = s
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-' && *c != '_')
+            .flat_map(char::to_lowercase)
+            .collect();
+        let product = match key.as_str() {
+            "checking" | "chequing" | "current" => AccountProduct::Checking,
+            "savings" | "saving" => AccountProduct::Savings,
+            "moneymarket" | "mma" => AccountProduct::MoneyMarket,
+            "certificateofdeposit" | "cd" => AccountProduct::CertificateOfDeposit,
+            "loan" => AccountProduct::Loan,
+            "creditcard" | "credit" => AccountProduct::CreditCard,
+            _ => {
+                let names: Vec<&str> = AccountProduct::ALL.iter().map(|p| p.name()).collect();
+                return Err(BankError::InvalidField {
+                    field: "account type",
+                    reason: format!("must be one of {}", names.join(", ")),
+                });
+            }
+        };
+        Ok(product)
+    }
+}
+
+impl TryFrom<String> for AccountProduct {
+    type Error = BankError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<AccountProduct> for String {
+    fn from(product: AccountProduct) -> Self {
+        product.name().to_string()
+    }
+}
+
+/// Reads a stored account's product, falling back as
+/// [`AccountProduct::from_legacy`] does rather than failing the whole file.
+pub(crate) fn deserialize_stored<'de, D: Deserializer<'de>>(deserializer: D) -> Result<AccountProduct, D::Error> {
+    String::deserialize(deserializer).map(|account_type| AccountProduct::from_legacy(&account_type))
+}
@@ -0,0 +1,195 @@
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Serialize, Deserialize};
+use super::fees::month_end;
+use super::{AccountId, BankError, CustomerId, Money, ScheduleId};
+
+/// When a scheduled transfer falls due.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Recurrence {
+    /// A single future-dated transfer.
+    Once,
+    Daily,
+    /// Every `interval` weeks, on the weekday of the first transfer.
+    Weekly { interval: u32 },
+    /// On day `day` of every month, or the month's last day if it is
+    /// shorter.
+    MonthlyOnDay { day: u32 },
+    /// On the last day of every month.
+    EndOfMonth,
+}
+
+impl Recurrence {
+    /// The first due date on or after `start`.
+    pub fn first_on_or_after(&self, start: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Recurrence::Once | Recurrence::Daily | Recurrence::Weekly { .. } => Some(start),
+            Recurrence::MonthlyOnDay { day } => {
+                let this_month = day_in_month(start, *day);
+                if this_month >= start {
+                    Some(this_month)
+                } else {
+                    Some(day_in_month(start.checked_add_months(Months::new(1))?, *day))
+                }
+            }
+            Recurrence::EndOfMonth => Some(month_end(start)),
+        }
+    }
+
+    /// The due date after `due`, or `None` once a one-off has run.
+    pub fn next_after(&self, due: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Recurrence::Once => None,
+            Recurrence::Daily => due.succ_opt(),
+            Recurrence::Weekly { interval } => due.checked_add_days(Days::new(7 * u64::from(*interval))),
+            Recurrence::MonthlyOnDay { day } => {
+                let next_month = due.with_day(1)?.checked_add_months(Months::new(1))?;
+                Some(day_in_month(next_month, *day))
+            }
+            Recurrence::EndOfMonth => Some(month_end(due.succ_opt()?)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), BankError> {
+        let invalid = |reason: &str| BankError::InvalidField { field: "recurrence", reason: reason.to_string() };
+        match self {
+            Recurrence::Weekly { interval: 0 } => Err(invalid("must be at least one week apart")),
+            Recurrence::MonthlyOnDay { day } if !(1..=31).contains(day) => Err(invalid("day must be between 1 and 31")),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Day `day` of the month `date` is in, clamped to the month's last day.
+fn day_in_month(date: NaiveDate, day: u32) -> NaiveDate {
+    let last = month_end(date);
+    last.with_day(day.min(last.day())).expect("clamped to the month")
+}
+
+/// What to do when the paying account can't cover a transfer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InsufficientFunds {
+    /// Give up on this occurrence and wait for the next.
+    #[default]
+    Skip,
+    /// Try again on each later run, up to `attempts` tries in all, then
+    /// skip.
+    Retry { attempts: u32 },
+}
+
+/// What a customer asked for: a transfer and when to make it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StandingOrder {
+    pub from_id: AccountId,
+    pub to_id: AccountId,
+    pub amount: Money,
+    pub memo: Option<String>,
+    pub recurrence: Recurrence,
+    /// The earliest date the first transfer may be made.
+    pub start: NaiveDate,
+    /// No transfers fall due after this date.
+    pub end: Option<NaiveDate>,
+    pub on_insufficient_funds: InsufficientFunds,
+    /// The parties signing for each transfer, as for
+    /// [`TransactionDetails::signers`](super::TransactionDetails::signers).
+    #[serde(default)]
+    pub signers: Vec<CustomerId>,
+}
+
+impl StandingOrder {
+    pub fn new(from_id: AccountId, to_id: AccountId, amount: Money, recurrence: Recurrence, start: NaiveDate) -> Self {
+        Self {
+            from_id,
+            to_id,
+            amount,
+            memo: None,
+            recurrence,
+            start,
+            end: None,
+            on_insufficient_funds: InsufficientFunds::default(),
+            signers: vec![],
+        }
+    }
+
+    pub fn with_memo(mut self, memo: String) -> Self {
+        self.memo = Some(memo);
+        self
+    }
+
+    pub fn until(mut self, end: NaiveDate) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    pub fn on_insufficient_funds(mut self, action: InsufficientFunds) -> Self {
+        self.on_insufficient_funds = action;
+        self
+    }
+
+    pub fn signed_by(mut self, customer_id: CustomerId) -> Self {
+        self.signers.push(customer_id);
+        self
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), BankError> {
+        if self.from_id == self.to_id {
+            return Err(BankError::SameAccount(self.from_id));
+        }
+        if !self.amount.is_positive() {
+            return Err(BankError::InvalidAmount(self.amount));
+        }
+        self.recurrence.validate()?;
+        if self.on_insufficient_funds == (InsufficientFunds::Retry { attempts: 0 }) {
+            return Err(BankError::InvalidField { field: "retry attempts", reason: "must be at least one".to_string() });
+        }
+        if self.end.is_some_and(|end| end < self.start) {
+            return Err(BankError::InvalidField { field: "end date", reason: "must not be before the start".to_string() });
+        }
+        Ok(())
+    }
+
+    /// The due date after `due`, if there is one before the end date.
+    pub(crate) fn next_due_after(&self, due: NaiveDate) -> Option<NaiveDate> {
+        self.recurrence.next_after(due).filter(|next| self.end.is_none_or(|end| *next <= end))
+    }
+}
+
+/// How one due transfer went.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    Executed,
+    /// Not enough money; will be tried again on the next run.
+    Retrying { attempt: u32 },
+    /// Given up on, with the reason.
+    Skipped { reason: String },
+}
+
+/// One attempt at a due transfer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Execution {
+    pub schedule_id: ScheduleId,
+    pub due: NaiveDate,
+    pub ran_on: NaiveDate,
+    pub outcome: Outcome,
+}
+
+/// A standing order and where it has got to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledTransfer {
+    pub id: ScheduleId,
+    pub order: StandingOrder,
+    /// The next transfer to make; `None` once finished or cancelled.
+    pub next_due: Option<NaiveDate>,
+    /// Failed tries at `next_due` so far.
+    pub attempts: u32,
+    pub cancelled: bool,
+    pub history: Vec<Execution>,
+}
+
+impl ScheduledTransfer {
+    /// Whether a transfer is due on or before `today` and hasn't already
+    /// been tried today.
+    pub fn is_due(&self, today: NaiveDate) -> bool {
+        let tried_today = self.history.last().is_some_and(|last| last.ran_on == today && Some(last.due) == self.next_due);
+        self.next_due.is_some_and(|due| due <= today) && !tried_today
+    }
+}
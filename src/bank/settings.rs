@@ -0,0 +1,25 @@
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+use super::fees::FeeSchedule;
+use super::interest::InterestPolicy;
+use super::{AccountNumberFormat, AccountProduct, LifecyclePolicy, Money};
+
+/// Bank-wide configuration. It is saved along with the data and every
+/// change to it is journaled, so it survives a reload or a replay.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    /// How new accounts are numbered.
+    #[serde(default)]
+    pub account_number_format: AccountNumberFormat,
+    /// How each interest-bearing product earns interest.
+    #[serde(default)]
+    pub interest_policies: BTreeMap<AccountProduct, InterestPolicy>,
+    /// Charged each time a debit leaves an account of the product below zero.
+    #[serde(default)]
+    pub overdraft_fees: BTreeMap<AccountProduct, Money>,
+    /// The monthly fees each product charges.
+    #[serde(default)]
+    pub fee_schedules: BTreeMap<AccountProduct, FeeSchedule>,
+    #[serde(default)]
+    pub lifecycle_policy: LifecyclePolicy,
+}
@@ -0,0 +1,191 @@
+use std::fmt::Write as _;
+use chrono::{DateTime, Months, NaiveDate, Utc};
+use serde::{Serialize, Deserialize};
+use super::{Account, AccountId, AccountNumber, AccountProduct, BankError, Money, Transaction, TransactionId, TransactionType};
+
+/// One transaction on a statement, with the balance right after it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatementLine {
+    pub transaction_id: TransactionId,
+    pub timestamp: DateTime<Utc>,
+    pub description: String,
+    pub memo: Option<String>,
+    /// Positive for money in, negative for money out.
+    pub amount: Money,
+    pub balance: Money,
+}
+
+/// An account's statement for a period, built from its history alone.
+///
+/// A statement lists the transactions booked during the period, so a
+/// backdated posting such as interest shows on the statement for the day
+/// it was entered. The opening balance is the booked balance at the end of
+/// the day before the period and the closing balance the one at the end
+/// of its last day, so each statement opens where the previous one closed.
+/// Nothing depends on the clock: generating a past period again gives the
+/// same statement, whatever has been posted since.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountStatement {
+    pub account_id: AccountId,
+    pub account_number: AccountNumber,
+    pub account_name: String,
+    pub product: AccountProduct,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub opening_balance: Money,
+    /// Oldest first.
+    pub lines: Vec<StatementLine>,
+    pub total_credits: Money,
+    pub total_debits: Money,
+    /// Fees charged, less fees refunded, during the period.
+    pub fees: Money,
+    /// Interest paid into the account.
+    pub interest_paid: Money,
+    /// Interest charged to the account.
+    pub interest_charged: Money,
+    pub closing_balance: Money,
+}
+
+impl AccountStatement {
+    /// The statement for the calendar month `month` of `year`.
+    pub fn for_month(account: &Account, year: i32, month: u32) -> Result<Self, BankError> {
+        let start = NaiveDate::from_ymd_opt(year, month, 1).ok_or_else(|| BankError::InvalidField {
+            field: "statement month",
+            reason: format!("{}-{:02} is not a month", year, month),
+        })?;
+        let end = start
+            .checked_add_months(Months::new(1))
+            .and_then(|next| next.pred_opt())
+            .ok_or_else(|| BankError::InvalidField {
+                field: "statement month",
+                reason: "runs past the last supported date".to_string(),
+            })?;
+        Self::for_period(account, start, end)
+    }
+
+    /// The statement for the days from `start` to `end`, both included.
+    pub fn for_period(account: &Account, start: NaiveDate, end: NaiveDate) -> Result<Self, BankError> {
+        if end < start {
+            return Err(BankError::InvalidField {
+                field: "statement period",
+                reason: format!("ends on {} before it starts on {}", end, start),
+            });
+        }
+        let before = start.pred_opt().ok_or_else(|| BankError::InvalidField {
+            field: "statement period",
+            reason: "starts on the first supported date".to_string(),
+        })?;
+        let opening_balance = account.booked_balance_at_end_of(before)?;
+        let zero = Money::zero(account.currency());
+
+        // The period is cut off by booking date, so a backdated posting made
+        // after the period ended falls on the next statement. Lines within
+        // the period run in order of the date they count from.
+        let mut transactions: Vec<&Transaction> = account.history
+            .iter()
+            .filter(|tx| (start..=end).contains(&tx.booked_on()))
+            .collect();
+        transactions.sort_by_key(|tx| tx.timestamp);
+
+        let mut statement = Self {
+            account_id: account.id,
+            account_number: account.number.clone(),
+            account_name: account.name.clone(),
+            product: account.product,
+            period_start: start,
+            period_end: end,
+            opening_balance,
+            lines: Vec::with_capacity(transactions.len()),
+            total_credits: zero,
+            total_debits: zero,
+            fees: zero,
+            interest_paid: zero,
+            interest_charged: zero,
+            closing_balance: opening_balance,
+        };
+        for tx in transactions {
+            let amount = if tx.tx_type.is_credit() {
+                statement.total_credits = statement.total_credits.checked_add(tx.amount)?;
+                tx.amount
+            } else {
+                statement.total_debits = statement.total_debits.checked_add(tx.amount)?;
+                tx.amount.checked_neg()?
+            };
+            match tx.tx_type {
+                TransactionType::Interest => statement.interest_paid = statement.interest_paid.checked_add(tx.amount)?,
+                TransactionType::InterestCharged => {
+                    statement.interest_charged = statement.interest_charged.checked_add(tx.amount)?
+                }
+                TransactionType::FeeReversal { .. } => statement.fees = statement.fees.checked_sub(tx.amount)?,
+                ref fee if fee.is_fee() => statement.fees = statement.fees.checked_add(tx.amount)?,
+                _ => {}
+            }
+            statement.closing_balance = statement.closing_balance.checked_add(amount)?;
+            statement.lines.push(StatementLine {
+                transaction_id: tx.id,
+                timestamp: tx.timestamp,
+                description: describe(&tx.tx_type),
+                memo: tx.memo.clone(),
+                amount,
+                balance: statement.closing_balance,
+            });
+        }
+        Ok(statement)
+    }
+
+    /// The statement as a plain text document.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        // Writing to a String cannot fail.
+        let _ = writeln!(text, "Statement for {} {} ({})", self.account_name, self.account_number, self.product);
+        let _ = writeln!(text, "Period: {} to {}", self.period_start, self.period_end);
+        let _ = writeln!(text);
+        let _ = writeln!(text, "{:<10}  {:<40}  {:>16}  {:>16}", "Date", "Description", "Amount", "Balance");
+        let _ = writeln!(text, "{:<10}  {:<40}  {:>16}  {:>16}", self.period_start, "Opening balance", "", self.opening_balance.to_string());
+        for line in &self.lines {
+            let description = match &line.memo {
+                Some(memo) => format!("{} - {}", line.description, memo),
+                None => line.description.clone(),
+            };
+            let _ = writeln!(
+                text,
+                "{:<10}  {:<40}  {:>16}  {:>16}",
+                line.timestamp.date_naive(),
+                description,
+                line.amount.to_string(),
+                line.balance.to_string()
+            );
+        }
+        let _ = writeln!(text, "{:<10}  {:<40}  {:>16}  {:>16}", self.period_end, "Closing balance", "", self.closing_balance.to_string());
+        let _ = writeln!(text);
+        let _ = writeln!(text, "Total credits:    {}", self.total_credits);
+        let _ = writeln!(text, "Total debits:     {}", self.total_debits);
+        let _ = writeln!(text, "Fees:             {}", self.fees);
+        let _ = writeln!(text, "Interest paid:    {}", self.interest_paid);
+        let _ = writeln!(text, "Interest charged: {}", self.interest_charged);
+        text
+    }
+
+    /// The statement as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, BankError> {
+        serde_json::to_string_pretty(self).map_err(|err| BankError::Render(err.to_string()))
+    }
+}
+
+fn describe(tx_type: &TransactionType) -> String {
+    match tx_type {
+        TransactionType::Deposit => "Deposit".to_string(),
+        TransactionType::Withdrawal => "Withdrawal".to_string(),
+        TransactionType::Transfer { to_account } => format!("Transfer to account {}", to_account),
+        TransactionType::TransferIn { from_account } => format!("Transfer from account {}", from_account),
+        TransactionType::Interest => "Interest".to_string(),
+        TransactionType::OverdraftFee => "Overdraft fee".to_string(),
+        TransactionType::Fee { reason } => format!("Fee ({})", reason),
+        TransactionType::FeeReversal { fee_id } => format!("Refund of fee {}", fee_id),
+        TransactionType::HoldCapture { hold_id } => format!("Hold {} captured", hold_id),
+        TransactionType::LoanRepayment { loan_account } => format!("Repayment of loan {}", loan_account),
+        TransactionType::PrincipalRepaid { from_account } => format!("Principal repaid from account {}", from_account),
+        TransactionType::InterestCharged => "Interest charged".to_string(),
+        TransactionType::Reversal { original, .. } => format!("Reversal of transaction {}", original),
+    }
+}
@@ -0,0 +1,88 @@
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use super::BankError;
+
+/// Where an account is in its lifecycle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AccountStatus {
+    /// Open for everything its product allows.
+    #[default]
+    Active,
+    /// Debits are blocked; money can still come in.
+    Frozen,
+    /// Untouched by the customer for too long. Debits are blocked until the
+    /// account is reactivated.
+    Dormant,
+    /// Nothing can be posted. Only a zero-balance account can be closed.
+    Closed,
+}
+
+impl AccountStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccountStatus::Active => "Active",
+            AccountStatus::Frozen => "Frozen",
+            AccountStatus::Dormant => "Dormant",
+            AccountStatus::Closed => "Closed",
+        }
+    }
+
+    pub fn allows_debits(&self) -> bool {
+        *self == AccountStatus::Active
+    }
+
+    pub fn allows_credits(&self) -> bool {
+        *self != AccountStatus::Closed
+    }
+
+    /// The transitions the lifecycle permits. A frozen account has to be
+    /// unfrozen before anything else happens to it, and a closed one can
+    /// only be reopened.
+    pub fn can_change_to(&self, next: AccountStatus) -> bool {
+        use AccountStatus::*;
+        matches!(
+            (self, next),
+            (Active, Frozen | Dormant | Closed)
+                | (Frozen, Active)
+                | (Dormant, Active | Frozen | Closed)
+                | (Closed, Active)
+        )
+    }
+}
+
+impl fmt::Display for AccountStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// When accounts go dormant and how long a closed one can be reopened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LifecyclePolicy {
+    /// Months without a customer deposit, withdrawal or transfer after
+    /// which an active account goes dormant.
+    pub dormant_after_months: u32,
+    /// Days after closing during which an account can be reopened.
+    pub reopen_within_days: i64,
+}
+
+impl LifecyclePolicy {
+    pub fn validate(&self) -> Result<(), BankError> {
+        if self.dormant_after_months == 0 {
+            return Err(BankError::InvalidField { field: "dormancy period", reason: "must be at least one month".to_string() });
+        }
+        if self.reopen_within_days < 0 {
+            return Err(BankError::InvalidField { field: "reopening period", reason: "must not be negative".to_string() });
+        }
+        Ok(())
+    }
+}
+
+impl Default for LifecyclePolicy {
+    fn default() -> Self {
+        Self {
+            dormant_after_months: 12,
+            reopen_within_days: 90,
+        }
+    }
+}
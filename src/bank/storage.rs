@@ -0,0 +1,408 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize, Deserializer, Serializer};
+use super::ledger::Ledger;
+use super::schedule::ScheduledTransfer;
+use super::settings::Settings;
+use super::{Account, AccountId, BankError, Customer, CustomerId, IdSequences, Money, ScheduleId, Transaction};
+
+/// Where a [`Bank`](super::Bank) keeps its customers, accounts, transactions
+/// and general ledger.
+///
+/// Listings must come back in a stable order, oldest first.
+pub trait Storage {
+    fn customer(&self, id: CustomerId) -> Option<&Customer>;
+    fn customer_mut(&mut self, id: CustomerId) -> Option<&mut Customer>;
+    fn customers(&self) -> Vec<&Customer>;
+    fn insert_customer(&mut self, customer: Customer) -> Result<(), BankError>;
+
+    fn account(&self, id: AccountId) -> Option<&Account>;
+    fn account_mut(&mut self, id: AccountId) -> Option<&mut Account>;
+    fn accounts(&self) -> Vec<&Account>;
+    fn insert_account(&mut self, account: Account) -> Result<(), BankError>;
+
+    /// Appends an already-posted transaction to an account's history and
+    /// stores the account's resulting balance.
+    fn record_transaction(&mut self, account_id: AccountId, tx: Transaction, balance: Money) -> Result<(), BankError>;
+
+    fn scheduled_transfer(&self, id: ScheduleId) -> Option<&ScheduledTransfer>;
+    fn scheduled_transfer_mut(&mut self, id: ScheduleId) -> Option<&mut ScheduledTransfer>;
+    fn scheduled_transfers(&self) -> Vec<&ScheduledTransfer>;
+    fn insert_scheduled_transfer(&mut self, schedule: ScheduledTransfer) -> Result<(), BankError>;
+
+    /// The next customer, account and transaction IDs to hand out.
+    fn sequences(&self) -> &IdSequences;
+    fn sequences_mut(&mut self) -> &mut IdSequences;
+
+    fn ledger(&self) -> &Ledger;
+    fn ledger_mut(&mut self) -> &mut Ledger;
+
+    fn settings(&self) -> &Settings;
+    fn settings_mut(&mut self) -> &mut Settings;
+
+    /// Called by `Bank` after each event it applies and journals. A backend
+    /// may make the changes durable straight away or hold them back until
+    /// [`Storage::sync`], since the journal can replay them. Without a
+    /// journal, `Bank` calls `sync` instead.
+    fn flush(&mut self) -> Result<(), BankError> {
+        Ok(())
+    }
+
+    /// Makes all changes so far durable.
+    fn sync(&mut self) -> Result<(), BankError> {
+        self.flush()
+    }
+}
+
+/// Keeps everything in memory, keyed by ID. Serializes to the same JSON
+/// layout `Bank` has always saved, with customers and accounts as lists.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryStorage {
+    #[serde(serialize_with = "serialize_values", deserialize_with = "deserialize_by_id")]
+    accounts: BTreeMap<AccountId, Account>,
+    #[serde(serialize_with = "serialize_values", deserialize_with = "deserialize_by_id")]
+    customers: BTreeMap<CustomerId, Customer>,
+    #[serde(default)]
+    sequences: IdSequences,
+    #[serde(default)]
+    ledger: Ledger,
+    #[serde(default, serialize_with = "serialize_values", deserialize_with = "deserialize_by_id")]
+    scheduled_transfers: BTreeMap<ScheduleId, ScheduledTransfer>,
+    #[serde(default)]
+    settings: Settings,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self {
+            accounts: BTreeMap::new(),
+            customers: BTreeMap::new(),
+            sequences: IdSequences::new(),
+            ledger: Ledger::new(),
+            scheduled_transfers: BTreeMap::new(),
+            settings: Settings::default(),
+        }
+    }
+}
+
+impl Default for MemoryStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn customer(&self, id: CustomerId) -> Option<&Customer> {
+        self.customers.get(&id)
+    }
+
+    fn customer_mut(&mut self, id: CustomerId) -> Option<&mut Customer> {
+        self.customers.get_mut(&id)
+    }
+
+    fn customers(&self) -> Vec<&Customer> {
+        self.customers.values().collect()
+    }
+
+    fn insert_customer(&mut self, customer: Customer) -> Result<(), BankError> {
+        if self.customers.contains_key(&customer.id) {
+            return Err(BankError::Storage(format!("customer {} already exists", customer.id)));
+        }
+        self.customers.insert(customer.id, customer);
+        Ok(())
+    }
+
+    fn account(&self, id: AccountId) -> Option<&Account> {
+        self.accounts.get(&id)
+    }
+
+    fn account_mut(&mut self, id: AccountId) -> Option<&mut Account> {
+        self.accounts.get_mut(&id)
+    }
+
+    fn accounts(&self) -> Vec<&Account> {
+        self.accounts.values().collect()
+    }
+
+    fn insert_account(&mut self, account: Account) -> Result<(), BankError> {
+        if self.accounts.contains_key(&account.id) {
+            return Err(BankError::Storage(format!("account {} already exists", account.id)));
+        }
+        self.accounts.insert(account.id, account);
+        Ok(())
+    }
+
+    fn record_transaction(&mut self, account_id: AccountId, tx: Transaction, balance: Money) -> Result<(), BankError> {
+        self.account_mut(account_id)
+            .ok_or(BankError::AccountNotFound(account_id))?
+            .record(tx, balance);
+        Ok(())
+    }
+
+    fn scheduled_transfer(&self, id: ScheduleId) -> Option<&ScheduledTransfer> {
+        self.scheduled_transfers.get(&id)
+    }
+
+    fn scheduled_transfer_mut(&mut self, id: ScheduleId) -> Option<&mut ScheduledTransfer> {
+        self.scheduled_transfers.get_mut(&id)
+    }
+
+    fn scheduled_transfers(&self) -> Vec<&ScheduledTransfer> {
+        self.scheduled_transfers.values().collect()
+    }
+
+    fn insert_scheduled_transfer(&mut self, schedule: ScheduledTransfer) -> Result<(), BankError> {
+        if self.scheduled_transfers.contains_key(&schedule.id) {
+            return Err(BankError::Storage(format!("scheduled transfer {} already exists", schedule.id)));
+        }
+        self.scheduled_transfers.insert(schedule.id, schedule);
+        Ok(())
+    }
+
+    fn sequences(&self) -> &IdSequences {
+        &self.sequences
+    }
+
+    fn sequences_mut(&mut self) -> &mut IdSequences {
+        &mut self.sequences
+    }
+
+    fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    fn ledger_mut(&mut self) -> &mut Ledger {
+        &mut self.ledger
+    }
+
+    fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+}
+
+/// Records stored in an ID-keyed map.
+trait Identified {
+    type Id: Ord + Copy + std::fmt::Display;
+
+    fn id(&self) -> Self::Id;
+}
+
+impl Identified for Account {
+    type Id = AccountId;
+
+    fn id(&self) -> AccountId {
+        self.id
+    }
+}
+
+impl Identified for ScheduledTransfer {
+    type Id = ScheduleId;
+
+    fn id(&self) -> ScheduleId {
+        self.id
+    }
+}
+
+impl Identified for Customer {
+    type Id = CustomerId;
+
+    fn id(&self) -> CustomerId {
+        self.id
+    }
+}
+
+fn serialize_values<S: Serializer, K, T: Serialize>(map: &BTreeMap<K, T>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(map.values())
+}
+
+fn deserialize_by_id<'de, D, T>(deserializer: D) -> Result<BTreeMap<T::Id, T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Identified,
+{
+    let items = Vec::<T>::deserialize(deserializer)?;
+    let mut map = BTreeMap::new();
+    for item in items {
+        let id = item.id();
+        if map.insert(id, item).is_some() {
+            return Err(serde::de::Error::custom(format!("duplicate ID {}", id)));
+        }
+    }
+    Ok(map)
+}
+
+/// How many flushes [`JsonFileStorage`] takes between snapshots unless told
+/// otherwise.
+const DEFAULT_SNAPSHOT_EVERY: u32 = 100;
+
+/// Keeps everything in memory and rewrites a single JSON file. Without a
+/// journal every event is synced, so the file is rewritten each time. With
+/// one, rewriting costs as much as the whole bank, so the file is written
+/// every so many flushes, on [`Storage::sync`] and when dropped; the events
+/// in between are replayed from the journal on open.
+#[derive(Debug)]
+pub struct JsonFileStorage {
+    path: PathBuf,
+    data: MemoryStorage,
+    snapshot_every: u32,
+    /// Flushes since the file was last written.
+    unsaved: u32,
+}
+
+impl JsonFileStorage {
+    /// Opens the file at `path`, starting empty if it does not exist yet.
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Self, BankError> {
+        let path = path.into();
+        let data = if path.exists() {
+            let json = fs::read(&path).map_err(storage_error)?;
+            serde_json::from_slice(&json).map_err(storage_error)?
+        } else {
+            MemoryStorage::new()
+        };
+        Ok(Self { path, data, snapshot_every: DEFAULT_SNAPSHOT_EVERY, unsaved: 0 })
+    }
+
+    /// Rewrites the file on every `flushes`-th flush; 1 writes every
+    /// journaled event straight away.
+    pub fn with_snapshot_every(mut self, flushes: u32) -> Self {
+        self.snapshot_every = flushes.max(1);
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for JsonFileStorage {
+    fn drop(&mut self) {
+        if self.unsaved > 0 {
+            let _ = self.sync();
+        }
+    }
+}
+
+impl Storage for JsonFileStorage {
+    fn customer(&self, id: CustomerId) -> Option<&Customer> {
+        self.data.customer(id)
+    }
+
+    fn customer_mut(&mut self, id: CustomerId) -> Option<&mut Customer> {
+        self.data.customer_mut(id)
+    }
+
+    fn customers(&self) -> Vec<&Customer> {
+        self.data.customers()
+    }
+
+    fn insert_customer(&mut self, customer: Customer) -> Result<(), BankError> {
+        self.data.insert_customer(customer)
+    }
+
+    fn account(&self, id: AccountId) -> Option<&Account> {
+        self.data.account(id)
+    }
+
+    fn account_mut(&mut self, id: AccountId) -> Option<&mut Account> {
+        self.data.account_mut(id)
+    }
+
+    fn accounts(&self) -> Vec<&Account> {
+        self.data.accounts()
+    }
+
+    fn insert_account(&mut self, account: Account) -> Result<(), BankError> {
+        self.data.insert_account(account)
+    }
+
+    fn record_transaction(&mut self, account_id: AccountId, tx: Transaction, balance: Money) -> Result<(), BankError> {
+        self.data.record_transaction(account_id, tx, balance)
+    }
+
+    fn scheduled_transfer(&self, id: ScheduleId) -> Option<&ScheduledTransfer> {
+        self.data.scheduled_transfer(id)
+    }
+
+    fn scheduled_transfer_mut(&mut self, id: ScheduleId) -> Option<&mut ScheduledTransfer> {
+        self.data.scheduled_transfer_mut(id)
+    }
+
+    fn scheduled_transfers(&self) -> Vec<&ScheduledTransfer> {
+        self.data.scheduled_transfers()
+    }
+
+    fn insert_scheduled_transfer(&mut self, schedule: ScheduledTransfer) -> Result<(), BankError> {
+        self.data.insert_scheduled_transfer(schedule)
+    }
+
+    fn sequences(&self) -> &IdSequences {
+        self.data.sequences()
+    }
+
+    fn sequences_mut(&mut self) -> &mut IdSequences {
+        self.data.sequences_mut()
+    }
+
+    fn ledger(&self) -> &Ledger {
+        self.data.ledger()
+    }
+
+    fn ledger_mut(&mut self) -> &mut Ledger {
+        self.data.ledger_mut()
+    }
+
+    fn settings(&self) -> &Settings {
+        self.data.settings()
+    }
+
+    fn settings_mut(&mut self) -> &mut Settings {
+        self.data.settings_mut()
+    }
+
+    fn flush(&mut self) -> Result<(), BankError> {
+        self.unsaved = self.unsaved.saturating_add(1);
+        if self.unsaved < self.snapshot_every {
+            return Ok(());
+        }
+        self.sync()
+    }
+
+    fn sync(&mut self) -> Result<(), BankError> {
+        write_json_atomic(&self.path, &self.data)?;
+        self.unsaved = 0;
+        Ok(())
+    }
+}
+
+/// Writes `value` as JSON to a temporary file that is synced and then
+/// renamed over `path`, so a crash mid-write never leaves a truncated file.
+pub(crate) fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> Result<(), BankError> {
+    let json = serde_json::to_vec_pretty(value).map_err(storage_error)?;
+
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = Path::new(&tmp_name);
+    let mut file = File::create(tmp_path).map_err(storage_error)?;
+    file.write_all(&json).map_err(storage_error)?;
+    file.sync_all().map_err(storage_error)?;
+    drop(file);
+    fs::rename(tmp_path, path).map_err(storage_error)?;
+
+    // Persist the rename itself. Not every platform can open a directory.
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn storage_error<E: std::fmt::Display>(err: E) -> BankError {
+    BankError::Storage(err.to_string())
+}
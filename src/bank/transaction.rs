@@ -1,26 +1,247 @@
-use chrono::{DateTime, Utc};
+use std::fmt;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Serialize, Deserialize};
+use super::fees::FeeReason;
+use super::{AccountId, AccountNumber, CustomerId, HoldId, Money, TransactionId};
+
+/// Where the request behind a transaction came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Channel {
+    /// A teller at a branch.
+    Teller,
+    /// The terminal interface.
+    Tui,
+    /// A program calling the library directly.
+    #[default]
+    Api,
+    /// A batch run such as interest, fees or standing orders.
+    Scheduler,
+}
+
+impl Channel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Channel::Teller => "Teller",
+            Channel::Tui => "TUI",
+            Channel::Api => "API",
+            Channel::Scheduler => "Scheduler",
+        }
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The other account of a transfer or loan payment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counterparty {
+    pub account_id: AccountId,
+    pub account_number: AccountNumber,
+    /// The customer holding the account, when known.
+    pub holder: Option<String>,
+}
+
+/// What a caller can attach to a deposit, withdrawal or transfer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionDetails {
+    /// Free text shown with the transaction.
+    pub memo: Option<String>,
+    /// An identifier from outside the bank, such as a cheque or invoice
+    /// number.
+    pub reference: Option<String>,
+    /// The parties signing for money to leave the account, checked against
+    /// its signing rule. Ignored for deposits.
+    pub signers: Vec<CustomerId>,
+}
+
+impl TransactionDetails {
+    pub fn with_memo(mut self, memo: impl Into<String>) -> Self {
+        self.memo = Some(memo.into());
+        self
+    }
+
+    pub fn with_reference(mut self, reference: impl Into<String>) -> Self {
+        self.reference = Some(reference.into());
+        self
+    }
+
+    pub fn signed_by(mut self, customer_id: CustomerId) -> Self {
+        self.signers.push(customer_id);
+        self
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum TransactionType {
     Deposit,
     Withdrawal,
-    Transfer { to_account: u64 },
+    /// Outgoing leg of a transfer, recorded on the debited account.
+    Transfer { to_account: AccountId },
+    /// Incoming leg of a transfer, recorded on the credited account.
+    TransferIn { from_account: AccountId },
+    /// Accrued interest paid into the account.
+    Interest,
+    /// Charged for taking the balance below zero.
+    OverdraftFee,
+    /// A fee from the product's fee schedule.
+    Fee { reason: FeeReason },
+    /// Refunds the fee transaction `fee_id`.
+    FeeReversal { fee_id: TransactionId },
+    /// Settles all or part of a hold.
+    HoldCapture { hold_id: HoldId },
+    /// A loan repayment, recorded on the paying account.
+    LoanRepayment { loan_account: AccountId },
+    /// Principal paid back, recorded on the loan account.
+    PrincipalRepaid { from_account: AccountId },
+    /// Interest charged on a credit card balance.
+    InterestCharged,
+    /// Undoes the transaction `original` on the same account. `credit` is
+    /// set when the original took money out, so the reversal puts it back.
+    Reversal { original: TransactionId, credit: bool },
+}
+
+impl TransactionType {
+    /// Whether this is a charge by the bank that can be reversed.
+    pub fn is_fee(&self) -> bool {
+        matches!(self, TransactionType::OverdraftFee | TransactionType::Fee { .. })
+    }
+
+    /// Whether this kind of transaction adds to the account's balance.
+    pub fn is_credit(&self) -> bool {
+        match self {
+            TransactionType::Deposit
+            | TransactionType::TransferIn { .. }
+            | TransactionType::Interest
+            | TransactionType::FeeReversal { .. }
+            | TransactionType::PrincipalRepaid { .. } => true,
+            TransactionType::Reversal { credit, .. } => *credit,
+            TransactionType::Withdrawal
+            | TransactionType::Transfer { .. }
+            | TransactionType::OverdraftFee
+            | TransactionType::Fee { .. }
+            | TransactionType::HoldCapture { .. }
+            | TransactionType::LoanRepayment { .. }
+            | TransactionType::InterestCharged => false,
+        }
+    }
+
+    /// The other account named by this kind of transaction, if any.
+    pub fn counterparty_account(&self) -> Option<AccountId> {
+        match self {
+            TransactionType::Transfer { to_account } => Some(*to_account),
+            TransactionType::TransferIn { from_account } => Some(*from_account),
+            TransactionType::LoanRepayment { loan_account } => Some(*loan_account),
+            TransactionType::PrincipalRepaid { from_account } => Some(*from_account),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
+    #[serde(default)]
+    pub id: TransactionId,
     pub tx_type: TransactionType,
-    pub amount: f64,
+    pub amount: Money,
     pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// The transaction that undid this one, if it has been reversed.
+    #[serde(default)]
+    pub reversed_by: Option<TransactionId>,
+    /// The other leg of a transfer, on the other account. Unknown for older
+    /// data.
+    #[serde(default)]
+    pub paired_with: Option<TransactionId>,
+    /// An identifier from outside the bank.
+    #[serde(default)]
+    pub reference: Option<String>,
+    #[serde(default)]
+    pub counterparty: Option<Counterparty>,
+    /// The account's balance right after posting; unknown for older data.
+    #[serde(default)]
+    pub balance_after: Option<Money>,
+    /// The day the money counts from for balances and interest, the date of
+    /// `timestamp`. Unknown for older data.
+    #[serde(default)]
+    pub value_date: Option<NaiveDate>,
+    /// The day the transaction was entered, later than the value date for
+    /// backdated postings such as interest. Unknown for older data.
+    #[serde(default)]
+    pub booking_date: Option<NaiveDate>,
+    /// Unknown for older data.
+    #[serde(default)]
+    pub channel: Option<Channel>,
+    /// Moved by the bank as part of another operation, such as an overdraft
+    /// protection pull, a loan disbursement or funding or paying out a
+    /// certificate, rather than on its own instruction. Not charged for as
+    /// a transfer or withdrawal.
+    #[serde(default)]
+    pub internal: bool,
 }
 
 impl Transaction {
-    pub fn new(tx_type: TransactionType, amount: f64) -> Self {
+    /// A transaction stamped now, valued and booked today.
+    pub fn new(tx_type: TransactionType, amount: Money) -> Self {
+        let now = Utc::now();
         Self {
+            id: TransactionId::default(),
             tx_type,
             amount,
-            timestamp: Utc::now(),
+            timestamp: now,
+            memo: None,
+            reversed_by: None,
+            paired_with: None,
+            reference: None,
+            counterparty: None,
+            balance_after: None,
+            value_date: Some(now.date_naive()),
+            booking_date: Some(now.date_naive()),
+            channel: None,
+            internal: false,
         }
     }
+
+    /// Sets the time the transaction takes effect, moving the value and
+    /// booking dates with it.
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = timestamp;
+        self.value_date = Some(timestamp.date_naive());
+        self.booking_date = Some(timestamp.date_naive());
+        self
+    }
+
+    pub fn with_memo(mut self, memo: Option<String>) -> Self {
+        self.memo = memo;
+        self
+    }
+
+    pub fn with_reference(mut self, reference: Option<String>) -> Self {
+        self.reference = reference;
+        self
+    }
+
+    pub fn with_counterparty(mut self, counterparty: Option<Counterparty>) -> Self {
+        self.counterparty = counterparty;
+        self
+    }
+
+    pub fn with_channel(mut self, channel: Channel) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    pub fn as_internal(mut self) -> Self {
+        self.internal = true;
+        self
+    }
+
+    /// The day the transaction was entered, taken as the date of
+    /// `timestamp` for older data.
+    pub fn booked_on(&self) -> NaiveDate {
+        self.booking_date.unwrap_or_else(|| self.timestamp.date_naive())
+    }
 }
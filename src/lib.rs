@@ -1,4 +1,4 @@
 pub mod bank;
 pub mod tui;
 
-pub use bank::{Bank, Account, Customer, Transaction};
\ No newline at end of file
+pub use bank::{Bank, Account, AccountId, Customer, CustomerId, Currency, Money, Transaction, TransactionId};
\ No newline at end of file
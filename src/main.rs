@@ -1,8 +1,3 @@
-mod bank;
-mod tui;
-
-pub use bank::*;
-
 use banksystem_rs::tui::BankTui;
 
 fn main() {
@@ -57,7 +57,7 @@ impl FormField {
         (self.validation)(&self.value)
     }
 
-    pub fn render(&self, focused: bool) -> Spans {
+    pub fn render(&self, focused: bool) -> Spans<'_> {
         let style = if focused {
             Style::default().fg(Color::Yellow)
         } else {
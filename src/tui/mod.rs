@@ -2,26 +2,63 @@ mod forms;
 mod widgets;
 mod screens;
 
-use crate::bank::Bank;
+use std::path::PathBuf;
+use crate::bank::{Bank, BankError, Channel};
 pub use forms::FormField;
 pub use screens::Screen;
+use widgets::StatusBar;
+
+/// Data file used when `BANKSYSTEM_DATA` is not set.
+pub const DEFAULT_DATA_FILE: &str = "bank.json";
 
 pub struct BankTui {
     bank: Bank,
     current_screen: Screen,
+    status: StatusBar,
+    data_file: Option<PathBuf>,
 }
 
 impl BankTui {
     pub fn new() -> Self {
+        let mut bank = Bank::new();
+        bank.set_channel(Channel::Tui);
         Self {
-            bank: Bank::new(),
+            bank,
             current_screen: Screen::MainMenu,
+            status: StatusBar::new(),
+            data_file: None,
+        }
+    }
+
+    /// Opens the bank saved at `path`, or starts an empty one there if the
+    /// file does not exist yet.
+    pub fn open(path: PathBuf) -> Result<Self, BankError> {
+        let mut tui = Self::new();
+        if path.exists() {
+            tui.bank = Bank::load(&path)?;
+            tui.bank.set_channel(Channel::Tui);
+            tui.set_status(format!("Loaded {}", path.display()));
         }
+        tui.data_file = Some(path);
+        Ok(tui)
     }
 
     pub fn run() -> std::io::Result<()> {
-        let mut tui = BankTui::new();
-        screens::run_tui(&mut tui)
+        let path = std::env::var_os("BANKSYSTEM_DATA")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_DATA_FILE));
+        let mut tui = BankTui::open(path).map_err(std::io::Error::other)?;
+        let result = screens::run_tui(&mut tui);
+        tui.save().map_err(std::io::Error::other)?;
+        result
+    }
+
+    /// Writes the bank to its data file, if it has one.
+    pub fn save(&self) -> Result<(), BankError> {
+        match &self.data_file {
+            Some(path) => self.bank.save(path),
+            None => Ok(()),
+        }
     }
 
     pub fn get_bank(&self) -> &Bank {
@@ -39,4 +76,18 @@ impl BankTui {
     pub fn get_screen(&self) -> &Screen {
         &self.current_screen
     }
+
+    pub fn set_status(&mut self, message: String) {
+        self.status.set_message(message);
+    }
+
+    pub fn get_status(&self) -> &str {
+        &self.status.message
+    }
+}
+
+impl Default for BankTui {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -5,12 +5,13 @@ use tui::text::{Spans, Span};
 use crossterm::event::{Event, KeyCode};
 use crossterm::terminal::{enable_raw_mode, disable_raw_mode};
 use super::{BankTui, forms::{Form, FormField}};
+use crate::bank::{AccountId, AccountProduct, AccountStatus, Bank, BankError, Money};
+use std::str::FromStr;
 use std::io;
 use std::sync::Arc;
 
 mod renderer {
     use super::*;
-    use crate::bank::Bank;
 
     pub fn render_customer_list<'a>(bank: &'a Bank) -> List<'a> {
         let customers = bank.list_customers();
@@ -34,11 +35,15 @@ mod renderer {
         let items: Vec<ListItem> = accounts
             .iter()
             .map(|a| {
-                ListItem::new(Spans::from(vec![
-                    Span::raw(format!("ID: {} - ", a.id)),
+                let mut spans = vec![
+                    Span::raw(format!("ID: {} - No. {} - ", a.id, a.number)),
                     Span::styled(&a.name, Style::default().fg(Color::Green)),
-                    Span::raw(format!(" (${:.2})", a.balance)),
-                ]))
+                    Span::raw(format!(" ({})", a.balance)),
+                ];
+                if a.status != AccountStatus::Active {
+                    spans.push(Span::styled(format!(" [{}]", a.status), Style::default().fg(Color::Red)));
+                }
+                ListItem::new(Spans::from(spans))
             })
             .collect();
 
@@ -47,7 +52,7 @@ mod renderer {
             .highlight_style(Style::default().fg(Color::Yellow))
     }
 
-    pub fn render_account_details<'a>(bank: &'a Bank, id: u64) -> Paragraph<'a> {
+    pub fn render_account_details<'a>(bank: &'a Bank, id: AccountId) -> Paragraph<'a> {
         if let Some(account) = bank.get_account(id) {
             let customer = bank.get_customer(account.customer_id)
                 .map(|c| c.name.as_str())
@@ -55,15 +60,29 @@ mod renderer {
 
             let history: Vec<String> = account.get_history()
                 .iter()
-                .map(|t| format!("{:?}: ${:.2}", t.tx_type, t.amount))
+                .map(|t| format!("{:?}: {}", t.tx_type, t.amount))
                 .collect();
 
+            let accrued = bank.accrued_interest(account.id)
+                .map(|m| m.to_string())
+                .unwrap_or_default();
+            let held = account.held_amount()
+                .map(|m| m.to_string())
+                .unwrap_or_default();
+            let available = account.available_balance()
+                .map(|m| m.to_string())
+                .unwrap_or_default();
             let content = format!(
-                "Account ID: {}\nCustomer: {}\nType: {}\nBalance: ${:.2}\n\nTransaction History:\n{}",
+                "Account ID: {}\nAccount No.: {}\nCustomer: {}\nType: {}\nStatus: {}\nLedger balance: {}\nHeld: {}\nAvailable: {}\nAccrued interest: {}\n\nTransaction History:\n{}",
                 account.id,
+                account.number,
                 customer,
-                account.account_type,
-                account.balance,
+                account.product,
+                account.status,
+                account.ledger_balance(),
+                held,
+                available,
+                accrued,
                 history.join("\n")
             );
 
@@ -76,14 +95,6 @@ mod renderer {
                 .style(Style::default().fg(Color::Red))
         }
     }
-
-    pub fn render_transfer_form<'a>() -> Paragraph<'a> {
-        let content = "Transfer funds form\n\nFrom Account: [    ]\nTo Account:   [    ]\nAmount:      [    ]";
-        
-        Paragraph::new(content)
-            .block(Block::default().title("Transfer").borders(Borders::ALL))
-            .style(Style::default().fg(Color::Green))
-    }
 }
 
 use renderer::*;
@@ -95,8 +106,8 @@ pub enum Screen {
     NewCustomer(Form),
     NewAccount(Form),
     AccountList,
-    AccountDetails(u64),
-    Transfer,
+    AccountDetails(AccountId),
+    Transfer(Form),
 }
 
 pub fn run_tui(tui: &mut BankTui) -> io::Result<()> {
@@ -130,10 +141,49 @@ fn create_account_form() -> Form {
     Form::new("Create New Account", vec![
         FormField::new("Customer ID", true, 20)
             .with_validation(Arc::new(|s: &str| s.parse::<u64>().is_ok())),
-        FormField::new("Account Type", true, 20),
+        FormField::new("Account Type (Checking, Savings, Money Market)", true, 25)
+            .with_validation(Arc::new(|s: &str| matches!(
+                s.parse::<AccountProduct>(),
+                Ok(AccountProduct::Checking | AccountProduct::Savings | AccountProduct::MoneyMarket)
+            ))),
     ])
 }
 
+/// Account numbers are only checked for stray characters here; numbers
+/// issued under an earlier format are still valid, so the lookup decides.
+fn create_transfer_form() -> Form {
+    let is_number: Arc<dyn Fn(&str) -> bool + Send + Sync> =
+        Arc::new(|s: &str| s.chars().all(|c| c.is_ascii_digit() || c == ' ' || c == '-'));
+    Form::new("Transfer Funds", vec![
+        FormField::new("From Account No.", true, 24).with_validation(is_number.clone()),
+        FormField::new("To Account No.", true, 24).with_validation(is_number),
+        FormField::new("Amount", true, 20)
+            .with_validation(Arc::new(|s: &str| s.chars().all(|c| c.is_ascii_digit() || c == '.'))),
+        FormField::new("Memo", false, 50),
+    ])
+}
+
+fn parse_id<T: FromStr>(field: &'static str, value: &str) -> Result<T, BankError> {
+    value.parse::<T>().map_err(|_| BankError::InvalidField {
+        field,
+        reason: "must be a number".to_string(),
+    })
+}
+
+/// Resolves an account number typed into `field`. Any number already
+/// issued is found, whatever format it was issued under; anything else is
+/// checked against the current format so a typo is reported as one.
+fn find_account(bank: &Bank, field: &'static str, value: &str) -> Result<AccountId, BankError> {
+    if let Some(account) = bank.find_account_by_number(value) {
+        return Ok(account.id);
+    }
+    let number = bank.account_number_format().parse(value).map_err(|err| match err {
+        BankError::InvalidField { reason, .. } => BankError::InvalidField { field, reason },
+        err => err,
+    })?;
+    Err(BankError::InvalidField { field, reason: format!("no account numbered {}", number) })
+}
+
 fn handle_screen<B: tui::backend::Backend>(
     terminal: &mut tui::Terminal<B>,
     tui: &mut BankTui,
@@ -184,13 +234,14 @@ fn handle_screen<B: tui::backend::Backend>(
                 f.render_widget(Clear, area);
                 f.render_widget(details, area);
             },
-            Screen::Transfer => {
-                let form = render_transfer_form();
+            Screen::Transfer(form) => {
                 let area = centered_rect(60, 40, chunks[1]);
                 f.render_widget(Clear, area);
-                f.render_widget(form, area);
+                form.render(f, area);
             }
         }
+
+        f.render_widget(render_status(tui.get_status()), chunks[2]);
     })?;
 
     if let Event::Key(key) = crossterm::event::read()? {
@@ -201,27 +252,44 @@ fn handle_screen<B: tui::backend::Backend>(
                 KeyCode::Char('2') => tui.set_screen(Screen::CustomerList),
                 KeyCode::Char('3') => tui.set_screen(Screen::NewAccount(create_account_form())),
                 KeyCode::Char('4') => tui.set_screen(Screen::AccountList),
+                KeyCode::Char('5') => tui.set_screen(Screen::Transfer(create_transfer_form())),
                 KeyCode::Char('x') | KeyCode::Char('X') => return Ok(false),
                 _ => {}
             },
             Screen::NewCustomer(mut form) => {
                 handle_form_input(key.code, &mut form, tui, Screen::NewCustomer, |values, bank| {
-                    bank.create_customer(
+                    let id = bank.create_customer(
                         values[0].clone(),
                         values[1].clone(),
                         values[2].clone(),
                         values[3].clone(),
-                    );
+                    )?;
+                    Ok(format!("Created customer {}", id))
                 });
             },
             Screen::NewAccount(mut form) => {
                 handle_form_input(key.code, &mut form, tui, Screen::NewAccount, |values, bank| {
-                    if let Ok(customer_id) = values[0].parse::<u64>() {
-                        bank.create_account(customer_id, values[1].clone());
-                    }
+                    let customer_id = parse_id("customer ID", &values[0])?;
+                    let product: AccountProduct = values[1].parse()?;
+                    let id = bank.create_account(customer_id, product)?;
+                    let number = &bank.get_account(id).expect("account just created").number;
+                    Ok(format!("Created account {} with number {}", id, number))
+                });
+            },
+            Screen::Transfer(mut form) => {
+                handle_form_input(key.code, &mut form, tui, Screen::Transfer, |values, bank| {
+                    let from_id = find_account(bank, "from account", &values[0])?;
+                    let to_id = find_account(bank, "to account", &values[1])?;
+                    let currency = bank.get_account(from_id)
+                        .ok_or(BankError::AccountNotFound(from_id))?
+                        .currency();
+                    let amount = Money::parse(&values[2], currency)?;
+                    let memo = Some(values[3].clone()).filter(|m| !m.is_empty());
+                    bank.transfer(from_id, to_id, amount, memo)?;
+                    Ok(format!("Transferred {} from account {} to account {}", amount, values[0], values[1]))
                 });
             },
-            Screen::CustomerList | Screen::AccountList | Screen::AccountDetails(_) | Screen::Transfer => {
+            Screen::CustomerList | Screen::AccountList | Screen::AccountDetails(_) => {
                 if key.code == KeyCode::Esc {
                     tui.set_screen(Screen::MainMenu);
                 }
@@ -239,7 +307,7 @@ fn handle_form_input<F, S>(
     screen_constructor: S,
     submit_handler: F
 ) where 
-    F: FnOnce(Vec<String>, &mut crate::bank::Bank),
+    F: FnOnce(Vec<String>, &mut crate::bank::Bank) -> Result<String, BankError>,
     S: Fn(Form) -> Screen
 {
     match key_code {
@@ -259,13 +327,21 @@ fn handle_form_input<F, S>(
             form.backspace();
             tui.set_screen(screen_constructor(form.clone()));
         },
-        KeyCode::Enter => {
-            if form.is_valid() {
-                let values = form.get_values();
-                submit_handler(values, tui.get_bank_mut());
-                tui.set_screen(Screen::MainMenu);
+        KeyCode::Enter if form.is_valid() => {
+            let values = form.get_values();
+            match submit_handler(values, tui.get_bank_mut()) {
+                Ok(message) => {
+                    match tui.save() {
+                        Ok(()) => tui.set_status(message),
+                        Err(err) => tui.set_status(format!("Error: {} but saving failed: {}", message, err)),
+                    }
+                    tui.set_screen(Screen::MainMenu);
+                }
+                // Stay on the form so the user can correct the input.
+                Err(err) => tui.set_status(format!("Error: {}", err)),
             }
         },
+        KeyCode::Enter => tui.set_status("Please fill in all required fields correctly".to_string()),
         KeyCode::Esc => tui.set_screen(Screen::MainMenu),
         _ => {}
     }
@@ -279,7 +355,7 @@ fn render_title(screen: &Screen) -> Paragraph<'static> {
         Screen::CustomerList => "Customer List",
         Screen::AccountList => "Account List",
         Screen::AccountDetails(_) => "Account Details",
-        Screen::Transfer => "Transfer Funds",
+        Screen::Transfer(_) => "Transfer Funds",
     };
 
     Paragraph::new(vec![
@@ -290,12 +366,20 @@ fn render_title(screen: &Screen) -> Paragraph<'static> {
     .style(Style::default())
 }
 
+fn render_status(message: &str) -> Paragraph<'_> {
+    let color = if message.starts_with("Error") { Color::Red } else { Color::Green };
+    Paragraph::new(message)
+        .style(Style::default().fg(color))
+        .block(Block::default().borders(Borders::ALL))
+}
+
 fn render_main_menu<B: tui::backend::Backend>(f: &mut tui::Frame<B>, area: Rect) {
-    let menu_items = vec![
+    let menu_items = [
         "1. Create New Customer",
         "2. View Customers",
         "3. Create New Account",
         "4. View Accounts",
+        "5. Transfer Funds",
         "",
         "X. Exit System",
     ];
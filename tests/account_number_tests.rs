@@ -0,0 +1,129 @@
+mod common;
+
+use banksystem_rs::bank::{AccountId, AccountNumberFormat, AccountProduct, Bank, BankError, CheckDigit, CustomerId};
+
+fn bank_with_customer() -> (Bank, CustomerId) {
+    let mut bank = Bank::new();
+    let customer_id = bank.create_customer(
+        "Number User".to_string(),
+        "1 Digit Rd".to_string(),
+        "555-0300".to_string(),
+        "number@example.com".to_string(),
+    ).unwrap();
+    (bank, customer_id)
+}
+
+#[test]
+fn test_luhn_check_digit() {
+    // The textbook Luhn example, 7992739871 -> 3.
+    let format = AccountNumberFormat::new("799", 7, CheckDigit::Luhn).unwrap();
+    let number = format.number_for(AccountId(2_739_871)).unwrap();
+    assert_eq!(number.as_str(), "79927398713");
+    assert_eq!(format.parse("799-2739871-3").unwrap(), number);
+    assert_eq!(format.parse("799 273 9871 3").unwrap(), number);
+}
+
+#[test]
+fn test_mod97_check_digits() {
+    let format = AccountNumberFormat::new("42", 6, CheckDigit::Mod97).unwrap();
+    let number = format.number_for(AccountId(1234)).unwrap();
+    assert_eq!(number.as_str().len(), format.number_len());
+    assert!(number.as_str().starts_with("42001234"));
+    // ISO 7064 MOD 97-10: the whole number leaves a remainder of 1.
+    assert_eq!(number.as_str().parse::<u64>().unwrap() % 97, 1);
+    assert_eq!(format.parse(number.as_str()).unwrap(), number);
+}
+
+#[test]
+fn test_typos_are_rejected() {
+    for check in [CheckDigit::Luhn, CheckDigit::Mod97] {
+        let format = AccountNumberFormat::new("100", 7, check).unwrap();
+        let number = format.number_for(AccountId(4_205_318)).unwrap();
+        let digits: Vec<char> = number.as_str().chars().collect();
+
+        for i in 0..digits.len() {
+            let mut typo = digits.clone();
+            typo[i] = if typo[i] == '9' { '0' } else { char::from(typo[i] as u8 + 1) };
+            let typo: String = typo.into_iter().collect();
+            assert!(format.parse(&typo).is_err(), "{:?} accepted {}", check, typo);
+        }
+        // Luhn misses a swapped 0 and 9; mod 97 catches every adjacent swap.
+        for i in 0..digits.len() - 1 {
+            let (a, b) = (digits[i], digits[i + 1]);
+            if a == b || (check == CheckDigit::Luhn && matches!((a, b), ('0', '9') | ('9', '0'))) {
+                continue;
+            }
+            let mut swapped = digits.clone();
+            swapped.swap(i, i + 1);
+            let swapped: String = swapped.into_iter().collect();
+            assert!(format.parse(&swapped).is_err(), "{:?} accepted {}", check, swapped);
+        }
+    }
+
+    let format = AccountNumberFormat::default();
+    assert!(matches!(format.parse("100000001x"), Err(BankError::InvalidField { field: "account number", .. })));
+    assert!(format.parse("1000001").is_err());
+    assert!(format.parse(format.number_for(AccountId(1)).unwrap().as_str()).is_ok());
+    assert!(AccountNumberFormat::new("A1", 7, CheckDigit::Luhn).is_err());
+    assert!(AccountNumberFormat::new("1", 0, CheckDigit::Luhn).is_err());
+}
+
+#[test]
+fn test_accounts_are_numbered_and_found() {
+    let (mut bank, customer_id) = bank_with_customer();
+    let checking = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    bank.set_account_number_format(AccountNumberFormat::new("205", 5, CheckDigit::Mod97).unwrap()).unwrap();
+    let savings = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+
+    let checking_number = bank.get_account(checking).unwrap().number.clone();
+    let savings_number = bank.get_account(savings).unwrap().number.clone();
+    assert_eq!(checking_number, AccountNumberFormat::default().number_for(checking).unwrap());
+    assert!(savings_number.as_str().starts_with("20500002"));
+
+    // Numbers issued under an earlier format still resolve.
+    assert_eq!(bank.find_account_by_number(checking_number.as_str()).unwrap().id, checking);
+    let spaced = format!("{} {}", &savings_number.as_str()[..3], &savings_number.as_str()[3..]);
+    assert_eq!(bank.find_account_by_number(&spaced).unwrap().id, savings);
+    assert!(bank.find_account_by_number("20500003").is_none());
+
+    // Too many accounts for the sequence width is an error, not a wrap.
+    bank.set_account_number_format(AccountNumberFormat::new("9", 1, CheckDigit::Luhn).unwrap()).unwrap();
+    for _ in 0..7 {
+        bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    }
+    assert!(matches!(
+        bank.create_account(customer_id, AccountProduct::Checking),
+        Err(BankError::InvalidField { field: "account number", .. })
+    ));
+}
+
+#[test]
+fn test_numbers_survive_save_and_reopen() {
+    let (mut bank, customer_id) = bank_with_customer();
+    let id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    let number = bank.get_account(id).unwrap().number.clone();
+
+    let reopened = Bank::with_storage(bank.storage().clone()).unwrap();
+    assert_eq!(reopened.find_account_by_number(number.as_str()).unwrap().id, id);
+
+    let json = serde_json::to_value(&bank).unwrap();
+    assert_eq!(json["accounts"][0]["number"], number.as_str());
+}
+
+#[test]
+fn test_format_survives_save_and_replay() {
+    let format = AccountNumberFormat::new("205", 5, CheckDigit::Mod97).unwrap();
+    let (mut replayed, customer_id) = common::replayed(|bank| {
+        bank.set_account_number_format(format.clone()).unwrap();
+        common::customer(bank, "Number User")
+    });
+
+    assert_eq!(replayed.account_number_format(), &format);
+    let id = replayed.create_account(customer_id, AccountProduct::Checking).unwrap();
+    assert_eq!(replayed.get_account(id).unwrap().number, format.number_for(id).unwrap());
+
+    // A saved format is checked like a new one.
+    let mut json = serde_json::to_value(replayed.storage()).unwrap();
+    json["settings"]["account_number_format"]["branch"] = "2x5".into();
+    assert!(serde_json::from_value::<Bank>(json).is_err());
+}
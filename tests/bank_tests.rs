@@ -1,53 +1,138 @@
-use banksystem_rs::bank::{Bank, Account};
+mod common;
+
+use std::collections::HashSet;
+use banksystem_rs::bank::{AccountId, AccountProduct, Bank, BankError, Currency, CustomerId, Money, TransactionId};
+use common::usd;
 
 #[test]
 fn test_account_creation() {
     let mut bank = Bank::new();
-    let id = bank.create_account("Alice".to_string());
-    assert_eq!(id, 1);
+    let customer_id = common::customer(&mut bank, "Alice");
+    let id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    // Accounts are numbered separately from customers.
+    assert_eq!(id, AccountId(1));
     let account = bank.get_account(id).unwrap();
-    assert_eq!(account.name, "Alice");
-    assert_eq!(account.balance, 0.0);
+    assert_eq!(account.name, "Checking Account");
+    assert_eq!(account.customer_id, customer_id);
+    assert_eq!(account.balance, usd(0));
 }
 
 #[test]
 fn test_deposit() {
     let mut bank = Bank::new();
-    let id = bank.create_account("Bob".to_string());
-    let acc = bank.get_account_mut(id).unwrap();
-    acc.deposit(100.0);
-    assert_eq!(acc.balance, 100.0);
+    let customer_id = common::customer(&mut bank, "Bob");
+    let id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    bank.deposit(id, usd(100)).unwrap();
+    bank.deposit(id, usd(50)).unwrap();
+    let account = bank.get_account(id).unwrap();
+    assert_eq!(account.balance, usd(150));
+    assert_eq!(account.get_history().len(), 2);
 }
 
 #[test]
 fn test_withdrawal() {
     let mut bank = Bank::new();
-    let id = bank.create_account("Carol".to_string());
-    let acc = bank.get_account_mut(id).unwrap();
-    acc.deposit(100.0);
-    let result = acc.withdraw(40.0);
-    assert!(result);
-    assert_eq!(acc.balance, 60.0);
-    let fail = acc.withdraw(100.0);
-    assert!(!fail);
-    assert_eq!(acc.balance, 60.0);
+    let customer_id = common::customer(&mut bank, "Carol");
+    let id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    bank.deposit(id, usd(100)).unwrap();
+    let result = bank.withdraw(id, usd(40));
+    assert!(result.is_ok());
+    assert_eq!(bank.get_account(id).unwrap().balance, usd(60));
+    let fail = bank.withdraw(id, usd(100));
+    assert_eq!(fail, Err(BankError::InsufficientFunds {
+        account_id: id,
+        requested: usd(100),
+        available: usd(60),
+    }));
+    assert_eq!(bank.get_account(id).unwrap().balance, usd(60));
 }
 
 #[test]
 fn test_transfer() {
     let mut bank = Bank::new();
-    let id1 = bank.create_account("Dave".to_string());
-    let id2 = bank.create_account("Eve".to_string());
-    {
-        let acc1 = bank.get_account_mut(id1).unwrap();
-        acc1.deposit(200.0);
+    let customer_id = common::customer(&mut bank, "Dave");
+    let id1 = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    let id2 = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.deposit(id1, usd(200)).unwrap();
+    let result = bank.transfer(id1, id2, usd(50), None);
+    assert!(result.is_ok());
+    assert_eq!(bank.get_account(id1).unwrap().balance, usd(150));
+    assert_eq!(bank.get_account(id2).unwrap().balance, usd(50));
+}
+
+#[test]
+fn test_create_account_unknown_customer() {
+    let mut bank = Bank::new();
+    let result = bank.create_account(CustomerId(42), AccountProduct::Checking);
+    assert_eq!(result, Err(BankError::CustomerNotFound(CustomerId(42))));
+    assert!(bank.list_accounts().is_empty());
+}
+
+#[test]
+fn test_bank_level_errors() {
+    let mut bank = Bank::new();
+    let customer_id = common::customer(&mut bank, "Frank");
+    let id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+
+    assert_eq!(bank.deposit(AccountId(999), usd(10)), Err(BankError::AccountNotFound(AccountId(999))));
+    assert_eq!(bank.deposit(id, usd(0)), Err(BankError::InvalidAmount(usd(0))));
+    assert!(matches!(
+        bank.deposit(id, Money::from_major(10, Currency::EUR).unwrap()),
+        Err(BankError::Money(_))
+    ));
+    bank.deposit(id, usd(10)).unwrap();
+    assert!(matches!(bank.withdraw(id, usd(11)), Err(BankError::InsufficientFunds { .. })));
+    assert_eq!(bank.get_account(id).unwrap().balance, usd(10));
+}
+
+#[test]
+fn test_indexed_listings_keep_creation_order() {
+    let mut bank = Bank::new();
+    let mut customers = vec![];
+    for name in ["Gina", "Hal", "Ivy"] {
+        customers.push(common::customer(&mut bank, name));
+    }
+
+    // Interleave account creation across customers.
+    let mut expected: Vec<Vec<AccountId>> = vec![vec![]; customers.len()];
+    for round in 0..2_000 {
+        let owner = round % customers.len();
+        let id = bank.create_account(customers[owner], AccountProduct::Checking).unwrap();
+        expected[owner].push(id);
+    }
+
+    for (owner, customer_id) in customers.iter().enumerate() {
+        let ids: Vec<AccountId> = bank.list_customer_accounts(*customer_id).iter().map(|a| a.id).collect();
+        assert_eq!(ids, expected[owner]);
     }
-    let (acc1, acc2) = {
-        let (left, right) = bank.accounts.split_at_mut(1);
-        (&mut left[0], &mut right[0])
-    };
-    let result = acc1.transfer(50.0, acc2);
-    assert!(result);
-    assert_eq!(acc1.balance, 150.0);
-    assert_eq!(acc2.balance, 50.0);
+    let all: Vec<AccountId> = bank.list_accounts().iter().map(|a| a.id).collect();
+    assert!(all.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(all.len(), 2_000);
+    let names: Vec<&str> = bank.list_customers().iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["Gina", "Hal", "Ivy"]);
+
+    // The secondary index is rebuilt when storage is reopened.
+    let reopened = Bank::with_storage(bank.storage().clone()).unwrap();
+    let ids: Vec<AccountId> = reopened.list_customer_accounts(customers[1]).iter().map(|a| a.id).collect();
+    assert_eq!(ids, expected[1]);
+    assert!(reopened.list_customer_accounts(CustomerId(999)).is_empty());
+}
+
+#[test]
+fn test_transaction_ids_are_unique_across_accounts() {
+    let mut bank = Bank::new();
+    let customer_id = common::customer(&mut bank, "Jack");
+    let checking = common::account(&mut bank, customer_id, AccountProduct::Checking, 100);
+    let savings = common::account(&mut bank, customer_id, AccountProduct::Savings, 0);
+    bank.transfer(checking, savings, usd(30), None).unwrap();
+    bank.withdraw(savings, usd(10)).unwrap();
+
+    let ids: Vec<TransactionId> = bank.list_accounts()
+        .iter()
+        .flat_map(|a| a.get_history().iter().map(|tx| tx.id))
+        .collect();
+    assert_eq!(ids.len(), 4);
+    assert_eq!(ids.iter().collect::<HashSet<_>>().len(), 4);
+    assert!(!ids.contains(&TransactionId::default()));
+    assert_eq!(bank.get_account(savings).unwrap().get_history()[1].id, TransactionId(4));
 }
@@ -0,0 +1,204 @@
+mod common;
+
+use banksystem_rs::bank::{AccountId, AccountProduct, Bank, BankError, Currency, Money, TransactionType};
+use banksystem_rs::bank::card::CardTerms;
+use banksystem_rs::bank::fees::FeeReason;
+use banksystem_rs::bank::interest::Rate;
+use banksystem_rs::bank::ledger::LedgerAccount;
+use common::{at, cents, date, usd};
+
+/// 24% APR, cycles closing on the 15th, payment due 20 days later, a
+/// minimum of 2% or 25 and a late fee of 30.
+fn terms() -> CardTerms {
+    CardTerms::new(Rate::from_bps(2_400), 15)
+        .with_grace_days(20)
+        .with_minimum_payment(Rate::from_bps(200), Some(usd(25)))
+        .with_late_fee(usd(30))
+}
+
+/// A customer's checking account holding 5,000 and a card with a 1,000
+/// limit, both opened on 5 January 2024.
+fn cardholder(bank: &mut Bank) -> (AccountId, AccountId) {
+    bank.set_clock(at(2024, 1, 5));
+    let customer_id = common::customer(bank, "Card Member");
+    let checking_id = common::account(bank, customer_id, AccountProduct::Checking, 5_000);
+    let card_id = bank.open_credit_card(customer_id, usd(1_000), terms()).unwrap();
+    (checking_id, card_id)
+}
+
+#[test]
+fn test_terms_and_minimum_payment() {
+    let terms = terms();
+    assert_eq!(terms.minimum_payment_on(usd(5_000)).unwrap(), usd(100));
+    assert_eq!(terms.minimum_payment_on(usd(300)).unwrap(), usd(25));
+    assert_eq!(terms.minimum_payment_on(usd(10)).unwrap(), usd(10));
+    assert_eq!(terms.minimum_payment_on(usd(-10)).unwrap(), usd(0));
+    assert_eq!(terms.minimum_payment_on(cents(234_567)).unwrap(), cents(4_692));
+
+    assert!(matches!(
+        CardTerms::new(Rate::from_bps(2_000), 29).validate(),
+        Err(BankError::InvalidField { field: "statement cycle day", .. })
+    ));
+    assert!(CardTerms::new(Rate::from_bps(2_000), 1).with_grace_days(28).validate().is_err());
+    assert!(CardTerms::new(Rate::from_bps(2_000), 1).with_minimum_payment(Rate::ZERO, None).validate().is_err());
+    assert!(CardTerms::new(Rate::from_bps(2_000), 1).with_late_fee(usd(0)).validate().is_err());
+}
+
+#[test]
+fn test_credit_limit() {
+    let mut bank = Bank::new();
+    let (checking, card) = cardholder(&mut bank);
+    assert_eq!(bank.get_account(card).unwrap().available_balance().unwrap(), usd(1_000));
+
+    bank.withdraw(card, usd(600)).unwrap();
+    assert_eq!(
+        bank.withdraw(card, usd(500)),
+        Err(BankError::InsufficientFunds { account_id: card, requested: usd(500), available: usd(400) })
+    );
+    bank.transfer(checking, card, usd(100), None).unwrap();
+    assert_eq!(bank.get_account(card).unwrap().available_balance().unwrap(), usd(500));
+    assert!(bank.credit_card(checking).is_none());
+
+    // A card that can't be set up leaves no account behind.
+    let customer_id = bank.get_account(checking).unwrap().customer_id;
+    assert!(matches!(
+        bank.open_credit_card(customer_id, Money::from_major(500, Currency::EUR).unwrap(), terms()),
+        Err(BankError::InvalidField { field: "credit card", .. })
+    ));
+    assert_eq!(bank.list_customer_accounts(customer_id).len(), 2);
+}
+
+#[test]
+fn test_paid_in_full_avoids_interest() {
+    let mut bank = Bank::new();
+    let (checking, card) = cardholder(&mut bank);
+    bank.set_clock(at(2024, 1, 10));
+    bank.withdraw(card, usd(300)).unwrap();
+
+    bank.close_card_cycles(date(2024, 1, 15)).unwrap();
+    let statement = bank.credit_card(card).unwrap().last_statement().unwrap().clone();
+    assert_eq!(statement.number, 1);
+    assert_eq!((statement.period_start, statement.closing_date), (date(2024, 1, 5), date(2024, 1, 15)));
+    assert_eq!(statement.due_date, date(2024, 2, 4));
+    assert_eq!(statement.previous_balance, usd(0));
+    assert_eq!(statement.statement_balance, usd(300));
+    assert_eq!(statement.minimum_payment, usd(25));
+    assert_eq!(statement.transactions.len(), 1);
+    assert_eq!(statement.paid_in_full(), None);
+
+    bank.set_clock(at(2024, 1, 25));
+    bank.transfer(checking, card, usd(300), None).unwrap();
+    bank.set_clock(at(2024, 2, 20));
+    bank.close_card_cycles(date(2024, 2, 15)).unwrap();
+
+    let card_state = bank.credit_card(card).unwrap();
+    assert_eq!(card_state.statements[0].paid_in_full(), Some(true));
+    assert_eq!(card_state.statements[1].statement_balance, usd(0));
+    assert_eq!(card_state.next_closing_date(), date(2024, 3, 15));
+    assert_eq!(bank.get_account(card).unwrap().balance, usd(0));
+}
+
+#[test]
+fn test_revolving_balance_is_charged_interest() {
+    let mut bank = Bank::new();
+    let (checking, card) = cardholder(&mut bank);
+    bank.set_clock(at(2024, 1, 10));
+    bank.withdraw(card, usd(1_000)).unwrap();
+    bank.close_card_cycles(date(2024, 1, 15)).unwrap();
+
+    bank.set_clock(at(2024, 1, 30));
+    bank.transfer(checking, card, usd(100), None).unwrap();
+    bank.set_clock(at(2024, 2, 16));
+    bank.close_card_cycles(date(2024, 2, 15)).unwrap();
+
+    // 14 days owing 1,000 and 17 owing 900, at 24% over 365 days.
+    let card_state = bank.credit_card(card).unwrap();
+    assert_eq!(card_state.statements[0].paid_in_full(), Some(false));
+    let statement = &card_state.statements[1];
+    assert_eq!(statement.previous_balance, usd(1_000));
+    assert_eq!(statement.statement_balance, cents(91_927));
+    let interest = statement.transactions.last().unwrap();
+    assert_eq!(interest.tx_type, TransactionType::InterestCharged);
+    assert_eq!(interest.amount, cents(1_927));
+    assert_eq!(interest.timestamp.date_naive(), date(2024, 2, 15));
+    assert_eq!(bank.ledger().balance(LedgerAccount::InterestIncome, Currency::USD), cents(1_927));
+    assert!(bank.trial_balance().is_balanced());
+
+    // No late fee: the minimum arrived in time.
+    assert!(bank.get_account(card).unwrap().get_history().iter().all(|tx| !tx.tx_type.is_fee()));
+
+    // Running again changes nothing.
+    let before = serde_json::to_string(&bank).unwrap();
+    bank.close_card_cycles(date(2024, 2, 15)).unwrap();
+    assert_eq!(serde_json::to_string(&bank).unwrap(), before);
+}
+
+#[test]
+fn test_late_fee() {
+    let mut bank = Bank::new();
+    let (_, card) = cardholder(&mut bank);
+    let customer_id = bank.get_account(card).unwrap().customer_id;
+    bank.set_clock(at(2024, 1, 10));
+    bank.withdraw(card, usd(200)).unwrap();
+
+    bank.set_clock(at(2024, 2, 16));
+    bank.close_card_cycles(date(2024, 2, 15)).unwrap();
+    let history = bank.get_account(card).unwrap().get_history().to_vec();
+    let fee = history.iter().find(|tx| tx.tx_type == TransactionType::Fee { reason: FeeReason::LatePayment }).unwrap();
+    assert_eq!(fee.amount, usd(30));
+    assert_eq!(fee.timestamp.date_naive(), date(2024, 2, 4));
+
+    // 19 days owing 200, then 12 owing 230 once the fee is added.
+    let statement = bank.credit_card(card).unwrap().last_statement().unwrap();
+    assert_eq!(statement.statement_balance, cents(23_431));
+    assert_eq!(statement.minimum_payment, usd(25));
+
+    // Late fees can be waived like any other.
+    bank.set_fee_waiver(customer_id, FeeReason::LatePayment, true).unwrap();
+    bank.set_clock(at(2024, 3, 16));
+    bank.close_card_cycles(date(2024, 3, 15)).unwrap();
+    let late_fees = bank.get_account(card).unwrap().get_history().iter().filter(|tx| tx.tx_type.is_fee()).count();
+    assert_eq!(late_fees, 1);
+    assert_eq!(bank.credit_card(card).unwrap().statements.len(), 3);
+}
+
+#[test]
+fn test_reversed_payment_is_late() {
+    let mut bank = Bank::new();
+    let (checking, card) = cardholder(&mut bank);
+    bank.set_clock(at(2024, 1, 10));
+    bank.withdraw(card, usd(200)).unwrap();
+    bank.set_clock(at(2024, 1, 16));
+    bank.close_card_cycles(date(2024, 1, 15)).unwrap();
+
+    // The payment bounces before the due date, so the minimum goes unpaid.
+    bank.set_clock(at(2024, 1, 20));
+    bank.transfer(checking, card, usd(100), None).unwrap();
+    let payment = bank.get_account(card).unwrap().get_history().last().unwrap().id;
+    bank.set_clock(at(2024, 1, 25));
+    bank.reverse_transaction(payment, "Returned unpaid".to_string()).unwrap();
+
+    bank.set_clock(at(2024, 2, 16));
+    bank.close_card_cycles(date(2024, 2, 15)).unwrap();
+    let card_account = bank.get_account(card).unwrap();
+    assert!(card_account.get_history().iter().any(|tx| tx.tx_type == TransactionType::Fee { reason: FeeReason::LatePayment }));
+    assert_eq!(bank.credit_card(card).unwrap().statements[0].paid_by_due, Some(usd(0)));
+}
+
+#[test]
+fn test_cards_survive_save_and_replay() {
+    let (replayed, card) = common::replayed(|bank| {
+        let (_, card) = cardholder(bank);
+        bank.withdraw(card, usd(400)).unwrap();
+        bank.set_clock(at(2024, 3, 1));
+        bank.close_card_cycles(date(2024, 2, 29)).unwrap();
+        card
+    });
+
+    // The late fee and interest are in the journal, not recomputed.
+    let card_state = replayed.credit_card(card).unwrap();
+    assert_eq!(card_state.statements.len(), 2);
+    assert_eq!(card_state.statements[0].paid_by_due, Some(usd(0)));
+    assert!(card_state.statements[1].transactions.iter().any(|tx| tx.tx_type == TransactionType::InterestCharged));
+    assert_eq!(replayed.get_account(card).unwrap().overdraft_limit, Some(usd(1_000)));
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use banksystem_rs::bank::{AccountId, AccountProduct, AccountStatus, Bank, BankError, Currency, MoneyError, TransactionType};
+use banksystem_rs::bank::certificate::{CertificatePhase, CertificateTerms, MaturityAction};
+use banksystem_rs::bank::fees::{FeeReason, FeeSchedule};
+use banksystem_rs::bank::interest::Rate;
+use banksystem_rs::bank::ledger::LedgerAccount;
+use common::{at, cents, date, usd};
+
+/// A year at 5%.
+fn one_year() -> CertificateTerms {
+    CertificateTerms::new(Rate::from_bps(500), 12)
+}
+
+/// A customer's checking account holding 20,000, from which a 10,000
+/// certificate on `terms` is opened on 1 January 2024.
+fn saver(bank: &mut Bank, terms: impl FnOnce(AccountId) -> CertificateTerms) -> (AccountId, AccountId) {
+    bank.set_clock(at(2024, 1, 1));
+    let customer_id = common::customer(bank, "Term Saver");
+    let checking_id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    bank.deposit(checking_id, usd(20_000)).unwrap();
+    let cd_id = bank.open_certificate(customer_id, checking_id, usd(10_000), terms(checking_id)).unwrap();
+    (checking_id, cd_id)
+}
+
+#[test]
+fn test_certificate_is_locked_until_maturity() {
+    let mut bank = Bank::new();
+    let (checking, cd) = saver(&mut bank, |_| one_year());
+    let account = bank.get_account(cd).unwrap();
+    assert_eq!(account.product, AccountProduct::CertificateOfDeposit);
+    assert_eq!(account.balance, usd(10_000));
+    assert_eq!(bank.get_account(checking).unwrap().balance, usd(10_000));
+    assert_eq!(bank.certificate(cd).unwrap().maturity, date(2025, 1, 1));
+
+    let locked = BankError::DepositLocked { account_id: cd, until: date(2025, 1, 1) };
+    assert_eq!(bank.withdraw(cd, usd(1)), Err(locked.clone()));
+    assert_eq!(bank.transfer(cd, checking, usd(1), None), Err(locked.clone()));
+    // Nor can money be added to a locked certificate.
+    assert_eq!(bank.deposit(cd, usd(1)), Err(locked.clone()));
+    assert_eq!(bank.transfer(checking, cd, usd(1), None), Err(locked.clone()));
+    assert_eq!(bank.get_account(cd).unwrap().balance, usd(10_000));
+    assert_eq!(locked.to_string(), format!("account {} is locked until 2025-01-01", cd));
+}
+
+#[test]
+fn test_early_withdrawal_penalty() {
+    let mut bank = Bank::new();
+    let (_, cd) = saver(&mut bank, |_| one_year());
+
+    // 90 days' interest at 5% on 1,000.
+    assert_eq!(bank.early_withdrawal_penalty(cd, usd(1_000)).unwrap(), cents(1_233));
+    bank.set_clock(at(2024, 3, 1));
+    assert_eq!(bank.withdraw_early(cd, usd(1_000)).unwrap(), cents(1_233));
+
+    let history = bank.get_account(cd).unwrap().get_history();
+    let [.., penalty, withdrawal] = history else { panic!("expected a penalty and a withdrawal") };
+    assert_eq!(penalty.tx_type, TransactionType::Fee { reason: FeeReason::EarlyWithdrawal });
+    assert_eq!(penalty.amount, cents(1_233));
+    assert_eq!(withdrawal.tx_type, TransactionType::Withdrawal);
+    assert_eq!(withdrawal.amount, cents(98_767));
+    assert_eq!(bank.get_account(cd).unwrap().balance, usd(9_000));
+    assert_eq!(bank.ledger().balance(LedgerAccount::FeeIncome, Currency::USD), cents(1_233));
+    assert!(bank.certificate(cd).unwrap().is_locked());
+
+    assert!(matches!(bank.withdraw_early(cd, usd(9_001)), Err(BankError::InsufficientFunds { .. })));
+    bank.withdraw_early(cd, usd(9_000)).unwrap();
+    assert_eq!(bank.certificate(cd).unwrap().phase, CertificatePhase::Ended);
+    assert!(bank.withdraw_early(cd, usd(1)).is_err());
+    bank.close_account(cd).unwrap();
+}
+
+#[test]
+fn test_interest_for_days_overflow() {
+    let terms = CertificateTerms::new(Rate::from_ppm(i64::MAX / 2), 12);
+    assert_eq!(terms.interest_for_days(usd(1), 3), Err(BankError::Money(MoneyError::Overflow)));
+}
+
+#[test]
+fn test_auto_renewal() {
+    let mut bank = Bank::new();
+    let (_, cd) = saver(&mut bank, |_| one_year());
+    bank.set_clock(at(2025, 1, 2));
+    bank.mature_certificates(date(2024, 12, 31)).unwrap();
+    assert_eq!(bank.certificate(cd).unwrap().terms_completed, 0);
+
+    // 366 days of 2024 at 5% over a 365-day year.
+    bank.mature_certificates(date(2025, 1, 2)).unwrap();
+    let certificate = bank.certificate(cd).unwrap();
+    assert_eq!(bank.get_account(cd).unwrap().balance, cents(1_050_137));
+    assert_eq!((certificate.start, certificate.maturity), (date(2025, 1, 1), date(2026, 1, 1)));
+    assert!(certificate.is_locked());
+    assert_eq!(certificate.terms_completed, 1);
+    let interest = bank.get_account(cd).unwrap().get_history().last().unwrap().clone();
+    assert_eq!(interest.tx_type, TransactionType::Interest);
+    assert_eq!(interest.timestamp.date_naive(), date(2025, 1, 1));
+
+    let before = serde_json::to_string(&bank).unwrap();
+    bank.mature_certificates(date(2025, 1, 2)).unwrap();
+    assert_eq!(serde_json::to_string(&bank).unwrap(), before);
+    assert!(matches!(bank.withdraw(cd, usd(1)), Err(BankError::DepositLocked { .. })));
+}
+
+#[test]
+fn test_payout_at_maturity() {
+    let mut bank = Bank::new();
+    let (checking, cd) = saver(&mut bank, |to| one_year().with_maturity_action(MaturityAction::PayOut { to }));
+    bank.set_clock(at(2025, 1, 2));
+    bank.mature_certificates(date(2025, 1, 1)).unwrap();
+
+    assert_eq!(bank.get_account(cd).unwrap().balance, usd(0));
+    assert_eq!(bank.get_account(checking).unwrap().balance, cents(2_050_137));
+    assert_eq!(bank.certificate(cd).unwrap().phase, CertificatePhase::Ended);
+    assert!(bank.trial_balance().is_balanced());
+    bank.close_account(cd).unwrap();
+}
+
+#[test]
+fn test_payout_is_not_customer_activity() {
+    let mut bank = Bank::new();
+    for product in [AccountProduct::Checking, AccountProduct::CertificateOfDeposit] {
+        bank.set_fee_schedule(product, FeeSchedule::new().with_per_transfer(usd(2))).unwrap();
+    }
+    let (checking, cd) = saver(&mut bank, |to| one_year().with_maturity_action(MaturityAction::PayOut { to }));
+    bank.set_clock(at(2025, 1, 2));
+    bank.mature_certificates(date(2025, 1, 1)).unwrap();
+    let payout = bank.get_account(cd).unwrap().get_history().last().unwrap().clone();
+    assert_eq!(payout.tx_type, TransactionType::Transfer { to_account: checking });
+    assert!(payout.internal);
+
+    // The checking account was last used by the customer when it was opened.
+    bank.set_clock(at(2025, 1, 15));
+    assert!(bank.flag_dormant_accounts(date(2025, 1, 15)).unwrap().contains(&checking));
+    assert_eq!(bank.get_account(checking).unwrap().status, AccountStatus::Dormant);
+
+    bank.set_clock(at(2025, 2, 1));
+    bank.assess_fees(date(2025, 1, 31)).unwrap();
+    for account_id in [checking, cd] {
+        let fees = bank.get_account(account_id).unwrap().get_history().iter().filter(|tx| tx.tx_type.is_fee()).count();
+        assert_eq!(fees, 0);
+    }
+}
+
+#[test]
+fn test_grace_period() {
+    let mut bank = Bank::new();
+    let (checking, cd) = saver(&mut bank, |_| one_year().with_maturity_action(MaturityAction::GracePeriod { days: 10 }));
+    bank.set_clock(at(2025, 1, 5));
+    bank.mature_certificates(date(2025, 1, 5)).unwrap();
+    assert_eq!(bank.certificate(cd).unwrap().phase, CertificatePhase::Grace { until: date(2025, 1, 11) });
+
+    // Money can be taken out freely, more than once, during the grace period.
+    bank.transfer(cd, checking, cents(50_137), None).unwrap();
+    bank.withdraw(cd, usd(1)).unwrap();
+    bank.mature_certificates(date(2025, 1, 10)).unwrap();
+    assert!(!bank.certificate(cd).unwrap().is_locked());
+
+    bank.mature_certificates(date(2025, 1, 11)).unwrap();
+    let certificate = bank.certificate(cd).unwrap();
+    assert_eq!((certificate.start, certificate.maturity), (date(2025, 1, 11), date(2026, 1, 11)));
+    assert!(certificate.is_locked());
+    assert_eq!(bank.get_account(cd).unwrap().balance, usd(9_999));
+}
+
+#[test]
+fn test_certificate_validation() {
+    let mut bank = Bank::new();
+    let (checking, _) = saver(&mut bank, |_| one_year());
+    let customer_id = bank.get_account(checking).unwrap().customer_id;
+    let stranger = common::customer(&mut bank, "Someone Else");
+    let theirs = bank.create_account(stranger, AccountProduct::Checking).unwrap();
+
+    assert!(matches!(
+        bank.open_certificate(stranger, checking, usd(1), one_year()),
+        Err(BankError::InvalidField { field: "funding account", .. })
+    ));
+    assert!(matches!(bank.open_certificate(customer_id, checking, usd(10_001), one_year()), Err(BankError::InsufficientFunds { .. })));
+    assert!(CertificateTerms::new(Rate::from_bps(500), 0).validate().is_err());
+    assert!(one_year().with_maturity_action(MaturityAction::GracePeriod { days: 0 }).validate().is_err());
+    assert!(matches!(
+        bank.open_certificate(customer_id, checking, usd(1), one_year().with_maturity_action(MaturityAction::PayOut { to: theirs })),
+        Err(BankError::InvalidField { field: "certificate", .. })
+    ));
+    // A term no calendar can hold fails before any account is opened.
+    assert!(bank.open_certificate(customer_id, checking, usd(1), CertificateTerms::new(Rate::from_bps(500), u32::MAX)).is_err());
+    assert_eq!(bank.list_customer_accounts(customer_id).len(), 2);
+    assert!(matches!(bank.withdraw_early(checking, usd(1)), Err(BankError::InvalidField { field: "certificate", .. })));
+}
+
+#[test]
+fn test_certificates_survive_save_and_replay() {
+    let (replayed, cd) = common::replayed(|bank| {
+        let (_, cd) = saver(bank, |_| one_year().with_maturity_action(MaturityAction::GracePeriod { days: 7 }));
+        bank.set_clock(at(2024, 6, 1));
+        bank.withdraw_early(cd, usd(500)).unwrap();
+        bank.set_clock(at(2025, 2, 1));
+        bank.mature_certificates(date(2025, 2, 1)).unwrap();
+        cd
+    });
+
+    // Matured on 1 January 2025 and renewed when the week's grace ran out.
+    let certificate = replayed.certificate(cd).unwrap();
+    assert_eq!(certificate.terms_completed, 1);
+    assert_eq!(certificate.start, date(2025, 1, 8));
+    assert!(certificate.is_locked());
+}
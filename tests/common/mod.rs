@@ -0,0 +1,83 @@
+//! Helpers shared by the integration test suites. Each suite compiles its
+//! own copy and uses only some of them.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use chrono::{NaiveDate, TimeZone, Utc};
+use banksystem_rs::bank::{AccountId, AccountProduct, Bank, Clock, Currency, CustomerId, MemoryStorage, Money};
+
+pub fn usd(major: i64) -> Money {
+    Money::from_major(major, Currency::USD).unwrap()
+}
+
+pub fn cents(minor: i64) -> Money {
+    Money::from_minor(minor, Currency::USD)
+}
+
+pub fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+/// 10:00 UTC on the given day.
+pub fn at(y: i32, m: u32, d: u32) -> Clock {
+    Clock::Fixed(Utc.with_ymd_and_hms(y, m, d, 10, 0, 0).unwrap())
+}
+
+/// Creates a customer called `name`, with made-up contact details.
+pub fn customer(bank: &mut Bank, name: &str) -> CustomerId {
+    let n = bank.list_customers().len();
+    let handle: String = name.chars().filter(char::is_ascii_alphanumeric).flat_map(|c| c.to_lowercase()).collect();
+    bank.create_customer(
+        name.to_string(),
+        format!("{} Test Street", n + 1),
+        format!("555-{:04}", n),
+        format!("{}@example.com", handle),
+    ).unwrap()
+}
+
+/// Opens a `product` account for `customer_id` and pays in `funds` whole
+/// dollars, if any.
+pub fn account(bank: &mut Bank, customer_id: CustomerId, product: AccountProduct, funds: i64) -> AccountId {
+    let account_id = bank.create_account(customer_id, product).unwrap();
+    if funds > 0 {
+        bank.deposit(account_id, usd(funds)).unwrap();
+    }
+    account_id
+}
+
+/// A directory of its own under the system temp directory, emptied first.
+pub fn temp_dir(name: &str) -> PathBuf {
+    static NEXT: AtomicU32 = AtomicU32::new(0);
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    let dir = std::env::temp_dir().join(format!("banksystem-rs-{}-{}-{}", name, std::process::id(), n));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Runs `scenario` on a bank that journals every change, then checks that
+/// replaying the journal, reopening the bank's storage and loading a saved
+/// copy each give the same bank back. Returns the replayed bank and what
+/// `scenario` returned.
+pub fn replayed<T>(scenario: impl FnOnce(&mut Bank) -> T) -> (Bank, T) {
+    let dir = temp_dir("replay");
+    let journal = dir.join("events.jsonl");
+    let saved = dir.join("bank.json");
+
+    let mut bank = Bank::with_journal(MemoryStorage::new(), &journal).unwrap();
+    let result = scenario(&mut bank);
+    let expected = serde_json::to_string(&bank).unwrap();
+
+    let replayed = Bank::replay(&journal).unwrap();
+    assert_eq!(serde_json::to_string(&replayed).unwrap(), expected, "replaying the journal");
+    let reopened = Bank::with_storage(bank.storage().clone()).unwrap();
+    assert_eq!(serde_json::to_string(&reopened).unwrap(), expected, "reopening the storage");
+    bank.save(&saved).unwrap();
+    let loaded = Bank::load(&saved).unwrap();
+    assert_eq!(serde_json::to_string(&loaded).unwrap(), expected, "loading a saved copy");
+
+    fs::remove_dir_all(&dir).unwrap();
+    (replayed, result)
+}
@@ -1,4 +1,4 @@
-use banksystem_rs::bank::Bank;
+use banksystem_rs::bank::{AccountProduct, Bank, BankError, CustomerId};
 
 #[test]
 fn test_customer_creation() {
@@ -8,8 +8,8 @@ fn test_customer_creation() {
         "123 Main St".to_string(),
         "555-1234".to_string(),
         "john@example.com".to_string(),
-    );
-    assert_eq!(id, 1);
+    ).unwrap();
+    assert_eq!(id, CustomerId(1));
     let customer = bank.get_customer(id).unwrap();
     assert_eq!(customer.name, "John Doe");
     assert_eq!(customer.address, "123 Main St");
@@ -25,22 +25,51 @@ fn test_customer_account_relationship() {
         "456 Oak Ave".to_string(),
         "555-5678".to_string(),
         "jane@example.com".to_string(),
-    );
+    ).unwrap();
     
     // Create multiple accounts for the customer
-    let checking_id = bank.create_account(customer_id, "Checking".to_string());
-    let savings_id = bank.create_account(customer_id, "Savings".to_string());
+    bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    bank.create_account(customer_id, AccountProduct::Savings).unwrap();
     
     // Test account listing
     let customer_accounts = bank.list_customer_accounts(customer_id);
     assert_eq!(customer_accounts.len(), 2);
-    assert!(customer_accounts.iter().any(|a| a.account_type == "Checking"));
-    assert!(customer_accounts.iter().any(|a| a.account_type == "Savings"));
+    assert!(customer_accounts.iter().any(|a| a.product == AccountProduct::Checking));
+    assert!(customer_accounts.iter().any(|a| a.product == AccountProduct::Savings));
 }
 
 #[test]
 fn test_customer_not_found() {
     let bank = Bank::new();
-    assert!(bank.get_customer(999).is_none());
-    assert!(bank.get_customer_mut(999).is_none());
+    assert!(bank.get_customer(CustomerId(999)).is_none());
+}
+
+#[test]
+fn test_customer_validation() {
+    let mut bank = Bank::new();
+    let result = bank.create_customer(
+        "".to_string(),
+        "1 Nowhere Rd".to_string(),
+        "555-0000".to_string(),
+        "nobody@example.com".to_string(),
+    );
+    assert!(matches!(result, Err(BankError::InvalidField { field: "name", .. })));
+    assert!(bank.list_customers().is_empty());
+
+    let id = bank.create_customer(
+        "Valid Name".to_string(),
+        "1 Somewhere Rd".to_string(),
+        "555-0001".to_string(),
+        "valid@example.com".to_string(),
+    ).unwrap();
+
+    // A bad email rejects the whole update, including the valid name.
+    let result = bank.update_customer(id, Some("New Name".to_string()), None, None, Some("invalid".to_string()));
+    assert!(matches!(result, Err(BankError::InvalidField { field: "email", .. })));
+    assert_eq!(bank.get_customer(id).unwrap().name, "Valid Name");
+
+    bank.update_customer(id, Some("New Name".to_string()), None, None, None).unwrap();
+    assert_eq!(bank.get_customer(id).unwrap().name, "New Name");
+
+    assert_eq!(bank.update_customer(CustomerId(999), None, None, None, None), Err(BankError::CustomerNotFound(CustomerId(999))));
 }
@@ -0,0 +1,231 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use banksystem_rs::bank::{AccountId, AccountProduct, Bank, BankError, Currency, CustomerId, Money, TransactionId, TransactionType};
+use banksystem_rs::bank::certificate::CertificateTerms;
+use banksystem_rs::bank::fees::{FeeReason, FeeSchedule};
+use banksystem_rs::bank::interest::Rate;
+use banksystem_rs::bank::ledger::LedgerAccount;
+use common::{at, date, usd};
+
+/// Savings pays 5 a month, 10 for dipping under 1,000 and 2 for each debit
+/// after the third; checking pays 1 per outgoing transfer.
+fn fee_bank() -> (Bank, CustomerId) {
+    let mut bank = Bank::new();
+    bank.set_clock(at(2024, 1, 1));
+    bank.set_fee_schedule(
+        AccountProduct::Savings,
+        FeeSchedule::new()
+            .with_monthly_maintenance(usd(5))
+            .with_below_minimum(usd(1_000), usd(10))
+            .with_excess_withdrawal(3, usd(2)),
+    ).unwrap();
+    bank.set_fee_schedule(AccountProduct::Checking, FeeSchedule::new().with_per_transfer(usd(1))).unwrap();
+    let customer_id = common::customer(&mut bank, "Fee Payer");
+    (bank, customer_id)
+}
+
+fn fees_on(bank: &Bank, account_id: AccountId) -> Vec<(FeeReason, Money)> {
+    bank.get_account(account_id)
+        .unwrap()
+        .get_history()
+        .iter()
+        .filter_map(|tx| match tx.tx_type {
+            TransactionType::Fee { reason } => Some((reason, tx.amount)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_monthly_and_excess_withdrawal_fees() {
+    let (mut bank, customer_id) = fee_bank();
+    let savings = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.deposit(savings, usd(2_000)).unwrap();
+    for _ in 0..5 {
+        bank.withdraw(savings, usd(10)).unwrap();
+    }
+
+    bank.assess_fees(date(2024, 1, 31)).unwrap();
+    assert_eq!(fees_on(&bank, savings), vec![
+        (FeeReason::MonthlyMaintenance, usd(5)),
+        (FeeReason::ExcessWithdrawal, usd(4)),
+    ]);
+    let account = bank.get_account(savings).unwrap();
+    assert_eq!(account.balance, usd(1_941));
+    assert_eq!(account.get_history().last().unwrap().timestamp, Utc.with_ymd_and_hms(2024, 1, 31, 23, 59, 59).unwrap());
+    assert_eq!(account.fees_assessed_through, Some(date(2024, 1, 31)));
+
+    // Running again, or part-way through a month, charges nothing new.
+    bank.assess_fees(date(2024, 1, 31)).unwrap();
+    bank.assess_fees(date(2024, 2, 28)).unwrap();
+    assert_eq!(fees_on(&bank, savings).len(), 2);
+
+    bank.assess_fees(date(2024, 3, 31)).unwrap();
+    assert_eq!(fees_on(&bank, savings).len(), 4);
+    assert_eq!(bank.ledger().balance(LedgerAccount::FeeIncome, Currency::USD), usd(19));
+    assert!(bank.trial_balance().is_balanced());
+}
+
+#[test]
+fn test_below_minimum_balance_fee() {
+    let (mut bank, customer_id) = fee_bank();
+    bank.set_fee_schedule(AccountProduct::Savings, FeeSchedule::new().with_below_minimum(usd(1_000), usd(10))).unwrap();
+    let savings = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.deposit(savings, usd(1_500)).unwrap();
+
+    // A single day under the minimum is enough.
+    bank.set_clock(at(2024, 1, 10));
+    bank.withdraw(savings, usd(600)).unwrap();
+    bank.set_clock(at(2024, 1, 11));
+    bank.deposit(savings, usd(600)).unwrap();
+
+    // Days before an account is opened don't count.
+    bank.set_clock(at(2024, 2, 15));
+    let late = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.deposit(late, usd(1_000)).unwrap();
+
+    bank.assess_fees(date(2024, 2, 29)).unwrap();
+    assert_eq!(fees_on(&bank, savings), vec![(FeeReason::BelowMinimumBalance, usd(10))]);
+    assert!(fees_on(&bank, late).is_empty());
+}
+
+#[test]
+fn test_per_transfer_fee() {
+    let (mut bank, customer_id) = fee_bank();
+    let checking = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    let savings = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.deposit(checking, usd(100)).unwrap();
+    bank.deposit(savings, usd(5_000)).unwrap();
+    for _ in 0..3 {
+        bank.transfer(checking, savings, usd(10), None).unwrap();
+    }
+    bank.withdraw(checking, usd(10)).unwrap();
+
+    bank.assess_fees(date(2024, 1, 31)).unwrap();
+    assert_eq!(fees_on(&bank, checking), vec![(FeeReason::Transfer, usd(3))]);
+    assert_eq!(fees_on(&bank, savings), vec![(FeeReason::MonthlyMaintenance, usd(5))]);
+}
+
+#[test]
+fn test_internal_transfers_are_not_charged() {
+    let (mut bank, customer_id) = fee_bank();
+    let checking = common::account(&mut bank, customer_id, AccountProduct::Checking, 1_000);
+    let savings = common::account(&mut bank, customer_id, AccountProduct::Savings, 5_000);
+    bank.set_overdraft_protection(checking, Some(savings)).unwrap();
+
+    // Certificate funding and four protection pulls move money out of
+    // checking and savings without either customer asking for a transfer.
+    bank.open_certificate(customer_id, checking, usd(1_000), CertificateTerms::new(Rate::from_bps(400), 6)).unwrap();
+    for _ in 0..4 {
+        bank.withdraw(checking, usd(10)).unwrap();
+    }
+    bank.deposit(checking, usd(5)).unwrap();
+    bank.transfer(checking, savings, usd(5), None).unwrap();
+
+    bank.assess_fees(date(2024, 1, 31)).unwrap();
+    assert_eq!(fees_on(&bank, checking), vec![(FeeReason::Transfer, usd(1))]);
+    assert_eq!(fees_on(&bank, savings), vec![(FeeReason::MonthlyMaintenance, usd(5))]);
+    assert!(bank.get_account(savings).unwrap().get_history()[1].internal);
+}
+
+#[test]
+fn test_fee_waivers() {
+    let (mut bank, customer_id) = fee_bank();
+    let savings = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.deposit(savings, usd(500)).unwrap();
+
+    bank.set_fee_waiver(customer_id, FeeReason::MonthlyMaintenance, true).unwrap();
+    assert!(bank.get_customer(customer_id).unwrap().fee_waivers.contains(&FeeReason::MonthlyMaintenance));
+    bank.assess_fees(date(2024, 1, 31)).unwrap();
+    assert_eq!(fees_on(&bank, savings), vec![(FeeReason::BelowMinimumBalance, usd(10))]);
+
+    bank.set_fee_waiver(customer_id, FeeReason::MonthlyMaintenance, false).unwrap();
+    bank.set_fee_waiver(customer_id, FeeReason::BelowMinimumBalance, true).unwrap();
+    bank.assess_fees(date(2024, 2, 29)).unwrap();
+    assert_eq!(fees_on(&bank, savings)[1], (FeeReason::MonthlyMaintenance, usd(5)));
+    assert_eq!(fees_on(&bank, savings).len(), 2);
+
+    assert_eq!(
+        bank.set_fee_waiver(CustomerId(99), FeeReason::Transfer, true),
+        Err(BankError::CustomerNotFound(CustomerId(99)))
+    );
+}
+
+#[test]
+fn test_fee_reversals() {
+    let (mut bank, customer_id) = fee_bank();
+    let savings = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.deposit(savings, usd(2_000)).unwrap();
+    bank.assess_fees(date(2024, 1, 31)).unwrap();
+
+    let history = bank.get_account(savings).unwrap().get_history();
+    let deposit_id = history[0].id;
+    let fee_id = history[1].id;
+    bank.reverse_fee(savings, fee_id).unwrap();
+
+    let account = bank.get_account(savings).unwrap();
+    assert_eq!(account.balance, usd(2_000));
+    let reversal = account.get_history().last().unwrap();
+    assert_eq!(reversal.tx_type, TransactionType::FeeReversal { fee_id });
+    assert_eq!(reversal.amount, usd(5));
+    assert_eq!(bank.ledger().balance(LedgerAccount::FeeIncome, Currency::USD), usd(0));
+    assert!(bank.trial_balance().is_balanced());
+
+    assert!(matches!(bank.reverse_fee(savings, fee_id), Err(BankError::InvalidField { field: "fee", .. })));
+    assert!(matches!(bank.reverse_fee(savings, deposit_id), Err(BankError::InvalidField { field: "fee", .. })));
+    assert!(matches!(bank.reverse_fee(savings, TransactionId(999)), Err(BankError::InvalidField { field: "fee", .. })));
+
+    // Overdraft fees can be refunded the same way.
+    let checking = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    bank.set_overdraft_limit(checking, usd(100)).unwrap();
+    bank.set_overdraft_fee(AccountProduct::Checking, usd(25)).unwrap();
+    bank.withdraw(checking, usd(10)).unwrap();
+    let overdraft_fee = bank.get_account(checking).unwrap().get_history().last().unwrap().id;
+    bank.reverse_fee(checking, overdraft_fee).unwrap();
+    assert_eq!(bank.get_account(checking).unwrap().balance, usd(-10));
+}
+
+#[test]
+fn test_fee_schedules_are_validated() {
+    let (mut bank, _) = fee_bank();
+    assert_eq!(
+        bank.set_fee_schedule(AccountProduct::Checking, FeeSchedule::new().with_monthly_maintenance(usd(0))),
+        Err(BankError::InvalidAmount(usd(0)))
+    );
+    assert!(matches!(
+        bank.set_fee_schedule(AccountProduct::Savings, FeeSchedule::new().with_below_minimum(usd(-1), usd(5))),
+        Err(BankError::InvalidField { field: "minimum balance", .. })
+    ));
+    assert_eq!(bank.fee_schedule(AccountProduct::Checking).unwrap().per_transfer, Some(usd(1)));
+    assert_eq!(FeeReason::BelowMinimumBalance.to_string(), "MINBAL");
+}
+
+#[test]
+fn test_fees_replay_from_journal() {
+    let (template, _) = fee_bank();
+    let (mut replayed, (savings, fee_id)) = common::replayed(|bank| {
+        bank.set_clock(at(2024, 1, 1));
+        bank.set_fee_schedule(AccountProduct::Savings, template.fee_schedule(AccountProduct::Savings).unwrap().clone()).unwrap();
+        let customer_id = common::customer(bank, "Replay Payer");
+        let savings = common::account(bank, customer_id, AccountProduct::Savings, 500);
+        bank.set_fee_waiver(customer_id, FeeReason::MonthlyMaintenance, true).unwrap();
+        bank.assess_fees(date(2024, 2, 29)).unwrap();
+        let fee_id = bank.get_account(savings).unwrap().get_history()[1].id;
+        bank.reverse_fee(savings, fee_id).unwrap();
+        (savings, fee_id)
+    });
+
+    // The waiver held for both months; the first minimum-balance fee is
+    // refunded and the second stands.
+    assert_eq!(fees_on(&replayed, savings), vec![(FeeReason::BelowMinimumBalance, usd(10)), (FeeReason::BelowMinimumBalance, usd(10))]);
+    let account = replayed.get_account(savings).unwrap();
+    assert!(account.get_history().iter().any(|tx| tx.tx_type == TransactionType::FeeReversal { fee_id }));
+    assert_eq!(account.fees_assessed_through, Some(date(2024, 2, 29)));
+    assert_eq!(account.balance, usd(490));
+
+    // The schedule came back with the data, so March is charged as well.
+    assert_eq!(replayed.fee_schedule(AccountProduct::Savings), template.fee_schedule(AccountProduct::Savings));
+    replayed.assess_fees(date(2024, 3, 31)).unwrap();
+    assert_eq!(fees_on(&replayed, savings).len(), 3);
+}
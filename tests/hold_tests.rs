@@ -0,0 +1,137 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use banksystem_rs::bank::{AccountId, AccountProduct, Bank, BankError, HoldId, HoldKind, Money, TransactionType};
+use common::{at, usd};
+
+/// A checking account holding 100 on 1 January 2024.
+fn funded_account(bank: &mut Bank) -> AccountId {
+    bank.set_clock(at(2024, 1, 1));
+    let customer_id = common::customer(bank, "Card Holder");
+    let account_id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    bank.deposit(account_id, usd(100)).unwrap();
+    account_id
+}
+
+fn available(bank: &Bank, account_id: AccountId) -> Money {
+    bank.get_account(account_id).unwrap().available_balance().unwrap()
+}
+
+#[test]
+fn test_card_authorization_and_capture() {
+    let mut bank = Bank::new();
+    let account_id = funded_account(&mut bank);
+
+    let hold_id = bank.place_hold(account_id, HoldKind::CardAuthorization, usd(60), None).unwrap();
+    let account = bank.get_account(account_id).unwrap();
+    assert_eq!(account.ledger_balance(), usd(100));
+    assert_eq!(account.held_amount().unwrap(), usd(60));
+    assert_eq!(available(&bank, account_id), usd(40));
+    assert_eq!(account.get_history().len(), 1);
+
+    assert_eq!(
+        bank.withdraw(account_id, usd(50)),
+        Err(BankError::InsufficientFunds { account_id, requested: usd(50), available: usd(40) })
+    );
+    assert!(matches!(
+        bank.place_hold(account_id, HoldKind::CardAuthorization, usd(41), None),
+        Err(BankError::InsufficientFunds { .. })
+    ));
+
+    // Settling for less frees the rest.
+    assert!(matches!(
+        bank.capture_hold(account_id, hold_id, usd(61)),
+        Err(BankError::InvalidField { field: "capture amount", .. })
+    ));
+    bank.capture_hold(account_id, hold_id, usd(45)).unwrap();
+    let account = bank.get_account(account_id).unwrap();
+    assert_eq!(account.ledger_balance(), usd(55));
+    assert_eq!(available(&bank, account_id), usd(55));
+    let capture = account.get_history().last().unwrap();
+    assert_eq!(capture.tx_type, TransactionType::HoldCapture { hold_id });
+    assert_eq!(capture.amount, usd(45));
+    assert!(matches!(bank.capture_hold(account_id, hold_id, usd(1)), Err(BankError::InvalidField { field: "hold", .. })));
+    assert!(bank.trial_balance().is_balanced());
+}
+
+#[test]
+fn test_cheque_and_legal_holds() {
+    let mut bank = Bank::new();
+    let account_id = funded_account(&mut bank);
+    let other = bank.create_account(bank.get_account(account_id).unwrap().customer_id, AccountProduct::Checking).unwrap();
+
+    bank.deposit(account_id, usd(500)).unwrap();
+    let cheque = bank.place_hold(account_id, HoldKind::ChequeClearing, usd(500), None).unwrap();
+    assert_eq!(available(&bank, account_id), usd(100));
+    assert!(bank.transfer(account_id, other, usd(150), None).is_err());
+
+    // A legal hold can take the available balance below zero.
+    let legal = bank.place_hold(account_id, HoldKind::Legal, usd(250), None).unwrap();
+    assert_eq!(available(&bank, account_id), usd(-150));
+    assert!(bank.withdraw(account_id, usd(1)).is_err());
+
+    bank.release_hold(account_id, cheque).unwrap();
+    assert_eq!(available(&bank, account_id), usd(350));
+    assert!(bank.release_hold(account_id, cheque).is_err());
+    assert!(bank.release_hold(other, legal).is_err());
+
+    // Frozen accounts can't settle card payments, but legal holds still go.
+    let card = bank.place_hold(account_id, HoldKind::CardAuthorization, usd(10), None).unwrap();
+    bank.freeze_account(account_id).unwrap();
+    assert!(matches!(bank.capture_hold(account_id, card, usd(10)), Err(BankError::AccountStatus { .. })));
+    bank.capture_hold(account_id, legal, usd(250)).unwrap();
+    assert_eq!(bank.get_account(account_id).unwrap().ledger_balance(), usd(350));
+
+    bank.unfreeze_account(account_id).unwrap();
+    bank.release_hold(account_id, card).unwrap();
+    assert!(bank.place_hold(account_id, HoldKind::Legal, usd(0), None).is_err());
+}
+
+#[test]
+fn test_holds_expire() {
+    let mut bank = Bank::new();
+    let account_id = funded_account(&mut bank);
+    let expires = Utc.with_ymd_and_hms(2024, 1, 5, 0, 0, 0).unwrap();
+    let hold_id = bank.place_hold(account_id, HoldKind::CardAuthorization, usd(30), Some(expires)).unwrap();
+    let lasting = bank.place_hold(account_id, HoldKind::CardAuthorization, usd(20), None).unwrap();
+
+    bank.set_clock(at(2024, 1, 4));
+    assert_eq!(bank.expire_holds().unwrap(), vec![]);
+    assert_eq!(available(&bank, account_id), usd(50));
+
+    bank.set_clock(at(2024, 1, 5));
+    assert_eq!(bank.expire_holds().unwrap(), vec![hold_id]);
+    assert_eq!(available(&bank, account_id), usd(80));
+    assert_eq!(bank.get_account(account_id).unwrap().holds[0].id, lasting);
+
+    assert!(matches!(
+        bank.place_hold(account_id, HoldKind::Legal, usd(5), Some(expires)),
+        Err(BankError::InvalidField { field: "hold expiry", .. })
+    ));
+
+    // Accounts with open holds can't be closed, even when empty.
+    bank.withdraw(account_id, usd(80)).unwrap();
+    bank.capture_hold(account_id, lasting, usd(20)).unwrap();
+    let legal = bank.place_hold(account_id, HoldKind::Legal, usd(5), None).unwrap();
+    assert!(matches!(bank.close_account(account_id), Err(BankError::InvalidField { field: "account status", .. })));
+    bank.release_hold(account_id, legal).unwrap();
+    bank.close_account(account_id).unwrap();
+}
+
+#[test]
+fn test_holds_survive_save_and_replay() {
+    let (mut replayed, account_id) = common::replayed(|bank| {
+        let account_id = funded_account(bank);
+        let first = bank.place_hold(account_id, HoldKind::CardAuthorization, usd(10), None).unwrap();
+        let second = bank.place_hold(account_id, HoldKind::Legal, usd(20), None).unwrap();
+        bank.capture_hold(account_id, first, usd(5)).unwrap();
+        assert_eq!((first, second), (HoldId(1), HoldId(2)));
+        account_id
+    });
+
+    // Only the legal hold is left, and hold IDs carry on from where they were.
+    let account = replayed.get_account(account_id).unwrap();
+    assert_eq!(account.holds.iter().map(|hold| hold.kind).collect::<Vec<_>>(), vec![HoldKind::Legal]);
+    assert_eq!(available(&replayed, account_id), usd(75));
+    assert_eq!(replayed.place_hold(account_id, HoldKind::Legal, usd(1), None).unwrap(), HoldId(3));
+}
@@ -0,0 +1,269 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use banksystem_rs::bank::{AccountId, AccountProduct, Bank, BankError, Currency, MemoryStorage, RoundingMode, TransactionType};
+use banksystem_rs::bank::interest::{DayCount, InterestPolicy, PostingFrequency, Promotion, Rate, RateSchedule, RateTable, RateTier};
+use banksystem_rs::bank::journal::{Event, EventJournal, JournalRecord};
+use banksystem_rs::bank::ledger::LedgerAccount;
+use common::{at, cents, date, usd};
+
+/// A bank paying `policy` on savings, with a savings account holding
+/// 10,000 opened on `opened`.
+fn savings_bank(policy: InterestPolicy, opened: (i32, u32, u32)) -> (Bank, AccountId) {
+    let mut bank = Bank::new();
+    bank.set_clock(at(opened.0, opened.1, opened.2));
+    bank.set_interest_policy(AccountProduct::Savings, policy).unwrap();
+    let customer_id = common::customer(&mut bank, "Saver");
+    let account_id = common::account(&mut bank, customer_id, AccountProduct::Savings, 10_000);
+    (bank, account_id)
+}
+
+#[test]
+fn test_day_count_conventions() {
+    assert_eq!(DayCount::Actual365.days_between(date(2024, 2, 1), date(2024, 3, 1)), 29);
+    assert_eq!(DayCount::Actual360.days_between(date(2024, 2, 1), date(2024, 3, 1)), 29);
+    assert_eq!(DayCount::Thirty360.days_between(date(2024, 2, 1), date(2024, 3, 1)), 30);
+    assert_eq!(DayCount::Thirty360.days_between(date(2024, 1, 30), date(2024, 1, 31)), 0);
+    assert_eq!(DayCount::Thirty360.days_between(date(2024, 1, 1), date(2025, 1, 1)), 360);
+    assert_eq!(DayCount::Actual365.days_in_year(), 365);
+    assert_eq!(DayCount::Thirty360.days_in_year(), 360);
+
+    assert!(PostingFrequency::Monthly.is_posting_date(date(2024, 2, 29)));
+    assert!(!PostingFrequency::Monthly.is_posting_date(date(2024, 2, 28)));
+    assert!(PostingFrequency::Quarterly.is_posting_date(date(2024, 6, 30)));
+    assert!(!PostingFrequency::Quarterly.is_posting_date(date(2024, 5, 31)));
+
+    assert_eq!(Rate::from_bps(150).to_string(), "1.5%");
+    assert_eq!(Rate::from_ppm(4_375).to_string(), "0.4375%");
+}
+
+#[test]
+fn test_accrues_daily_and_posts_monthly() {
+    let (mut bank, account_id) = savings_bank(InterestPolicy::new(Rate::from_bps(100)), (2024, 1, 1));
+
+    // 15 days at 10,000 and 1% / 365: 4.1095...
+    bank.accrue_interest(date(2024, 1, 15)).unwrap();
+    assert_eq!(bank.accrued_interest(account_id).unwrap(), cents(410));
+    assert_eq!(bank.get_account(account_id).unwrap().balance, usd(10_000));
+
+    // All 31 days of January: 8.4931... paid as 8.49 on the 31st.
+    bank.accrue_interest(date(2024, 2, 1)).unwrap();
+    let account = bank.get_account(account_id).unwrap();
+    assert_eq!(account.balance, cents(1_000_849));
+    let interest = account.get_history().last().unwrap();
+    assert_eq!(interest.tx_type, TransactionType::Interest);
+    assert_eq!(interest.amount, cents(849));
+    assert_eq!(interest.timestamp, Utc.with_ymd_and_hms(2024, 1, 31, 23, 59, 59).unwrap());
+    assert_eq!(account.interest.accrued_through, Some(date(2024, 2, 1)));
+
+    assert_eq!(bank.ledger().balance(LedgerAccount::InterestExpense, Currency::USD), cents(849));
+    assert!(bank.trial_balance().is_balanced());
+
+    // Running again for a date already covered changes nothing.
+    let entries = bank.ledger().entries().len();
+    bank.accrue_interest(date(2024, 1, 20)).unwrap();
+    bank.accrue_interest(date(2024, 2, 1)).unwrap();
+    assert_eq!(bank.ledger().entries().len(), entries);
+}
+
+#[test]
+fn test_accrual_follows_daily_balances() {
+    let (mut bank, account_id) = savings_bank(InterestPolicy::new(Rate::from_bps(100)), (2024, 1, 1));
+    bank.set_clock(at(2024, 1, 11));
+    bank.withdraw(account_id, usd(5_000)).unwrap();
+
+    // 10 days at 10,000 and 21 at 5,000: 5.6164... paid as 5.61, with the
+    // rest carried.
+    bank.accrue_interest(date(2024, 1, 31)).unwrap();
+    let history = bank.get_account(account_id).unwrap().get_history();
+    assert_eq!(history.last().unwrap().amount, cents(561));
+}
+
+#[test]
+fn test_other_day_counts() {
+    // February 2024 has 29 days but counts as 30 under 30/360.
+    let thirty = InterestPolicy::new(Rate::from_bps(100)).with_day_count(DayCount::Thirty360);
+    let (mut bank, account_id) = savings_bank(thirty, (2024, 2, 1));
+    bank.accrue_interest(date(2024, 2, 29)).unwrap();
+    assert_eq!(bank.get_account(account_id).unwrap().balance, cents(1_000_833));
+
+    let actual_360 = InterestPolicy::new(Rate::from_bps(100)).with_day_count(DayCount::Actual360);
+    let (mut bank, account_id) = savings_bank(actual_360, (2024, 2, 1));
+    bank.accrue_interest(date(2024, 2, 29)).unwrap();
+    assert_eq!(bank.get_account(account_id).unwrap().balance, cents(1_000_805));
+}
+
+#[test]
+fn test_quarterly_posting_and_rounding_carry() {
+    let policy = InterestPolicy::new(Rate::from_bps(100))
+        .with_frequency(PostingFrequency::Quarterly)
+        .with_rounding(RoundingMode::Down);
+    let (mut bank, account_id) = savings_bank(policy, (2024, 1, 1));
+
+    bank.accrue_interest(date(2024, 2, 29)).unwrap();
+    assert_eq!(bank.get_account(account_id).unwrap().balance, usd(10_000));
+
+    bank.accrue_interest(date(2024, 3, 31)).unwrap();
+    let account = bank.get_account(account_id).unwrap();
+    // 91 days: 24.9315... rounded down, with the rest kept for next quarter.
+    assert_eq!(account.balance, cents(1_002_493));
+    let accrued = account.interest.accrued_micros;
+    assert!(accrued > 0 && accrued < 1_000_000);
+    assert_eq!(accrued + 2_493 * 1_000_000, 91 * 27_397_260);
+}
+
+#[test]
+fn test_only_interest_bearing_products_take_policies() {
+    let mut bank = Bank::new();
+    assert!(matches!(
+        bank.set_interest_policy(AccountProduct::Checking, InterestPolicy::new(Rate::from_bps(100))),
+        Err(BankError::InvalidField { field: "interest policy", .. })
+    ));
+    assert!(bank.interest_policy(AccountProduct::Checking).is_none());
+    assert_eq!(bank.accrued_interest(AccountId(1)), Err(BankError::AccountNotFound(AccountId(1))));
+}
+
+#[test]
+fn test_interest_replays_from_journal() {
+    let (mut replayed, (original, account_id)) = common::replayed(|bank| {
+        bank.set_clock(at(2024, 1, 1));
+        bank.set_interest_policy(AccountProduct::Savings, InterestPolicy::new(Rate::from_bps(250))).unwrap();
+        bank.schedule_rate_change(AccountProduct::Savings, date(2024, 5, 1), RateTable::flat(Rate::from_bps(100))).unwrap();
+        let customer_id = common::customer(bank, "Replay Saver");
+        let account_id = common::account(bank, customer_id, AccountProduct::Savings, 2_500);
+        bank.accrue_interest(date(2024, 4, 10)).unwrap();
+        (bank.accrued_interest(account_id).unwrap(), account_id)
+    });
+
+    // Three month ends paid out, and ten days of April still accrued.
+    let account = replayed.get_account(account_id).unwrap();
+    assert_eq!(account.get_history().iter().filter(|tx| tx.tx_type == TransactionType::Interest).count(), 3);
+    assert_eq!(account.interest.accrued_through, Some(date(2024, 4, 10)));
+    assert_eq!(replayed.accrued_interest(account_id).unwrap(), original);
+
+    // The policy and its scheduled change came back too, so accrual carries on.
+    let policy = replayed.interest_policy(AccountProduct::Savings).unwrap();
+    assert_eq!(policy.rates.table_on(date(2024, 5, 1)).tiers()[0].rate, Rate::from_bps(100));
+    let before = accrued_micros(&replayed, account_id);
+    replayed.accrue_interest(date(2024, 4, 11)).unwrap();
+    assert!(accrued_micros(&replayed, account_id) > before);
+}
+
+fn accrued_micros(bank: &Bank, account_id: AccountId) -> i64 {
+    bank.get_account(account_id).unwrap().interest.accrued_micros
+}
+
+#[test]
+fn test_tiered_rates_pay_per_band() {
+    let tiers = RateTable::tiered(vec![
+        RateTier { floor: 0, rate: Rate::from_bps(50) },
+        RateTier { floor: 1_000_000, rate: Rate::from_bps(150) },
+    ]).unwrap();
+    let policy = InterestPolicy::with_rates(RateSchedule::new(tiers));
+    let (mut bank, account_id) = savings_bank(policy, (2024, 1, 1));
+    bank.deposit(account_id, usd(10_000)).unwrap();
+
+    // 0.5% on the first 10,000 and 1.5% on the other 10,000: 200 a year,
+    // 16.986... for January, paid as 16.98.
+    bank.accrue_interest(date(2024, 1, 31)).unwrap();
+    assert_eq!(bank.get_account(account_id).unwrap().balance, cents(2_001_698));
+
+    assert!(RateTable::tiered(vec![]).is_err());
+    assert!(RateTable::tiered(vec![RateTier { floor: 5, rate: Rate::from_bps(1) }]).is_err());
+    assert!(RateTable::tiered(vec![RateTier { floor: 0, rate: Rate::from_bps(-1) }]).is_err());
+    assert!(RateTable::tiered(vec![
+        RateTier { floor: 0, rate: Rate::from_bps(1) },
+        RateTier { floor: 0, rate: Rate::from_bps(2) },
+    ]).is_err());
+}
+
+#[test]
+fn test_promotional_rate_for_new_accounts() {
+    let policy = InterestPolicy::new(Rate::from_bps(100))
+        .with_promotion(Promotion { rate: Rate::from_bps(500), days: 10 });
+    let (mut bank, early) = savings_bank(policy, (2024, 1, 1));
+
+    bank.set_clock(at(2024, 1, 5));
+    let late = bank.create_account(bank.get_account(early).unwrap().customer_id, AccountProduct::Savings).unwrap();
+    bank.deposit(late, usd(10_000)).unwrap();
+
+    // Days 1-10 of the first account earn the 5% teaser.
+    bank.accrue_interest(date(2024, 1, 10)).unwrap();
+    assert_eq!(accrued_micros(&bank, early), 10 * 136_986_301);
+
+    // On the 11th the first account is back on 1%; the second is still new.
+    let (early_before, late_before) = (accrued_micros(&bank, early), accrued_micros(&bank, late));
+    bank.accrue_interest(date(2024, 1, 11)).unwrap();
+    assert_eq!(accrued_micros(&bank, early) - early_before, 27_397_260);
+    assert_eq!(accrued_micros(&bank, late) - late_before, 136_986_301);
+}
+
+#[test]
+fn test_negative_rates_are_rejected() {
+    let mut bank = Bank::new();
+    let teaser = InterestPolicy::new(Rate::from_bps(100)).with_promotion(Promotion { rate: Rate::from_bps(-50), days: 10 });
+    assert!(bank.set_interest_policy(AccountProduct::Savings, teaser).is_err());
+    assert!(bank.set_interest_policy(AccountProduct::Savings, InterestPolicy::new(Rate::from_bps(-1))).is_err());
+
+    bank.set_interest_policy(AccountProduct::Savings, InterestPolicy::new(Rate::from_bps(100))).unwrap();
+    let change = bank.schedule_rate_change(AccountProduct::Savings, date(2024, 2, 1), RateTable::flat(Rate::from_bps(-1)));
+    assert!(change.is_err());
+}
+
+#[test]
+fn test_rounding_up_never_pays_ahead() {
+    let policy = InterestPolicy::new(Rate::from_bps(100)).with_rounding(RoundingMode::Up);
+    let (mut bank, account_id) = savings_bank(policy, (2024, 1, 1));
+
+    // Whatever the daily rounding, only whole cents already earned are paid.
+    bank.accrue_interest(date(2024, 1, 31)).unwrap();
+    assert_eq!(bank.get_account(account_id).unwrap().balance, cents(1_000_849));
+    assert!(accrued_micros(&bank, account_id) >= 0);
+}
+
+#[test]
+fn test_journal_cannot_post_more_than_accrued() {
+    let path = common::temp_dir("interest-overpaid").join("events.jsonl");
+    let mut bank = Bank::with_journal(MemoryStorage::new(), &path).unwrap();
+    bank.set_clock(at(2024, 1, 1));
+    bank.set_interest_policy(AccountProduct::Savings, InterestPolicy::new(Rate::from_bps(100))).unwrap();
+    let customer_id = common::customer(&mut bank, "Forged Saver");
+    let account_id = common::account(&mut bank, customer_id, AccountProduct::Savings, 10_000);
+    bank.accrue_interest(date(2024, 1, 20)).unwrap();
+    let sequence = bank.last_sequence();
+    drop(bank);
+
+    let mut journal = EventJournal::open(&path).unwrap();
+    journal.append(&JournalRecord {
+        sequence: sequence + 1,
+        timestamp: Utc.with_ymd_and_hms(2024, 1, 21, 10, 0, 0).unwrap(),
+        channel: None,
+        event: Event::InterestPosted { account_id, on: date(2024, 1, 20), amount: usd(10) },
+    }).unwrap();
+    assert_eq!(Bank::replay(&path).err(), Some(BankError::InvalidAmount(usd(10))));
+}
+
+#[test]
+fn test_rate_changes_are_effective_dated() {
+    let (mut bank, account_id) = savings_bank(InterestPolicy::new(Rate::from_bps(100)), (2024, 1, 1));
+    bank.accrue_interest(date(2024, 1, 10)).unwrap();
+    assert_eq!(accrued_micros(&bank, account_id), 10 * 27_397_260);
+
+    // A change back-dated into days already accrued leaves them alone.
+    bank.schedule_rate_change(AccountProduct::Savings, date(2024, 1, 5), RateTable::flat(Rate::from_bps(100))).unwrap();
+    bank.schedule_rate_change(AccountProduct::Savings, date(2024, 1, 16), RateTable::flat(Rate::from_bps(300))).unwrap();
+    bank.accrue_interest(date(2024, 1, 20)).unwrap();
+    assert_eq!(accrued_micros(&bank, account_id), 15 * 27_397_260 + 5 * 82_191_781);
+
+    let mut schedule = RateSchedule::new(RateTable::flat(Rate::from_bps(100)));
+    schedule.change_from(date(2024, 3, 1), RateTable::flat(Rate::from_bps(200)));
+    schedule.change_from(date(2024, 2, 1), RateTable::flat(Rate::from_bps(150)));
+    assert_eq!(schedule.table_on(date(2024, 1, 31)).tiers()[0].rate, Rate::from_bps(100));
+    assert_eq!(schedule.table_on(date(2024, 2, 15)).tiers()[0].rate, Rate::from_bps(150));
+    assert_eq!(schedule.table_on(date(2025, 1, 1)).tiers()[0].rate, Rate::from_bps(200));
+
+    assert!(matches!(
+        bank.schedule_rate_change(AccountProduct::MoneyMarket, date(2024, 2, 1), RateTable::flat(Rate::ZERO)),
+        Err(BankError::InvalidField { field: "interest policy", .. })
+    ));
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use banksystem_rs::bank::{AccountId, AccountProduct, Bank, BankError, CustomerId, JsonFileStorage, MemoryStorage};
+use banksystem_rs::bank::journal::{Event, EventJournal};
+use common::usd;
+
+fn temp_journal(name: &str) -> PathBuf {
+    common::temp_dir(&format!("journal-{}", name)).join("events.jsonl")
+}
+
+fn snapshot(bank: &Bank) -> String {
+    serde_json::to_string(bank).unwrap()
+}
+
+fn populate(bank: &mut Bank<MemoryStorage>) -> (CustomerId, AccountId, AccountId) {
+    let customer_id = bank.create_customer(
+        "Journal User".to_string(),
+        "1 Log St".to_string(),
+        "555-4444".to_string(),
+        "journal@example.com".to_string(),
+    ).unwrap();
+    let checking = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    let savings = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.deposit(checking, usd(300)).unwrap();
+    bank.withdraw(checking, usd(50)).unwrap();
+    bank.transfer(checking, savings, usd(100), Some("save".to_string())).unwrap();
+    bank.update_customer(customer_id, None, Some("2 Log St".to_string()), None, None).unwrap();
+    (customer_id, checking, savings)
+}
+
+#[test]
+fn test_replay_rebuilds_identical_bank() {
+    let path = temp_journal("replay");
+    let mut bank = Bank::with_journal(MemoryStorage::new(), &path).unwrap();
+    populate(&mut bank);
+
+    let records = EventJournal::read(&path).unwrap();
+    assert_eq!(records.len(), 7);
+    assert_eq!(bank.last_sequence(), 7);
+    assert!(matches!(records[0].event, Event::CustomerCreated { id: CustomerId(1), .. }));
+
+    let replayed = Bank::replay(&path).unwrap();
+    assert_eq!(snapshot(&replayed), snapshot(&bank));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_failed_operations_are_not_journaled() {
+    let path = temp_journal("failures");
+    let mut bank = Bank::with_journal(MemoryStorage::new(), &path).unwrap();
+    let (customer_id, checking, savings) = populate(&mut bank);
+
+    assert!(bank.withdraw(checking, usd(1_000)).is_err());
+    assert!(bank.transfer(savings, AccountId(999), usd(1), None).is_err());
+    assert!(bank.create_account(CustomerId(999), AccountProduct::Checking).is_err());
+    assert!(bank.update_customer(customer_id, None, None, None, Some("bad".to_string())).is_err());
+
+    assert_eq!(EventJournal::read(&path).unwrap().len(), 7);
+    assert_eq!(snapshot(&Bank::replay(&path).unwrap()), snapshot(&bank));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_storage_flush_failures_do_not_undo_committed_events() {
+    let path = temp_journal("flush-failure");
+    // The snapshot's directory doesn't exist, so every flush fails.
+    let storage = JsonFileStorage::open(path.with_file_name("missing").join("bank.json")).unwrap();
+    let mut bank = Bank::with_journal(storage, &path).unwrap();
+    let customer_id = bank.create_customer(
+        "Flush Failure".to_string(),
+        "1 Full Disk Rd".to_string(),
+        "555-0000".to_string(),
+        "full@example.com".to_string(),
+    ).unwrap();
+    let checking = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    bank.set_overdraft_fee(AccountProduct::Checking, usd(25)).unwrap();
+    bank.set_overdraft_limit(checking, usd(100)).unwrap();
+
+    // The withdrawal and the fee it triggers both go through.
+    bank.withdraw(checking, usd(50)).unwrap();
+    assert_eq!(bank.get_account(checking).unwrap().balance, usd(-75));
+    let replayed = Bank::replay(&path).unwrap();
+    assert_eq!(replayed.get_account(checking).unwrap().balance, usd(-75));
+    assert_eq!(EventJournal::read(&path).unwrap().len(), bank.last_sequence() as usize);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_storage_flush_failures_without_a_journal_are_returned() {
+    let dir = common::temp_dir("journal-unjournaled-flush-failure");
+    let storage = JsonFileStorage::open(dir.join("missing").join("bank.json")).unwrap();
+    let mut bank = Bank::with_storage(storage).unwrap();
+    let result = bank.create_customer(
+        "Flush Failure".to_string(),
+        "1 Full Disk Rd".to_string(),
+        "555-0000".to_string(),
+        "full@example.com".to_string(),
+    );
+    assert!(matches!(result, Err(BankError::Storage(_))));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_replay_until_past_event() {
+    let path = temp_journal("point-in-time");
+    let mut bank = Bank::with_journal(MemoryStorage::new(), &path).unwrap();
+    let (_, checking, savings) = populate(&mut bank);
+
+    // After the deposit (event 4) but before the withdrawal.
+    let past = Bank::replay_until(&path, 4).unwrap();
+    assert_eq!(past.get_account(checking).unwrap().balance, usd(300));
+    assert_eq!(past.get_account(savings).unwrap().balance, usd(0));
+    assert_eq!(past.last_sequence(), 4);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_recovers_after_torn_write() {
+    let path = temp_journal("recovery");
+    let expected = {
+        let mut bank = Bank::with_journal(MemoryStorage::new(), &path).unwrap();
+        populate(&mut bank);
+        snapshot(&bank)
+    };
+
+    // Simulate a crash halfway through writing the next event.
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(br#"{"sequence":8,"timestamp":"2024-01"#).unwrap();
+    drop(file);
+
+    let mut recovered = Bank::with_journal(MemoryStorage::new(), &path).unwrap();
+    assert_eq!(snapshot(&recovered), expected);
+    assert_eq!(recovered.last_sequence(), 7);
+
+    // New events continue cleanly after the truncated tail.
+    let checking = recovered.list_accounts()[0].id;
+    recovered.deposit(checking, usd(5)).unwrap();
+    let records = EventJournal::read(&path).unwrap();
+    assert_eq!(records.len(), 8);
+    assert_eq!(records[7].sequence, 8);
+    assert_eq!(snapshot(&Bank::replay(&path).unwrap()), snapshot(&recovered));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_ledger_overflow_is_caught_before_journaling() {
+    let path = temp_journal("ledger-overflow");
+    let mut bank = Bank::with_journal(MemoryStorage::new(), &path).unwrap();
+    let (_, checking, savings) = populate(&mut bank);
+    let huge = common::cents(i64::MAX - 1_000_000);
+    bank.deposit(checking, huge).unwrap();
+
+    // Savings can hold the amount, but the bank's cash on hand can't.
+    assert!(bank.deposit(savings, huge).is_err());
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(100));
+
+    assert_eq!(EventJournal::read(&path).unwrap().len(), 8);
+    assert_eq!(snapshot(&Bank::replay(&path).unwrap()), snapshot(&bank));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_replays_free_text_account_type() {
+    let path = temp_journal("legacy-product");
+    let checking = {
+        let mut bank = Bank::with_journal(MemoryStorage::new(), &path).unwrap();
+        populate(&mut bank).1
+    };
+
+    // Journals written before products were an enum stored free text.
+    let old = fs::read_to_string(&path).unwrap().replacen(r#""product":"Checking""#, r#""account_type":"Business""#, 1);
+    assert!(old.contains("Business"));
+    fs::write(&path, old).unwrap();
+
+    let bank = Bank::replay(&path).unwrap();
+    let account = bank.get_account(checking).unwrap();
+    assert_eq!(account.product, AccountProduct::Checking);
+    assert_eq!(account.balance, usd(150));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use banksystem_rs::bank::{AccountId, AccountProduct, Bank, BankError, Currency, Money};
+use banksystem_rs::bank::ledger::{Ledger, LedgerAccount, Posting};
+use chrono::Utc;
+use common::usd;
+
+fn bank_with_accounts() -> (Bank, AccountId, AccountId) {
+    let mut bank = Bank::new();
+    let customer_id = bank.create_customer(
+        "Ledger User".to_string(),
+        "1 Ledger Rd".to_string(),
+        "555-1111".to_string(),
+        "ledger@example.com".to_string(),
+    ).unwrap();
+    let checking = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    let savings = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    (bank, checking, savings)
+}
+
+#[test]
+fn test_operations_post_balanced_entries() {
+    let (mut bank, checking, savings) = bank_with_accounts();
+    bank.deposit(checking, usd(500)).unwrap();
+    bank.withdraw(checking, usd(120)).unwrap();
+    bank.transfer(checking, savings, usd(80), None).unwrap();
+
+    let ledger = bank.ledger();
+    assert_eq!(ledger.entries().len(), 3);
+    assert!(ledger.entries().iter().all(|e| e.validate().is_ok()));
+
+    assert_eq!(ledger.balance(LedgerAccount::Cash, Currency::USD), usd(380));
+    assert_eq!(ledger.balance(LedgerAccount::Customer(checking), Currency::USD), usd(300));
+    assert_eq!(ledger.balance(LedgerAccount::Customer(savings), Currency::USD), usd(80));
+
+    // Account balances are the ledger balances.
+    assert_eq!(bank.get_account(checking).unwrap().balance, usd(300));
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(80));
+}
+
+#[test]
+fn test_trial_balance() {
+    let (mut bank, checking, savings) = bank_with_accounts();
+    bank.deposit(checking, usd(200)).unwrap();
+    bank.transfer(checking, savings, usd(50), None).unwrap();
+
+    let trial_balance = bank.trial_balance();
+    assert!(trial_balance.is_balanced());
+    assert_eq!(trial_balance.totals()[&Currency::USD], (usd(200), usd(200)));
+
+    let cash = trial_balance.lines.iter().find(|l| l.account == LedgerAccount::Cash).unwrap();
+    assert_eq!((cash.debit, cash.credit), (usd(200), usd(0)));
+    let savings_line = trial_balance.lines.iter().find(|l| l.account == LedgerAccount::Customer(savings)).unwrap();
+    assert_eq!((savings_line.debit, savings_line.credit), (usd(0), usd(50)));
+}
+
+#[test]
+fn test_rejects_unbalanced_entries() {
+    let mut ledger = Ledger::new();
+    let result = ledger.post(Utc::now(), "bad".to_string(), vec![
+        Posting::debit(LedgerAccount::Cash, usd(10)),
+        Posting::credit(LedgerAccount::Customer(AccountId(1)), usd(9)),
+    ]);
+    assert!(matches!(result, Err(BankError::UnbalancedEntry(_))));
+
+    let mixed = ledger.post(Utc::now(), "mixed currencies".to_string(), vec![
+        Posting::debit(LedgerAccount::Cash, usd(10)),
+        Posting::credit(LedgerAccount::Customer(AccountId(1)), Money::from_major(10, Currency::EUR).unwrap()),
+    ]);
+    assert!(matches!(mixed, Err(BankError::UnbalancedEntry(_))));
+    assert!(ledger.entries().is_empty());
+    assert!(ledger.trial_balance().lines.is_empty());
+}
+
+#[test]
+fn test_failed_operations_post_nothing() {
+    let (mut bank, checking, savings) = bank_with_accounts();
+    assert!(bank.withdraw(checking, usd(1)).is_err());
+    assert!(bank.transfer(checking, savings, usd(1), None).is_err());
+    assert!(bank.ledger().entries().is_empty());
+}
+
+#[test]
+fn test_ledger_serde_round_trip() {
+    let (mut bank, checking, _) = bank_with_accounts();
+    bank.deposit(checking, usd(75)).unwrap();
+
+    let json = serde_json::to_string(bank.ledger()).unwrap();
+    let restored: Ledger = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.entries(), bank.ledger().entries());
+    assert_eq!(restored.balance(LedgerAccount::Cash, Currency::USD), usd(75));
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use banksystem_rs::bank::{AccountId, AccountProduct, AccountStatus, Bank, BankError, HoldKind, LifecyclePolicy};
+use banksystem_rs::bank::fees::FeeSchedule;
+use banksystem_rs::bank::interest::Rate;
+use banksystem_rs::bank::loan::{LoanTerms, RepaymentFrequency};
+use common::{at, date, usd};
+
+/// A bank on 1 January 2024 with two funded checking accounts of one
+/// customer.
+fn two_accounts(bank: &mut Bank) -> (AccountId, AccountId) {
+    bank.set_clock(at(2024, 1, 1));
+    let customer_id = common::customer(bank, "Life Cycle");
+    let first = common::account(bank, customer_id, AccountProduct::Checking, 100);
+    let second = common::account(bank, customer_id, AccountProduct::Checking, 100);
+    (first, second)
+}
+
+#[test]
+fn test_status_transitions() {
+    use AccountStatus::*;
+    assert!(Active.can_change_to(Frozen));
+    assert!(Active.can_change_to(Closed));
+    assert!(!Frozen.can_change_to(Closed));
+    assert!(!Frozen.can_change_to(Dormant));
+    assert!(Dormant.can_change_to(Active));
+    assert!(!Closed.can_change_to(Frozen));
+    assert!(!Active.can_change_to(Active));
+    assert!(Dormant.allows_credits() && !Dormant.allows_debits());
+    assert!(!Closed.allows_credits());
+}
+
+#[test]
+fn test_frozen_accounts_block_debits() {
+    let mut bank = Bank::new();
+    let (frozen, other) = two_accounts(&mut bank);
+    bank.freeze_account(frozen).unwrap();
+
+    let blocked = BankError::AccountStatus { account_id: frozen, status: AccountStatus::Frozen };
+    assert_eq!(bank.withdraw(frozen, usd(10)), Err(blocked.clone()));
+    assert_eq!(bank.transfer(frozen, other, usd(10), None), Err(blocked.clone()));
+    assert_eq!(blocked.to_string(), format!("account {} is frozen", frozen));
+
+    // Money can still come in.
+    bank.deposit(frozen, usd(10)).unwrap();
+    bank.transfer(other, frozen, usd(10), None).unwrap();
+    assert_eq!(bank.get_account(frozen).unwrap().balance, usd(120));
+
+    // A frozen account must be unfrozen before it can be closed.
+    assert!(matches!(bank.close_account(frozen), Err(BankError::InvalidField { field: "account status", .. })));
+    bank.unfreeze_account(frozen).unwrap();
+    assert!(bank.unfreeze_account(frozen).is_err());
+    bank.withdraw(frozen, usd(120)).unwrap();
+    bank.close_account(frozen).unwrap();
+}
+
+#[test]
+fn test_closing_and_reopening() {
+    let mut bank = Bank::new();
+    let (account_id, other) = two_accounts(&mut bank);
+    bank.set_fee_schedule(AccountProduct::Checking, FeeSchedule::new().with_monthly_maintenance(usd(1))).unwrap();
+
+    assert!(matches!(bank.close_account(account_id), Err(BankError::InvalidField { field: "account status", .. })));
+    bank.transfer(account_id, other, usd(100), None).unwrap();
+    bank.set_clock(at(2024, 1, 20));
+    bank.close_account(account_id).unwrap();
+
+    let account = bank.get_account(account_id).unwrap();
+    assert_eq!(account.status, AccountStatus::Closed);
+    assert_eq!(account.closed_at, Some(Utc.with_ymd_and_hms(2024, 1, 20, 10, 0, 0).unwrap()));
+    // A final statement covers the closing month up to the closing day.
+    let statement = account.closing_statement.as_ref().unwrap();
+    assert_eq!((statement.period_start, statement.period_end), (date(2024, 1, 1), date(2024, 1, 20)));
+    assert_eq!(statement.lines.len(), 2);
+    assert_eq!(statement.total_credits, usd(100));
+    assert_eq!(statement.closing_balance, usd(0));
+    let closed = Err(BankError::AccountStatus { account_id, status: AccountStatus::Closed });
+    assert_eq!(bank.deposit(account_id, usd(1)), closed);
+    assert_eq!(bank.transfer(other, account_id, usd(1), None), closed);
+    assert_eq!(bank.set_overdraft_limit(account_id, usd(50)), closed);
+
+    // Closed accounts are skipped by month-end runs.
+    bank.assess_fees(date(2024, 1, 31)).unwrap();
+    assert_eq!(bank.get_account(account_id).unwrap().balance, usd(0));
+    assert_eq!(bank.get_account(other).unwrap().balance, usd(199));
+
+    // Reopening is allowed within 90 days of closing, and only then.
+    bank.set_clock(at(2024, 4, 18));
+    bank.reopen_account(account_id).unwrap();
+    bank.deposit(account_id, usd(5)).unwrap();
+    assert!(bank.reopen_account(account_id).is_err());
+
+    bank.withdraw(account_id, usd(5)).unwrap();
+    bank.close_account(account_id).unwrap();
+    let statement = bank.get_account(account_id).unwrap().closing_statement.clone().unwrap();
+    assert_eq!(statement.period_start, date(2024, 4, 1));
+    assert_eq!(statement.total_debits, usd(5));
+    bank.set_clock(at(2024, 7, 20));
+    assert!(matches!(bank.reopen_account(account_id), Err(BankError::InvalidField { field: "account status", .. })));
+    assert_eq!(bank.get_account(account_id).unwrap().status, AccountStatus::Closed);
+}
+
+#[test]
+fn test_dormancy() {
+    let mut bank = Bank::new();
+    bank.set_lifecycle_policy(LifecyclePolicy { dormant_after_months: 6, ..LifecyclePolicy::default() }).unwrap();
+    let (idle, busy) = two_accounts(&mut bank);
+    bank.set_fee_schedule(AccountProduct::Checking, FeeSchedule::new().with_monthly_maintenance(usd(1))).unwrap();
+    bank.set_clock(at(2024, 5, 1));
+    bank.deposit(busy, usd(1)).unwrap();
+
+    // Monthly fees are not customer activity.
+    bank.assess_fees(date(2024, 6, 30)).unwrap();
+    assert_eq!(bank.flag_dormant_accounts(date(2024, 6, 30)).unwrap(), vec![]);
+    assert_eq!(bank.flag_dormant_accounts(date(2024, 7, 1)).unwrap(), vec![idle]);
+    assert_eq!(bank.get_account(idle).unwrap().status, AccountStatus::Dormant);
+    assert_eq!(bank.get_account(busy).unwrap().status, AccountStatus::Active);
+    assert_eq!(bank.flag_dormant_accounts(date(2024, 7, 1)).unwrap(), vec![]);
+
+    assert_eq!(
+        bank.withdraw(idle, usd(1)),
+        Err(BankError::AccountStatus { account_id: idle, status: AccountStatus::Dormant })
+    );
+    bank.deposit(idle, usd(1)).unwrap();
+    bank.reactivate_account(idle).unwrap();
+    bank.withdraw(idle, usd(1)).unwrap();
+    assert!(bank.reactivate_account(idle).is_err());
+}
+
+#[test]
+fn test_only_customer_moves_keep_accounts_active() {
+    let mut bank = Bank::new();
+    bank.set_lifecycle_policy(LifecyclePolicy { dormant_after_months: 6, ..LifecyclePolicy::default() }).unwrap();
+    let (idle, carded) = two_accounts(&mut bank);
+    let customer_id = bank.get_account(idle).unwrap().customer_id;
+    bank.set_clock(at(2024, 5, 1));
+
+    // A loan paid into an idle account is the bank's doing, not the
+    // customer's; spending on a card is the customer's.
+    let terms = LoanTerms::new(usd(1_000), Rate::from_bps(600), 12, RepaymentFrequency::Monthly);
+    bank.open_loan(customer_id, idle, terms).unwrap();
+    let hold_id = bank.place_hold(carded, HoldKind::CardAuthorization, usd(10), None).unwrap();
+    bank.capture_hold(carded, hold_id, usd(10)).unwrap();
+
+    assert_eq!(bank.flag_dormant_accounts(date(2024, 7, 1)).unwrap(), vec![idle]);
+    assert_eq!(bank.get_account(carded).unwrap().status, AccountStatus::Active);
+}
+
+#[test]
+fn test_lifecycle_policy_is_validated() {
+    let mut bank = Bank::new();
+    let never_dormant = LifecyclePolicy { dormant_after_months: 0, ..LifecyclePolicy::default() };
+    assert!(matches!(bank.set_lifecycle_policy(never_dormant), Err(BankError::InvalidField { .. })));
+    let negative = LifecyclePolicy { reopen_within_days: -1, ..LifecyclePolicy::default() };
+    assert!(matches!(bank.set_lifecycle_policy(negative), Err(BankError::InvalidField { .. })));
+    assert_eq!(bank.lifecycle_policy(), LifecyclePolicy::default());
+}
+
+#[test]
+fn test_status_survives_save_and_replay() {
+    let (mut replayed, (frozen, closed)) = common::replayed(|bank| {
+        let (frozen, closed) = two_accounts(bank);
+        bank.set_lifecycle_policy(LifecyclePolicy { reopen_within_days: 10, ..LifecyclePolicy::default() }).unwrap();
+        bank.freeze_account(frozen).unwrap();
+        bank.withdraw(closed, usd(100)).unwrap();
+        bank.set_clock(at(2024, 2, 1));
+        bank.close_account(closed).unwrap();
+        (frozen, closed)
+    });
+
+    assert_eq!(replayed.get_account(frozen).unwrap().status, AccountStatus::Frozen);
+    assert!(matches!(replayed.withdraw(frozen, usd(1)), Err(BankError::AccountStatus { .. })));
+    let account = replayed.get_account(closed).unwrap();
+    assert_eq!(account.status, AccountStatus::Closed);
+    assert_eq!(account.closed_at.map(|closed_at| closed_at.date_naive()), Some(date(2024, 2, 1)));
+
+    // The shorter reopening window still applies.
+    assert_eq!(replayed.lifecycle_policy().reopen_within_days, 10);
+    replayed.set_clock(at(2024, 2, 20));
+    assert!(matches!(replayed.reopen_account(closed), Err(BankError::InvalidField { field: "account status", .. })));
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use banksystem_rs::bank::{AccountId, AccountProduct, Bank, BankError, Currency, Money, MoneyError, TransactionType};
+use banksystem_rs::bank::interest::Rate;
+use banksystem_rs::bank::ledger::LedgerAccount;
+use banksystem_rs::bank::loan::{Amortization, LoanTerms, Recalculation, RepaymentFrequency};
+use common::{at, cents, date, usd};
+
+/// 10,000 at 6% a year over twelve monthly annuity repayments.
+fn car_loan() -> LoanTerms {
+    LoanTerms::new(usd(10_000), Rate::from_bps(600), 12, RepaymentFrequency::Monthly)
+}
+
+/// A customer with a checking account holding `funds`, on 31 January 2024.
+fn borrower(bank: &mut Bank, funds: i64) -> AccountId {
+    bank.set_clock(at(2024, 1, 31));
+    let customer_id = common::customer(bank, "Loan Taker");
+    common::account(bank, customer_id, AccountProduct::Checking, funds)
+}
+
+#[test]
+fn test_annuity_schedule() {
+    let schedule = car_loan().schedule(date(2024, 1, 31)).unwrap();
+    assert_eq!(schedule.len(), 12);
+    assert_eq!(schedule[0].payment, cents(86_066));
+    assert_eq!(schedule[0].interest, usd(50));
+    assert_eq!(schedule[0].principal, cents(81_066));
+    assert_eq!(schedule[0].remaining, cents(918_934));
+    assert!(schedule[..11].iter().all(|i| i.payment == cents(86_066)));
+    assert!(schedule.windows(2).all(|w| w[1].interest < w[0].interest));
+
+    // Due dates count from the disbursement, so month ends don't drift.
+    assert_eq!(schedule[0].due, date(2024, 2, 29));
+    assert_eq!(schedule[1].due, date(2024, 3, 31));
+    assert_eq!(schedule[11].due, date(2025, 1, 31));
+
+    let last = &schedule[11];
+    assert_eq!(last.remaining, usd(0));
+    let total_principal = schedule.iter().fold(usd(0), |sum, i| sum.checked_add(i.principal).unwrap());
+    assert_eq!(total_principal, usd(10_000));
+}
+
+#[test]
+fn test_annuity_payment_is_exact_over_long_terms() {
+    // The textbook 30-year mortgage: 300,000 at 6.5% is 1,896.20 a month.
+    let mortgage = LoanTerms::new(usd(300_000), Rate::from_bps(650), 360, RepaymentFrequency::Monthly);
+    let schedule = mortgage.schedule(date(2024, 1, 1)).unwrap();
+    assert_eq!(schedule.len(), 360);
+    assert_eq!(schedule[0].payment, cents(189_620));
+    assert!(schedule[..359].iter().all(|i| i.payment == cents(189_620)));
+    assert_eq!(schedule[359].remaining, usd(0));
+
+    // Amounts too big to work with are an error, not a wrong payment.
+    let huge = LoanTerms::new(Money::from_minor(i64::MAX, Currency::USD), Rate::from_bps(650), 360, RepaymentFrequency::Monthly);
+    assert!(matches!(huge.schedule(date(2024, 1, 1)), Err(BankError::Money(MoneyError::Overflow))));
+}
+
+#[test]
+fn test_equal_principal_and_other_schedules() {
+    let terms = LoanTerms::new(usd(12_000), Rate::from_bps(1_200), 12, RepaymentFrequency::Monthly)
+        .with_amortization(Amortization::EqualPrincipal);
+    let schedule = terms.schedule(date(2024, 1, 1)).unwrap();
+    assert!(schedule.iter().all(|i| i.principal == usd(1_000)));
+    assert_eq!(schedule[0].payment, usd(1_120));
+    assert_eq!(schedule[11].payment, usd(1_010));
+
+    let interest_free = LoanTerms::new(usd(1_000), Rate::ZERO, 3, RepaymentFrequency::Monthly);
+    let payments: Vec<Money> = interest_free.schedule(date(2024, 1, 1)).unwrap().iter().map(|i| i.payment).collect();
+    assert_eq!(payments, vec![cents(33_334), cents(33_334), cents(33_332)]);
+
+    let quarterly = LoanTerms::new(usd(4_000), Rate::from_bps(800), 4, RepaymentFrequency::Quarterly);
+    let schedule = quarterly.schedule(date(2024, 1, 15)).unwrap();
+    assert_eq!(schedule[0].interest, usd(80));
+    assert_eq!(schedule[3].due, date(2025, 1, 15));
+
+    assert!(LoanTerms::new(usd(0), Rate::ZERO, 3, RepaymentFrequency::Monthly).schedule(date(2024, 1, 1)).is_err());
+    assert!(matches!(
+        LoanTerms::new(usd(1), Rate::ZERO, 0, RepaymentFrequency::Monthly).validate(),
+        Err(BankError::InvalidField { field: "loan term", .. })
+    ));
+}
+
+#[test]
+fn test_disbursement_and_repayment() {
+    let mut bank = Bank::new();
+    let checking = borrower(&mut bank, 1_000);
+    let customer_id = bank.get_account(checking).unwrap().customer_id;
+    let loan_id = bank.open_loan(customer_id, checking, car_loan()).unwrap();
+
+    let loan_account = bank.get_account(loan_id).unwrap();
+    assert_eq!(loan_account.product, AccountProduct::Loan);
+    assert_eq!(loan_account.balance, usd(-10_000));
+    assert_eq!(bank.get_account(checking).unwrap().balance, usd(11_000));
+    let credit = bank.get_account(checking).unwrap().get_history().last().unwrap().clone();
+    assert_eq!(credit.tx_type, TransactionType::TransferIn { from_account: loan_id });
+    assert_eq!(credit.memo.as_deref(), Some("Loan disbursement"));
+    assert_eq!(bank.list_customer_accounts(customer_id).len(), 2);
+
+    bank.set_clock(at(2024, 2, 29));
+    let first = bank.repay_loan(loan_id).unwrap();
+    assert_eq!((first.number, first.interest, first.principal), (1, usd(50), cents(81_066)));
+    assert_eq!(bank.outstanding_principal(loan_id).unwrap(), cents(918_934));
+    assert_eq!(bank.get_account(loan_id).unwrap().balance, cents(-918_934));
+    assert_eq!(bank.get_account(checking).unwrap().balance, cents(1_013_934));
+    assert_eq!(bank.ledger().balance(LedgerAccount::InterestIncome, Currency::USD), usd(50));
+    assert!(bank.trial_balance().is_balanced());
+
+    let loan = bank.loan(loan_id).unwrap();
+    assert_eq!(loan.next_installment().unwrap().number, 2);
+    assert_eq!(loan.outstanding_on(date(2024, 2, 28)).unwrap(), usd(10_000));
+    assert_eq!(loan.outstanding_on(date(2024, 2, 29)).unwrap(), cents(918_934));
+    assert_eq!(loan.outstanding_on(date(2024, 1, 1)).unwrap(), usd(0));
+    assert_eq!(loan.interest_paid().unwrap(), usd(50));
+
+    // Repaying everything lets the loan account close.
+    for _ in 2..=12 {
+        bank.repay_loan(loan_id).unwrap();
+    }
+    assert!(bank.loan(loan_id).unwrap().is_repaid());
+    assert_eq!(bank.get_account(loan_id).unwrap().balance, usd(0));
+    assert!(matches!(bank.repay_loan(loan_id), Err(BankError::InvalidField { field: "loan", .. })));
+    bank.close_account(loan_id).unwrap();
+}
+
+#[test]
+fn test_extra_payments_redraw_the_schedule() {
+    let mut bank = Bank::new();
+    let checking = borrower(&mut bank, 5_000);
+    let customer_id = bank.get_account(checking).unwrap().customer_id;
+    let shorter = bank.open_loan(customer_id, checking, car_loan()).unwrap();
+    let cheaper = bank.open_loan(customer_id, checking, car_loan()).unwrap();
+
+    bank.set_clock(at(2024, 2, 29));
+    for loan_id in [shorter, cheaper] {
+        bank.repay_loan(loan_id).unwrap();
+    }
+    bank.prepay_loan(shorter, usd(3_000), Recalculation::ShortenTerm).unwrap();
+    bank.prepay_loan(cheaper, usd(3_000), Recalculation::ReducePayment).unwrap();
+
+    let loan = bank.loan(shorter).unwrap();
+    assert_eq!(loan.outstanding_principal, cents(618_934));
+    assert_eq!(loan.schedule[0].number, 2);
+    assert_eq!(loan.schedule[0].payment, cents(86_066));
+    assert_eq!(loan.schedule.len(), 8);
+    assert_eq!(loan.schedule.last().unwrap().remaining, usd(0));
+
+    let loan = bank.loan(cheaper).unwrap();
+    assert_eq!(loan.schedule.len(), 11);
+    assert_eq!(loan.schedule[0].due, date(2024, 3, 31));
+    assert!(loan.schedule[0].payment < cents(86_066));
+    assert_eq!(loan.repayments.last().unwrap().installment, None);
+    assert_eq!(bank.get_account(cheaper).unwrap().balance, cents(-618_934));
+
+    assert!(matches!(
+        bank.prepay_loan(cheaper, usd(7_000), Recalculation::ReducePayment),
+        Err(BankError::InvalidField { field: "extra payment", .. })
+    ));
+    bank.deposit(checking, usd(10_000)).unwrap();
+    bank.prepay_loan(cheaper, cents(618_934), Recalculation::ReducePayment).unwrap();
+    assert!(bank.loan(cheaper).unwrap().is_repaid());
+    assert!(bank.loan(cheaper).unwrap().schedule.is_empty());
+    assert!(bank.trial_balance().is_balanced());
+}
+
+#[test]
+fn test_loan_errors() {
+    let mut bank = Bank::new();
+    let checking = borrower(&mut bank, 0);
+    let customer_id = bank.get_account(checking).unwrap().customer_id;
+    let stranger = common::customer(&mut bank, "Someone Else");
+    let accounts_before = bank.list_accounts().len();
+    assert!(matches!(
+        bank.open_loan(stranger, checking, car_loan()),
+        Err(BankError::InvalidField { field: "linked account", .. })
+    ));
+    let euros = LoanTerms::new(Money::from_major(5_000, Currency::EUR).unwrap(), Rate::from_bps(600), 12, RepaymentFrequency::Monthly);
+    assert!(bank.open_loan(customer_id, checking, euros).is_err());
+    assert_eq!(bank.list_accounts().len(), accounts_before);
+
+    let loan_id = bank.open_loan(customer_id, checking, car_loan()).unwrap();
+    bank.withdraw(checking, usd(10_000)).unwrap();
+    assert!(matches!(bank.repay_loan(loan_id), Err(BankError::InsufficientFunds { .. })));
+    assert_eq!(bank.outstanding_principal(loan_id).unwrap(), usd(10_000));
+    assert!(matches!(bank.outstanding_principal(checking), Err(BankError::InvalidField { field: "loan", .. })));
+    assert!(bank.withdraw(loan_id, usd(1)).is_err());
+    assert!(bank.prepay_loan(loan_id, usd(0), Recalculation::ShortenTerm).is_err());
+}
+
+#[test]
+fn test_loans_survive_save_and_replay() {
+    let (replayed, (loan_id, original)) = common::replayed(|bank| {
+        let checking = borrower(bank, 1_000);
+        let customer_id = bank.get_account(checking).unwrap().customer_id;
+        let loan_id = bank.open_loan(customer_id, checking, car_loan()).unwrap();
+        bank.set_clock(at(2024, 2, 29));
+        bank.repay_loan(loan_id).unwrap();
+        bank.prepay_loan(loan_id, usd(500), Recalculation::ShortenTerm).unwrap();
+        (loan_id, bank.loan(loan_id).unwrap().clone())
+    });
+
+    // The shortened schedule is redrawn the same way on replay.
+    let loan = replayed.loan(loan_id).unwrap();
+    assert_eq!(loan, &original);
+    assert!(loan.schedule.len() < 12);
+    assert_eq!(replayed.outstanding_principal(loan_id).unwrap(), original.outstanding_principal);
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use banksystem_rs::bank::{
+    AccountId, AccountProduct, Bank, Channel, Transaction, TransactionDetails, TransactionId,
+    TransactionType,
+};
+use banksystem_rs::bank::interest::{InterestPolicy, Rate};
+use banksystem_rs::bank::schedule::{Recurrence, StandingOrder};
+use common::{at, date, usd};
+
+/// Two customers with a checking account each, the first holding 1,000,
+/// on 10 May 2024.
+fn two_customers(bank: &mut Bank) -> (AccountId, AccountId) {
+    bank.set_clock(at(2024, 5, 10));
+    let payer = common::customer(bank, "Paying Party");
+    let payee = common::customer(bank, "Receiving Party");
+    let from = bank.create_account(payer, AccountProduct::Checking).unwrap();
+    let to = bank.create_account(payee, AccountProduct::Checking).unwrap();
+    bank.deposit(from, usd(1_000)).unwrap();
+    (from, to)
+}
+
+fn last(bank: &Bank, account_id: AccountId) -> Transaction {
+    bank.get_account(account_id).unwrap().get_history().last().unwrap().clone()
+}
+
+#[test]
+fn test_deposit_details() {
+    let mut bank = Bank::new();
+    let (from, _) = two_customers(&mut bank);
+    assert_eq!(bank.channel(), Channel::Api);
+    bank.set_channel(Channel::Teller);
+    let details = TransactionDetails::default().with_memo("Birthday money").with_reference("CHQ-100234");
+    bank.deposit_with_details(from, usd(50), details).unwrap();
+
+    let tx = last(&bank, from);
+    assert_ne!(tx.id, TransactionId::default());
+    assert_eq!(tx.memo.as_deref(), Some("Birthday money"));
+    assert_eq!(tx.reference.as_deref(), Some("CHQ-100234"));
+    assert_eq!(tx.channel, Some(Channel::Teller));
+    assert_eq!(tx.balance_after, Some(usd(1_050)));
+    assert_eq!((tx.value_date, tx.booking_date), (Some(date(2024, 5, 10)), Some(date(2024, 5, 10))));
+    assert_eq!(tx.counterparty, None);
+
+    let opening = &bank.get_account(from).unwrap().get_history()[0];
+    assert_eq!(opening.channel, Some(Channel::Api));
+    assert_eq!(opening.balance_after, Some(usd(1_000)));
+    assert!(opening.id < tx.id);
+}
+
+#[test]
+fn test_transfer_counterparties() {
+    let mut bank = Bank::new();
+    let (from, to) = two_customers(&mut bank);
+    let details = TransactionDetails::default().with_memo("Rent").with_reference("INV-7");
+    bank.transfer_with_details(from, to, usd(400), details).unwrap();
+
+    let debit = last(&bank, from);
+    let credit = last(&bank, to);
+    assert_ne!(debit.id, credit.id);
+    assert_eq!(debit.reference.as_deref(), Some("INV-7"));
+    assert_eq!(credit.reference.as_deref(), Some("INV-7"));
+    assert_eq!(credit.memo.as_deref(), Some("Rent"));
+    assert_eq!((debit.balance_after, credit.balance_after), (Some(usd(600)), Some(usd(400))));
+
+    let payee = debit.counterparty.unwrap();
+    assert_eq!(payee.account_id, to);
+    assert_eq!(payee.account_number, bank.get_account(to).unwrap().number);
+    assert_eq!(payee.holder.as_deref(), Some("Receiving Party"));
+    let payer = credit.counterparty.unwrap();
+    assert_eq!(payer.account_id, from);
+    assert_eq!(payer.holder.as_deref(), Some("Paying Party"));
+
+    // A reversal keeps the reference and counterparty of what it undoes.
+    bank.reverse_transaction(debit.id, "Paid twice".to_string()).unwrap();
+    let reversal = last(&bank, from);
+    assert_eq!(reversal.reference.as_deref(), Some("INV-7"));
+    assert_eq!(reversal.counterparty.unwrap().account_id, to);
+    assert_eq!(reversal.balance_after, Some(usd(1_000)));
+}
+
+#[test]
+fn test_batch_runs_are_booked_by_the_scheduler() {
+    let mut bank = Bank::new();
+    bank.set_interest_policy(AccountProduct::Savings, InterestPolicy::new(Rate::from_bps(500))).unwrap();
+    let (from, _) = two_customers(&mut bank);
+    let customer_id = bank.get_account(from).unwrap().customer_id;
+    let savings = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.set_channel(Channel::Tui);
+    bank.transfer(from, savings, usd(500), None).unwrap();
+
+    // Interest for May is valued at the month end but booked on the day
+    // the run happens.
+    bank.set_clock(at(2024, 6, 3));
+    bank.accrue_interest(date(2024, 6, 2)).unwrap();
+    let interest = last(&bank, savings);
+    assert_eq!(interest.tx_type, TransactionType::Interest);
+    assert_eq!(interest.value_date, Some(date(2024, 5, 31)));
+    assert_eq!(interest.booking_date, Some(date(2024, 6, 3)));
+    assert_eq!(interest.channel, Some(Channel::Scheduler));
+    assert_eq!(interest.balance_after, Some(bank.get_account(savings).unwrap().balance));
+    assert_eq!(bank.channel(), Channel::Tui);
+
+    bank.schedule_transfer(StandingOrder::new(from, savings, usd(20), Recurrence::Once, date(2024, 6, 3))).unwrap();
+    bank.run_due_transfers(date(2024, 6, 3)).unwrap();
+    let scheduled = last(&bank, savings);
+    assert_eq!(scheduled.tx_type, TransactionType::TransferIn { from_account: from });
+    assert_eq!(scheduled.channel, Some(Channel::Scheduler));
+    assert_eq!(scheduled.counterparty.unwrap().account_id, from);
+}
+
+#[test]
+fn test_plain_transfers_fill_in_details() {
+    let mut bank = Bank::new();
+    let (from, to) = two_customers(&mut bank);
+    bank.transfer(from, to, usd(100), Some("Lunch".to_string())).unwrap();
+
+    let debit = last(&bank, from);
+    let credit = last(&bank, to);
+    assert_eq!(debit.channel, Some(Channel::Api));
+    assert_eq!(debit.balance_after, Some(usd(900)));
+    assert_eq!(debit.counterparty.unwrap().account_number, bank.get_account(to).unwrap().number);
+    assert_eq!(credit.memo.as_deref(), Some("Lunch"));
+    assert_eq!(credit.balance_after, Some(usd(100)));
+
+    // Every transaction gets its own ID when it is posted.
+    assert_eq!(bank.get_account(from).unwrap().get_history()[0].id, TransactionId(1));
+    assert_eq!((debit.id, credit.id), (TransactionId(2), TransactionId(3)));
+    assert_eq!((debit.paired_with, credit.paired_with), (Some(credit.id), Some(debit.id)));
+    bank.reverse_transaction(debit.id, "Wrong payee".to_string()).unwrap();
+    let mut reversals = [last(&bank, from).id, last(&bank, to).id];
+    reversals.sort();
+    assert_eq!(reversals, [TransactionId(4), TransactionId(5)]);
+}
+
+#[test]
+fn test_older_transactions_still_load() {
+    let json = r#"{"id":3,"tx_type":"Deposit","amount":{"minor":100,"currency":"USD"},"timestamp":"2023-01-01T00:00:00Z","memo":null}"#;
+    let tx: Transaction = serde_json::from_str(json).unwrap();
+    assert_eq!(tx.id, TransactionId(3));
+    assert_eq!((tx.reference, tx.counterparty, tx.balance_after), (None, None, None));
+    assert_eq!((tx.value_date, tx.booking_date, tx.channel), (None, None, None));
+}
+
+#[test]
+fn test_details_survive_save_and_replay() {
+    let (replayed, (from, to)) = common::replayed(|bank| {
+        let (from, to) = two_customers(bank);
+        bank.set_channel(Channel::Teller);
+        bank.withdraw_with_details(from, usd(30), TransactionDetails::default().with_reference("ATM-42")).unwrap();
+        bank.set_channel(Channel::Tui);
+        bank.transfer_with_details(from, to, usd(70), TransactionDetails::default().with_memo("Split bill")).unwrap();
+        (from, to)
+    });
+
+    // Channels come from the journal records, not the replaying bank.
+    let history = replayed.get_account(from).unwrap().get_history();
+    assert_eq!(history[1].channel, Some(Channel::Teller));
+    assert_eq!(history[1].reference.as_deref(), Some("ATM-42"));
+    assert_eq!(history[2].channel, Some(Channel::Tui));
+    assert_eq!(history[2].balance_after, Some(usd(900)));
+    assert_eq!(replayed.get_account(to).unwrap().get_history()[0].memo.as_deref(), Some("Split bill"));
+}
@@ -0,0 +1,88 @@
+use banksystem_rs::bank::{Currency, Money, MoneyError, RoundingMode};
+
+#[test]
+fn test_no_float_drift() {
+    let a = Money::parse("0.1", Currency::USD).unwrap();
+    let b = Money::parse("0.2", Currency::USD).unwrap();
+    assert_eq!(a.checked_add(b).unwrap(), Money::parse("0.3", Currency::USD).unwrap());
+    assert_eq!(a.checked_add(b).unwrap().minor_units(), 30);
+}
+
+#[test]
+fn test_parse_and_display() {
+    assert_eq!(Money::parse("12.34", Currency::USD).unwrap().minor_units(), 1234);
+    assert_eq!(Money::parse("-5", Currency::EUR).unwrap().minor_units(), -500);
+    assert_eq!(Money::parse("150", Currency::JPY).unwrap().minor_units(), 150);
+    assert_eq!(Money::from_minor(-1205, Currency::USD).to_string(), "-12.05 USD");
+    assert_eq!(Money::from_major(7, Currency::JPY).unwrap().to_string(), "7 JPY");
+
+    assert!(matches!(Money::parse("1.234", Currency::USD), Err(MoneyError::Precision { .. })));
+    assert!(matches!(Money::parse("abc", Currency::USD), Err(MoneyError::InvalidFormat(_))));
+    assert!(matches!(Money::parse("", Currency::USD), Err(MoneyError::InvalidFormat(_))));
+}
+
+#[test]
+fn test_checked_arithmetic() {
+    let usd = Money::from_major(1, Currency::USD).unwrap();
+    let eur = Money::from_major(1, Currency::EUR).unwrap();
+    assert!(matches!(usd.checked_add(eur), Err(MoneyError::CurrencyMismatch { .. })));
+    assert_eq!(usd.partial_cmp(&eur), None);
+
+    let max = Money::from_minor(i64::MAX, Currency::USD);
+    assert_eq!(max.checked_add(usd), Err(MoneyError::Overflow));
+    assert_eq!(usd.checked_sub(usd.checked_mul(3).unwrap()).unwrap().minor_units(), -200);
+}
+
+#[test]
+fn test_rounding_modes() {
+    let half = |mode| Money::parse_rounded("2.345", Currency::USD, mode).unwrap().minor_units();
+    assert_eq!(half(RoundingMode::HalfEven), 234);
+    assert_eq!(half(RoundingMode::HalfUp), 235);
+    assert_eq!(half(RoundingMode::HalfDown), 234);
+    assert_eq!(half(RoundingMode::Down), 234);
+    assert_eq!(half(RoundingMode::Up), 235);
+
+    let negative = |mode| Money::parse_rounded("-2.341", Currency::USD, mode).unwrap().minor_units();
+    assert_eq!(negative(RoundingMode::Floor), -235);
+    assert_eq!(negative(RoundingMode::Ceiling), -234);
+    assert_eq!(negative(RoundingMode::HalfEven), -234);
+
+    let third = Money::from_major(100, Currency::USD).unwrap().mul_ratio(1, 3, RoundingMode::HalfEven).unwrap();
+    assert_eq!(third.minor_units(), 3333);
+}
+
+#[test]
+fn test_serde_round_trip_and_legacy() {
+    let amount = Money::parse("19.99", Currency::GBP).unwrap();
+    let json = serde_json::to_string(&amount).unwrap();
+    assert_eq!(serde_json::from_str::<Money>(&json).unwrap(), amount);
+
+    // Balances saved as plain floats load as USD.
+    let legacy: Money = serde_json::from_str("123.45").unwrap();
+    assert_eq!(legacy, Money::from_minor(12345, Currency::USD));
+}
+
+#[test]
+fn test_out_of_range_amounts_are_errors() {
+    assert_eq!(Money::from_major(i64::MAX, Currency::USD), Err(MoneyError::Overflow));
+    assert_eq!(Money::from_major(i64::MAX, Currency::JPY).unwrap().minor_units(), i64::MAX);
+    assert_eq!(Money::from_minor(i64::MIN, Currency::USD).checked_abs(), Err(MoneyError::Overflow));
+    assert_eq!(Money::from_minor(-5, Currency::USD).checked_abs(), Ok(Money::from_minor(5, Currency::USD)));
+
+    // Far more decimal places than a divisor can hold still round.
+    let tiny = format!("0.{}1", "0".repeat(60));
+    let rounded = |input: &str, mode| Money::parse_rounded(input, Currency::USD, mode).unwrap().minor_units();
+    assert_eq!(rounded(&tiny, RoundingMode::HalfEven), 0);
+    assert_eq!(rounded(&tiny, RoundingMode::Up), 1);
+    assert_eq!(rounded(&format!("-{}", tiny), RoundingMode::Floor), -1);
+    assert_eq!(Money::from_f64(1e-300, Currency::USD, RoundingMode::HalfEven), Ok(Money::zero(Currency::USD)));
+    assert_eq!(serde_json::from_str::<Money>("1e-300").unwrap(), Money::zero(Currency::USD));
+
+    // Trailing zeros and cut-off places never overflow.
+    let one = format!("1.{}", "0".repeat(40));
+    assert_eq!(Money::parse_rounded(&one, Currency::USD, RoundingMode::HalfEven), Ok(Money::from_minor(100, Currency::USD)));
+    assert_eq!(Money::parse(&one, Currency::USD), Ok(Money::from_minor(100, Currency::USD)));
+    let long = format!("2.345{}", "9".repeat(40));
+    assert_eq!(Money::parse_rounded(&long, Currency::USD, RoundingMode::Down).unwrap().minor_units(), 234);
+    assert_eq!(Money::parse_rounded(&long, Currency::USD, RoundingMode::HalfDown).unwrap().minor_units(), 235);
+}
@@ -0,0 +1,216 @@
+mod common;
+
+use std::fs;
+use banksystem_rs::bank::{AccountId, AccountProduct, AccountStatus, Bank, BankError, Currency, MemoryStorage, Money, TransactionType};
+use banksystem_rs::bank::journal::{Event, EventJournal, OverdraftCover};
+use banksystem_rs::bank::ledger::LedgerAccount;
+use common::usd;
+
+/// A customer with a checking account holding `checking` and a savings
+/// account holding `savings`.
+fn checking_and_savings(bank: &mut Bank, checking: i64, savings: i64) -> (AccountId, AccountId) {
+    let customer_id = common::customer(bank, "Overdrawn");
+    let checking_id = common::account(bank, customer_id, AccountProduct::Checking, checking);
+    let savings_id = common::account(bank, customer_id, AccountProduct::Savings, savings);
+    (checking_id, savings_id)
+}
+
+#[test]
+fn test_overdraft_limit() {
+    let mut bank = Bank::new();
+    let (checking, savings) = checking_and_savings(&mut bank, 50, 500);
+
+    // No arranged overdraft, no negative balance.
+    assert!(matches!(bank.withdraw(checking, usd(60)), Err(BankError::InsufficientFunds { .. })));
+
+    bank.set_overdraft_limit(checking, usd(100)).unwrap();
+    assert_eq!(bank.get_account(checking).unwrap().available_balance().unwrap(), usd(150));
+    bank.withdraw(checking, usd(120)).unwrap();
+    assert_eq!(bank.get_account(checking).unwrap().balance, usd(-70));
+    assert_eq!(
+        bank.withdraw(checking, usd(40)),
+        Err(BankError::InsufficientFunds { account_id: checking, requested: usd(40), available: usd(30) })
+    );
+    assert!(bank.trial_balance().is_balanced());
+
+    // Removing the limit leaves the debt but stops further borrowing.
+    bank.set_overdraft_limit(checking, usd(0)).unwrap();
+    assert_eq!(bank.get_account(checking).unwrap().overdraft_limit, None);
+    assert!(bank.withdraw(checking, usd(1)).is_err());
+
+    assert!(matches!(
+        bank.set_overdraft_limit(savings, usd(100)),
+        Err(BankError::InvalidField { field: "overdraft limit", .. })
+    ));
+    assert!(matches!(
+        bank.set_overdraft_limit(checking, usd(-5)),
+        Err(BankError::InvalidField { field: "overdraft limit", .. })
+    ));
+    assert!(bank.set_overdraft_limit(checking, Money::from_major(5, Currency::EUR).unwrap()).is_err());
+}
+
+#[test]
+fn test_overdraft_protection_pulls_from_savings() {
+    let mut bank = Bank::new();
+    let (checking, savings) = checking_and_savings(&mut bank, 50, 500);
+    bank.set_overdraft_protection(checking, Some(savings)).unwrap();
+
+    bank.withdraw(checking, usd(120)).unwrap();
+    assert_eq!(bank.get_account(checking).unwrap().balance, usd(0));
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(430));
+    let history = bank.get_account(checking).unwrap().get_history();
+    assert_eq!(history[1].tx_type, TransactionType::TransferIn { from_account: savings });
+    assert_eq!(history[1].amount, usd(70));
+    assert_eq!(history[1].memo.as_deref(), Some("Overdraft protection"));
+
+    // Transfers out are covered too.
+    let other = bank.create_account(bank.get_account(checking).unwrap().customer_id, AccountProduct::Checking).unwrap();
+    bank.transfer(checking, other, usd(30), None).unwrap();
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(400));
+
+    // Nothing is pulled for a withdrawal that would fail anyway.
+    assert!(bank.withdraw(checking, usd(1_000)).is_err());
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(400));
+
+    bank.set_overdraft_protection(checking, None).unwrap();
+    assert!(bank.withdraw(checking, usd(10)).is_err());
+    assert!(bank.trial_balance().is_balanced());
+}
+
+#[test]
+fn test_overdraft_protection_links_are_checked() {
+    let mut bank = Bank::new();
+    let (checking, savings) = checking_and_savings(&mut bank, 1, 1);
+    let (stranger_checking, stranger_savings) = checking_and_savings(&mut bank, 1, 1);
+
+    assert!(matches!(
+        bank.set_overdraft_protection(checking, Some(stranger_savings)),
+        Err(BankError::InvalidField { field: "overdraft protection", .. })
+    ));
+    assert!(matches!(
+        bank.set_overdraft_protection(checking, Some(stranger_checking)),
+        Err(BankError::InvalidField { field: "overdraft protection", .. })
+    ));
+    assert_eq!(bank.set_overdraft_protection(savings, Some(savings)), Err(BankError::SameAccount(savings)));
+    assert_eq!(
+        bank.set_overdraft_protection(checking, Some(AccountId(99))),
+        Err(BankError::AccountNotFound(AccountId(99)))
+    );
+    assert_eq!(bank.get_account(checking).unwrap().overdraft_protection, None);
+}
+
+#[test]
+fn test_protection_moves_nothing_when_the_recipient_refuses() {
+    let mut bank = Bank::new();
+    let (checking, savings) = checking_and_savings(&mut bank, 50, 500);
+    bank.set_overdraft_protection(checking, Some(savings)).unwrap();
+    let customer_id = bank.get_account(checking).unwrap().customer_id;
+    let closed = common::account(&mut bank, customer_id, AccountProduct::Checking, 0);
+    bank.close_account(closed).unwrap();
+
+    assert!(matches!(bank.transfer(checking, closed, usd(80), None), Err(BankError::AccountStatus { .. })));
+    assert_eq!(bank.get_account(checking).unwrap().balance, usd(50));
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(500));
+    assert_eq!(bank.get_account(savings).unwrap().get_history().len(), 1);
+}
+
+#[test]
+fn test_overdraft_fee() {
+    let mut bank = Bank::new();
+    let (checking, savings) = checking_and_savings(&mut bank, 50, 30);
+    bank.set_overdraft_limit(checking, usd(100)).unwrap();
+    bank.set_overdraft_protection(checking, Some(savings)).unwrap();
+    bank.set_overdraft_fee(AccountProduct::Checking, usd(25)).unwrap();
+
+    // Savings covers 30 of the 70 shortfall; the overdraft covers the rest.
+    bank.withdraw(checking, usd(120)).unwrap();
+    let account = bank.get_account(checking).unwrap();
+    assert_eq!(account.balance, usd(-65));
+    let fee = account.get_history().last().unwrap();
+    assert_eq!(fee.tx_type, TransactionType::OverdraftFee);
+    assert_eq!(fee.amount, usd(25));
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(0));
+    assert_eq!(bank.ledger().balance(LedgerAccount::FeeIncome, Currency::USD), usd(25));
+
+    // A fee may take the balance past the limit.
+    bank.withdraw(checking, usd(35)).unwrap();
+    assert_eq!(bank.get_account(checking).unwrap().balance, usd(-125));
+    assert!(bank.trial_balance().is_balanced());
+
+    // Debits that stay in credit cost nothing.
+    bank.deposit(checking, usd(200)).unwrap();
+    bank.withdraw(checking, usd(10)).unwrap();
+    assert_eq!(bank.get_account(checking).unwrap().balance, usd(65));
+    assert_eq!(bank.ledger().balance(LedgerAccount::FeeIncome, Currency::USD), usd(50));
+
+    assert!(matches!(
+        bank.set_overdraft_fee(AccountProduct::Savings, usd(25)),
+        Err(BankError::InvalidField { field: "overdraft fee", .. })
+    ));
+    assert_eq!(bank.set_overdraft_fee(AccountProduct::Checking, usd(0)), Err(BankError::InvalidAmount(usd(0))));
+}
+
+#[test]
+fn test_overdrafts_replay_from_journal() {
+    let (mut replayed, (checking, savings)) = common::replayed(|bank| {
+        let (checking, savings) = checking_and_savings(bank, 50, 30);
+        bank.set_overdraft_limit(checking, usd(100)).unwrap();
+        bank.set_overdraft_protection(checking, Some(savings)).unwrap();
+        bank.set_overdraft_fee(AccountProduct::Checking, usd(25)).unwrap();
+        bank.withdraw(checking, usd(120)).unwrap();
+        (checking, savings)
+    });
+
+    // Savings covered 30 of the 70 short, the overdraft the other 40, and
+    // the fee came on top.
+    let account = replayed.get_account(checking).unwrap();
+    assert_eq!(account.balance, usd(-65));
+    assert_eq!(account.overdraft_limit, Some(usd(100)));
+    assert_eq!(account.overdraft_protection, Some(savings));
+    assert_eq!(account.get_history().last().unwrap().tx_type, TransactionType::OverdraftFee);
+    assert_eq!(replayed.get_account(savings).unwrap().balance, usd(0));
+
+    // The fee is still set, so the next overdrawn debit is charged too.
+    assert_eq!(replayed.overdraft_fee(AccountProduct::Checking), Some(usd(25)));
+    replayed.withdraw(checking, usd(5)).unwrap();
+    assert_eq!(replayed.get_account(checking).unwrap().balance, usd(-95));
+}
+
+#[test]
+fn test_cover_debit_and_fee_are_one_event() {
+    let dir = common::temp_dir("overdraft-one-event");
+    let journal = dir.join("events.jsonl");
+    let mut bank = Bank::with_journal(MemoryStorage::new(), &journal).unwrap();
+    let (checking, savings) = checking_and_savings(&mut bank, 50, 30);
+    bank.set_overdraft_limit(checking, usd(100)).unwrap();
+    bank.set_overdraft_protection(checking, Some(savings)).unwrap();
+    bank.set_overdraft_fee(AccountProduct::Checking, usd(25)).unwrap();
+    let customer_id = bank.get_account(checking).unwrap().customer_id;
+    let closed = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.close_account(closed).unwrap();
+    let events = EventJournal::read(&journal).unwrap().len();
+
+    // A transfer the recipient refuses moves nothing out of savings.
+    assert_eq!(
+        bank.transfer(checking, closed, usd(120), None),
+        Err(BankError::AccountStatus { account_id: closed, status: AccountStatus::Closed })
+    );
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(30));
+    assert_eq!(EventJournal::read(&journal).unwrap().len(), events);
+
+    bank.withdraw(checking, usd(120)).unwrap();
+    let records = EventJournal::read(&journal).unwrap();
+    assert_eq!(records.len(), events + 1);
+    assert_eq!(records.last().unwrap().event, Event::Withdrawal {
+        account_id: checking,
+        amount: usd(120),
+        memo: None,
+        reference: None,
+        cover: Some(OverdraftCover { savings_id: savings, amount: usd(30) }),
+        overdraft_fee: Some(usd(25)),
+    });
+    assert_eq!(bank.get_account(checking).unwrap().balance, usd(-65));
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(0));
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,300 @@
+mod common;
+
+use banksystem_rs::bank::{
+    AccountId, AccountParty, AccountProduct, Bank, BankError, CustomerId, HoldKind, PartyRole, SigningRule,
+    TransactionDetails,
+};
+use banksystem_rs::bank::certificate::{CertificateTerms, MaturityAction};
+use banksystem_rs::bank::interest::Rate;
+use banksystem_rs::bank::loan::{LoanTerms, Recalculation, RepaymentFrequency};
+use banksystem_rs::bank::schedule::{Outcome, Recurrence, StandingOrder};
+use common::{at, date, usd};
+
+/// A checking account holding 1,000 opened on 1 July 2024 by `primary`, with
+/// `joint` as joint holder and `child` as beneficiary.
+fn household(bank: &mut Bank) -> (AccountId, CustomerId, CustomerId, CustomerId) {
+    bank.set_clock(at(2024, 7, 1));
+    let primary = common::customer(bank, "First Holder");
+    let joint = common::customer(bank, "Second Holder");
+    let child = common::customer(bank, "Young Heir");
+    let account_id = common::account(bank, primary, AccountProduct::Checking, 1_000);
+    bank.add_account_party(account_id, joint, PartyRole::JointHolder).unwrap();
+    bank.add_account_party(account_id, child, PartyRole::Beneficiary).unwrap();
+    (account_id, primary, joint, child)
+}
+
+fn signed(signers: &[CustomerId]) -> TransactionDetails {
+    signers.iter().fold(TransactionDetails::default(), |details, id| details.signed_by(*id))
+}
+
+/// 1,200 over a year, interest free.
+fn small_loan() -> LoanTerms {
+    LoanTerms::new(usd(1_200), Rate::ZERO, 12, RepaymentFrequency::Monthly)
+}
+
+#[test]
+fn test_parties_and_customer_accounts() {
+    let mut bank = Bank::new();
+    let (shared, primary, joint, child) = household(&mut bank);
+    let own = bank.create_account(joint, AccountProduct::Savings).unwrap();
+
+    let account = bank.get_account(shared).unwrap();
+    assert_eq!(account.customer_id, primary);
+    assert_eq!(account.all_parties(), vec![
+        AccountParty { customer_id: primary, role: PartyRole::PrimaryHolder },
+        AccountParty { customer_id: joint, role: PartyRole::JointHolder },
+        AccountParty { customer_id: child, role: PartyRole::Beneficiary },
+    ]);
+    assert_eq!(account.roles_of(joint), vec![PartyRole::JointHolder]);
+    assert!(account.roles_of(CustomerId(99)).is_empty());
+    assert_eq!(account.signing_rule, SigningRule::AnyOne);
+
+    let ids = |bank: &Bank, customer_id| bank.list_customer_accounts(customer_id).iter().map(|a| a.id).collect::<Vec<_>>();
+    assert_eq!(ids(&bank, primary), vec![shared]);
+    assert_eq!(ids(&bank, joint), vec![shared, own]);
+    assert_eq!(ids(&bank, child), vec![shared]);
+
+    // A customer keeps the account while they have any role on it.
+    bank.add_account_party(shared, child, PartyRole::AuthorizedSigner).unwrap();
+    bank.remove_account_party(shared, child, PartyRole::Beneficiary).unwrap();
+    assert_eq!(ids(&bank, child), vec![shared]);
+    bank.remove_account_party(shared, child, PartyRole::AuthorizedSigner).unwrap();
+    assert!(ids(&bank, child).is_empty());
+    assert_eq!(PartyRole::PowerOfAttorney.to_string(), "Power of attorney");
+}
+
+#[test]
+fn test_any_one_may_sign() {
+    let mut bank = Bank::new();
+    let (shared, primary, joint, child) = household(&mut bank);
+    let stranger = common::customer(&mut bank, "Not Related");
+    let other = bank.create_account(stranger, AccountProduct::Checking).unwrap();
+
+    bank.withdraw_with_details(shared, usd(100), signed(&[joint])).unwrap();
+    bank.transfer_with_details(shared, other, usd(50), signed(&[primary])).unwrap();
+    bank.withdraw(shared, usd(10)).unwrap();
+    assert_eq!(bank.get_account(shared).unwrap().balance, usd(840));
+
+    assert_eq!(
+        bank.withdraw_with_details(shared, usd(1), signed(&[child])),
+        Err(BankError::NotAuthorized { account_id: shared, customer_id: child })
+    );
+    assert_eq!(
+        bank.transfer_with_details(shared, other, usd(1), signed(&[joint, stranger])),
+        Err(BankError::NotAuthorized { account_id: shared, customer_id: stranger })
+    );
+    bank.add_account_party(shared, stranger, PartyRole::PowerOfAttorney).unwrap();
+    bank.withdraw_with_details(shared, usd(40), signed(&[stranger])).unwrap();
+    assert_eq!(bank.get_account(shared).unwrap().balance, usd(800));
+}
+
+#[test]
+fn test_all_must_sign() {
+    let mut bank = Bank::new();
+    let (shared, primary, joint, child) = household(&mut bank);
+    let signer = common::customer(&mut bank, "Office Manager");
+    bank.add_account_party(shared, signer, PartyRole::AuthorizedSigner).unwrap();
+    bank.set_signing_rule(shared, SigningRule::AllMustSign).unwrap();
+
+    let missing = |missing: Vec<CustomerId>| Err(BankError::SignaturesMissing { account_id: shared, missing });
+    assert_eq!(bank.withdraw(shared, usd(10)), missing(vec![primary, joint]));
+    assert_eq!(bank.withdraw_with_details(shared, usd(10), signed(&[primary])), missing(vec![joint]));
+    assert_eq!(bank.withdraw_with_details(shared, usd(10), signed(&[signer])), missing(vec![primary, joint]));
+    assert_eq!(bank.withdraw_with_details(shared, usd(10), signed(&[primary, joint, child])),
+        Err(BankError::NotAuthorized { account_id: shared, customer_id: child }));
+    assert_eq!(bank.get_account(shared).unwrap().balance, usd(1_000));
+    assert_eq!(
+        BankError::SignaturesMissing { account_id: shared, missing: vec![primary, joint] }.to_string(),
+        format!("account {} also needs the signatures of customers {}, {}", shared, primary, joint)
+    );
+
+    // The account on its own is held to the same rule.
+    assert_eq!(bank.get_account(shared).unwrap().check_signatures(&[]), missing(vec![primary, joint]));
+
+    bank.withdraw_with_details(shared, usd(10), signed(&[joint, primary])).unwrap();
+    bank.withdraw_with_details(shared, usd(10), signed(&[primary, joint, signer])).unwrap();
+    // Money coming in needs no signatures.
+    bank.deposit(shared, usd(5)).unwrap();
+    assert_eq!(bank.get_account(shared).unwrap().balance, usd(985));
+
+    bank.set_signing_rule(shared, SigningRule::AnyOne).unwrap();
+    bank.withdraw(shared, usd(85)).unwrap();
+}
+
+#[test]
+fn test_every_debit_needs_all_signatures() {
+    let mut bank = Bank::new();
+    let (shared, primary, joint, _) = household(&mut bank);
+    bank.set_signing_rule(shared, SigningRule::AllMustSign).unwrap();
+    let both = [primary, joint];
+    let missing = |missing: Vec<CustomerId>| BankError::SignaturesMissing { account_id: shared, missing };
+
+    // Card authorizations are the customer's; the bank's own holds need no
+    // signatures.
+    assert_eq!(bank.place_hold(shared, HoldKind::CardAuthorization, usd(10), None).err(), Some(missing(vec![primary, joint])));
+    let hold = bank.place_hold_signed(shared, HoldKind::CardAuthorization, usd(10), None, &both).unwrap();
+    assert_eq!(bank.capture_hold(shared, hold, usd(10)), Err(missing(vec![primary, joint])));
+    bank.capture_hold_signed(shared, hold, usd(10), &both).unwrap();
+    bank.place_hold(shared, HoldKind::Legal, usd(5), None).unwrap();
+
+    // Funding a certificate, and drawing on it early.
+    let terms = CertificateTerms::new(Rate::from_bps(500), 12);
+    assert_eq!(bank.open_certificate(primary, shared, usd(100), terms).err(), Some(missing(vec![primary, joint])));
+    let cd = bank.open_certificate_signed(joint, shared, usd(100), terms, &both).unwrap();
+    bank.add_account_party(cd, primary, PartyRole::JointHolder).unwrap();
+    bank.set_signing_rule(cd, SigningRule::AllMustSign).unwrap();
+    assert_eq!(
+        bank.withdraw_early_signed(cd, usd(10), &[joint]).err(),
+        Some(BankError::SignaturesMissing { account_id: cd, missing: vec![primary] })
+    );
+    bank.withdraw_early_signed(cd, usd(10), &both).unwrap();
+
+    // Repaying a loan from the account.
+    let loan = bank.open_loan(joint, shared, small_loan()).unwrap();
+    assert_eq!(bank.repay_loan(loan).err(), Some(missing(vec![primary, joint])));
+    bank.repay_loan_signed(loan, &both).unwrap();
+    assert_eq!(bank.prepay_loan(loan, usd(100), Recalculation::ShortenTerm), Err(missing(vec![primary, joint])));
+    bank.prepay_loan_signed(loan, usd(100), Recalculation::ShortenTerm, &both).unwrap();
+
+    assert_eq!(bank.get_account(shared).unwrap().balance, usd(1_890));
+    assert_eq!(bank.outstanding_principal(loan).unwrap(), usd(1_000));
+}
+
+#[test]
+fn test_protection_pulls_need_the_savings_signatures() {
+    let mut bank = Bank::new();
+    let (shared, primary, joint, _) = household(&mut bank);
+    let savings = common::account(&mut bank, primary, AccountProduct::Savings, 500);
+    bank.add_account_party(savings, joint, PartyRole::JointHolder).unwrap();
+    bank.set_signing_rule(savings, SigningRule::AllMustSign).unwrap();
+    bank.set_overdraft_protection(shared, Some(savings)).unwrap();
+
+    // One holder may sign for the checking account but not for the savings.
+    assert!(matches!(
+        bank.withdraw_with_details(shared, usd(1_200), signed(&[primary])),
+        Err(BankError::InsufficientFunds { .. })
+    ));
+    assert!(matches!(bank.withdraw(shared, usd(1_200)), Err(BankError::InsufficientFunds { .. })));
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(500));
+
+    bank.withdraw_with_details(shared, usd(1_200), signed(&[primary, joint])).unwrap();
+    assert_eq!(bank.get_account(shared).unwrap().balance, usd(0));
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(300));
+}
+
+#[test]
+fn test_protection_from_a_joint_holders_savings() {
+    let mut bank = Bank::new();
+    let (shared, primary, joint, child) = household(&mut bank);
+
+    // The joint holder's own savings can cover the shared account; a
+    // beneficiary's can't.
+    let savings = common::account(&mut bank, joint, AccountProduct::Savings, 500);
+    bank.set_overdraft_protection(shared, Some(savings)).unwrap();
+    let allowance = common::account(&mut bank, child, AccountProduct::Savings, 500);
+    assert!(matches!(
+        bank.set_overdraft_protection(shared, Some(allowance)),
+        Err(BankError::InvalidField { field: "overdraft protection", .. })
+    ));
+    assert_eq!(bank.get_account(shared).unwrap().overdraft_protection, Some(savings));
+
+    // Only the joint holder can draw on it, whether the primary holder
+    // signs or acts without naming signers.
+    assert!(matches!(bank.withdraw(shared, usd(1_200)), Err(BankError::InsufficientFunds { .. })));
+    assert!(matches!(
+        bank.withdraw_with_details(shared, usd(1_200), signed(&[primary])),
+        Err(BankError::InsufficientFunds { .. })
+    ));
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(500));
+    bank.withdraw_with_details(shared, usd(1_200), signed(&[joint])).unwrap();
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(300));
+}
+
+#[test]
+fn test_standing_orders_keep_their_signers() {
+    let (mut replayed, (shared, id, primary, joint)) = common::replayed(|bank| {
+        let (shared, primary, joint, _) = household(bank);
+        let landlord = common::customer(bank, "Landlord");
+        let rent = bank.create_account(landlord, AccountProduct::Checking).unwrap();
+        bank.set_signing_rule(shared, SigningRule::AllMustSign).unwrap();
+
+        let order = StandingOrder::new(shared, rent, usd(250), Recurrence::MonthlyOnDay { day: 2 }, date(2024, 7, 2));
+        assert_eq!(
+            bank.schedule_transfer(order.clone().signed_by(primary)),
+            Err(BankError::SignaturesMissing { account_id: shared, missing: vec![joint] })
+        );
+        let id = bank.schedule_transfer(order.signed_by(primary).signed_by(joint)).unwrap();
+        (shared, id, primary, joint)
+    });
+
+    assert_eq!(replayed.scheduled_transfer(id).unwrap().order.signers, vec![primary, joint]);
+    let executions = replayed.run_due_transfers(date(2024, 8, 2)).unwrap();
+    assert!(executions.iter().all(|execution| execution.outcome == Outcome::Executed));
+    assert_eq!(replayed.get_account(shared).unwrap().balance, usd(500));
+
+    // A signer who leaves the account no longer signs for it.
+    replayed.remove_account_party(shared, joint, PartyRole::JointHolder).unwrap();
+    let executions = replayed.run_due_transfers(date(2024, 9, 2)).unwrap();
+    assert!(matches!(executions[0].outcome, Outcome::Skipped { .. }));
+}
+
+#[test]
+fn test_joint_holders_borrow_and_save_through_the_account() {
+    let (replayed, (shared, loan, cd, joint)) = common::replayed(|bank| {
+        let (shared, _, joint, child) = household(bank);
+        let terms = CertificateTerms::new(Rate::from_bps(500), 12).with_maturity_action(MaturityAction::PayOut { to: shared });
+
+        // A beneficiary doesn't own the account.
+        assert!(matches!(bank.open_loan(child, shared, small_loan()), Err(BankError::InvalidField { field: "linked account", .. })));
+        assert!(matches!(
+            bank.open_certificate(child, shared, usd(100), terms),
+            Err(BankError::InvalidField { field: "funding account", .. })
+        ));
+        let loan = bank.open_loan(joint, shared, small_loan()).unwrap();
+        let cd = bank.open_certificate(joint, shared, usd(100), terms).unwrap();
+        (shared, loan, cd, joint)
+    });
+
+    assert_eq!(replayed.get_account(loan).unwrap().customer_id, joint);
+    assert_eq!(replayed.get_account(cd).unwrap().customer_id, joint);
+    assert_eq!(replayed.loan(loan).unwrap().linked_account, shared);
+    assert_eq!(replayed.get_account(shared).unwrap().balance, usd(2_100));
+}
+
+#[test]
+fn test_party_validation() {
+    let mut bank = Bank::new();
+    let (shared, primary, joint, _) = household(&mut bank);
+    let invalid = |result: Result<(), BankError>| matches!(result, Err(BankError::InvalidField { field: "account party", .. }));
+
+    assert!(invalid(bank.add_account_party(shared, joint, PartyRole::PrimaryHolder)));
+    assert!(invalid(bank.add_account_party(shared, joint, PartyRole::JointHolder)));
+    assert!(invalid(bank.add_account_party(shared, primary, PartyRole::JointHolder)));
+    assert!(invalid(bank.remove_account_party(shared, primary, PartyRole::PrimaryHolder)));
+    assert!(invalid(bank.remove_account_party(shared, joint, PartyRole::Beneficiary)));
+    assert_eq!(bank.add_account_party(shared, CustomerId(99), PartyRole::Beneficiary), Err(BankError::CustomerNotFound(CustomerId(99))));
+    assert_eq!(bank.add_account_party(AccountId(99), joint, PartyRole::Beneficiary), Err(BankError::AccountNotFound(AccountId(99))));
+
+    // The primary holder may take another role that isn't a holder's.
+    bank.add_account_party(shared, primary, PartyRole::Beneficiary).unwrap();
+    assert_eq!(bank.get_account(shared).unwrap().roles_of(primary), vec![PartyRole::PrimaryHolder, PartyRole::Beneficiary]);
+}
+
+#[test]
+fn test_parties_survive_save_and_replay() {
+    let (replayed, (shared, primary, joint, child)) = common::replayed(|bank| {
+        let (shared, primary, joint, child) = household(bank);
+        bank.set_signing_rule(shared, SigningRule::AllMustSign).unwrap();
+        bank.withdraw_with_details(shared, usd(200), signed(&[primary, joint])).unwrap();
+        bank.remove_account_party(shared, child, PartyRole::Beneficiary).unwrap();
+        (shared, primary, joint, child)
+    });
+
+    // The customer index is rebuilt from the parties, not just the owner.
+    assert_eq!(replayed.list_customer_accounts(primary).len(), 1);
+    assert_eq!(replayed.list_customer_accounts(joint).len(), 1);
+    assert!(replayed.list_customer_accounts(child).is_empty());
+    let account = replayed.get_account(shared).unwrap();
+    assert_eq!(account.signing_rule, SigningRule::AllMustSign);
+    assert_eq!(account.balance, usd(800));
+}
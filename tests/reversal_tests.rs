@@ -0,0 +1,214 @@
+mod common;
+
+use banksystem_rs::bank::{AccountId, AccountProduct, Bank, BankError, Currency, TransactionId, TransactionType};
+use banksystem_rs::bank::fees::{FeeReason, FeeSchedule};
+use banksystem_rs::bank::ledger::LedgerAccount;
+use common::{at, date, usd};
+
+/// A customer with a checking account holding 1,000 and an empty savings
+/// account, on 1 April 2024.
+fn teller_day(bank: &mut Bank) -> (AccountId, AccountId) {
+    bank.set_clock(at(2024, 4, 1));
+    let customer_id = common::customer(bank, "Mistaken Identity");
+    let checking_id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    let savings_id = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.deposit(checking_id, usd(1_000)).unwrap();
+    (checking_id, savings_id)
+}
+
+fn last_id(bank: &Bank, account_id: AccountId) -> TransactionId {
+    bank.get_account(account_id).unwrap().get_history().last().unwrap().id
+}
+
+#[test]
+fn test_reverse_deposit() {
+    let mut bank = Bank::new();
+    let (checking, _) = teller_day(&mut bank);
+    bank.deposit(checking, usd(250)).unwrap();
+    let deposit_id = last_id(&bank, checking);
+
+    bank.reverse_transaction(deposit_id, "Keyed 250 instead of 25".to_string()).unwrap();
+    let account = bank.get_account(checking).unwrap();
+    assert_eq!(account.balance, usd(1_000));
+    let [.., deposit, reversal] = account.get_history() else { panic!("expected a deposit and its reversal") };
+    assert_eq!(deposit.reversed_by, Some(reversal.id));
+    assert_eq!(reversal.tx_type, TransactionType::Reversal { original: deposit_id, credit: false });
+    assert_eq!(reversal.amount, usd(250));
+    assert_eq!(reversal.memo.as_deref(), Some("Keyed 250 instead of 25"));
+    assert!(reversal.reversed_by.is_none());
+    assert_eq!(bank.ledger().balance(LedgerAccount::Cash, Currency::USD), usd(1_000));
+    assert!(bank.trial_balance().is_balanced());
+}
+
+#[test]
+fn test_reverse_withdrawal() {
+    let mut bank = Bank::new();
+    let (checking, _) = teller_day(&mut bank);
+    bank.withdraw(checking, usd(400)).unwrap();
+    let withdrawal_id = last_id(&bank, checking);
+
+    bank.reverse_transaction(withdrawal_id, "Wrong account".to_string()).unwrap();
+    let account = bank.get_account(checking).unwrap();
+    assert_eq!(account.balance, usd(1_000));
+    assert_eq!(
+        account.get_history().last().unwrap().tx_type,
+        TransactionType::Reversal { original: withdrawal_id, credit: true }
+    );
+    assert_eq!(bank.ledger().balance(LedgerAccount::Cash, Currency::USD), usd(1_000));
+}
+
+#[test]
+fn test_reverse_transfer_reverses_both_legs() {
+    let mut bank = Bank::new();
+    let (checking, savings) = teller_day(&mut bank);
+    bank.transfer(checking, savings, usd(300), None).unwrap();
+    let debit_id = last_id(&bank, checking);
+    let credit_id = last_id(&bank, savings);
+
+    // Each leg names the other.
+    assert_eq!(bank.get_account(checking).unwrap().get_history().last().unwrap().paired_with, Some(credit_id));
+    assert_eq!(bank.get_account(savings).unwrap().get_history().last().unwrap().paired_with, Some(debit_id));
+
+    // Either leg identifies the transfer.
+    bank.reverse_transaction(credit_id, "Meant for another customer".to_string()).unwrap();
+    assert_eq!(bank.get_account(checking).unwrap().balance, usd(1_000));
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(0));
+
+    let checking_history = bank.get_account(checking).unwrap().get_history();
+    let savings_history = bank.get_account(savings).unwrap().get_history();
+    let [.., debit, checking_reversal] = checking_history else { panic!("expected the debit leg and its reversal") };
+    let [.., credit, savings_reversal] = savings_history else { panic!("expected the credit leg and its reversal") };
+    assert_eq!(checking_reversal.tx_type, TransactionType::Reversal { original: debit_id, credit: true });
+    assert_eq!(savings_reversal.tx_type, TransactionType::Reversal { original: credit_id, credit: false });
+    assert_eq!(debit.reversed_by, Some(checking_reversal.id));
+    assert_eq!(credit.reversed_by, Some(savings_reversal.id));
+    assert!(bank.trial_balance().is_balanced());
+
+    let reversed = BankError::InvalidField { field: "transaction", reason: format!("transaction {} is already reversed", credit_id) };
+    assert_eq!(bank.reverse_transaction(debit_id, "Again".to_string()), Err(reversed.clone()));
+    assert_eq!(bank.reverse_transaction(credit_id, "Again".to_string()), Err(reversed.clone()));
+}
+
+/// Transaction `index` of `account_id` in saved bank data.
+fn saved_leg(json: &mut serde_json::Value, account_id: AccountId, index: usize) -> &mut serde_json::Value {
+    let account = json["accounts"].as_array_mut().unwrap().iter_mut().find(|account| account["id"] == account_id.0).unwrap();
+    &mut account["history"][index]
+}
+
+#[test]
+fn test_transfer_legs_are_found_through_their_link() {
+    let mut bank = Bank::new();
+    let (checking, savings) = teller_day(&mut bank);
+    bank.transfer(checking, savings, usd(300), None).unwrap();
+    bank.transfer(checking, savings, usd(40), None).unwrap();
+    let debit_id = last_id(&bank, checking);
+    let credit_id = last_id(&bank, savings);
+    let saved = serde_json::to_value(bank.storage()).unwrap();
+
+    // Legs whose IDs aren't next to each other are still paired up.
+    let mut json = saved.clone();
+    let savings_leg = saved_leg(&mut json, savings, 1);
+    assert_eq!(savings_leg["id"], credit_id.0);
+    savings_leg["id"] = 9_000.into();
+    saved_leg(&mut json, checking, 2)["paired_with"] = 9_000.into();
+    let mut renumbered: Bank = serde_json::from_value(json).unwrap();
+    renumbered.reverse_transaction(debit_id, "Sent twice".to_string()).unwrap();
+    assert_eq!(renumbered.get_account(savings).unwrap().balance, usd(300));
+    assert!(renumbered.get_account(savings).unwrap().get_history()[1].reversed_by.is_some());
+    assert!(renumbered.trial_balance().is_balanced());
+
+    // Older legs without a link are paired by their consecutive IDs.
+    let mut json = saved;
+    for (account_id, index) in [(checking, 2), (savings, 1)] {
+        saved_leg(&mut json, account_id, index).as_object_mut().unwrap().remove("paired_with");
+    }
+    let mut legacy: Bank = serde_json::from_value(json).unwrap();
+    legacy.reverse_transaction(credit_id, "Sent twice".to_string()).unwrap();
+    assert_eq!(legacy.get_account(checking).unwrap().balance, usd(700));
+    assert!(legacy.trial_balance().is_balanced());
+}
+
+#[test]
+fn test_refuses_double_and_invalid_reversals() {
+    let mut bank = Bank::new();
+    let (checking, savings) = teller_day(&mut bank);
+    let deposit_id = last_id(&bank, checking);
+    bank.reverse_transaction(deposit_id, "Counterfeit notes".to_string()).unwrap();
+    let reversal_id = last_id(&bank, checking);
+
+    let before = serde_json::to_string(&bank).unwrap();
+    assert_eq!(
+        bank.reverse_transaction(deposit_id, "Counterfeit notes".to_string()),
+        Err(BankError::InvalidField { field: "transaction", reason: format!("transaction {} is already reversed", deposit_id) })
+    );
+    assert!(matches!(bank.reverse_transaction(reversal_id, "Undo the undo".to_string()), Err(BankError::InvalidField { field: "transaction", .. })));
+    assert!(matches!(bank.reverse_transaction(TransactionId(999), "Missing".to_string()), Err(BankError::InvalidField { field: "transaction", .. })));
+    assert_eq!(serde_json::to_string(&bank).unwrap(), before);
+
+    bank.deposit(savings, usd(100)).unwrap();
+    let savings_deposit = last_id(&bank, savings);
+    assert!(matches!(bank.reverse_transaction(savings_deposit, "  ".to_string()), Err(BankError::InvalidField { field: "reversal reason", .. })));
+    bank.withdraw(savings, usd(60)).unwrap();
+    assert!(matches!(bank.reverse_transaction(savings_deposit, "Bounced".to_string()), Err(BankError::InsufficientFunds { .. })));
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(40));
+}
+
+#[test]
+fn test_fees_are_refunded_not_reversed() {
+    let mut bank = Bank::new();
+    bank.set_overdraft_fee(AccountProduct::Checking, usd(25)).unwrap();
+    let (checking, _) = teller_day(&mut bank);
+    bank.set_overdraft_limit(checking, usd(500)).unwrap();
+    bank.withdraw(checking, usd(1_100)).unwrap();
+    let fee_id = last_id(&bank, checking);
+
+    assert!(matches!(bank.reverse_transaction(fee_id, "Goodwill".to_string()), Err(BankError::InvalidField { field: "transaction", .. })));
+    bank.reverse_fee(checking, fee_id).unwrap();
+    let history = bank.get_account(checking).unwrap().get_history();
+    let fee = history.iter().find(|tx| tx.id == fee_id).unwrap();
+    assert_eq!(fee.reversed_by, Some(history.last().unwrap().id));
+}
+
+#[test]
+fn test_reversed_transfers_are_not_charged_or_counted() {
+    let mut bank = Bank::new();
+    bank.set_fee_schedule(AccountProduct::Savings, FeeSchedule::new().with_per_transfer(usd(1))).unwrap();
+    let (checking, savings) = teller_day(&mut bank);
+    bank.transfer(checking, savings, usd(600), None).unwrap();
+    for _ in 0..6 {
+        bank.transfer(savings, checking, usd(10), None).unwrap();
+    }
+    assert!(matches!(bank.transfer(savings, checking, usd(10), None), Err(BankError::WithdrawalLimitExceeded { .. })));
+
+    // Reversing a transfer gives its withdrawal back.
+    bank.reverse_transaction(last_id(&bank, savings), "Sent twice".to_string()).unwrap();
+    bank.transfer(savings, checking, usd(10), None).unwrap();
+    bank.reverse_transaction(last_id(&bank, savings), "Wrong amount".to_string()).unwrap();
+
+    bank.set_clock(at(2024, 5, 1));
+    bank.assess_fees(date(2024, 4, 30)).unwrap();
+    let fees: Vec<_> = bank.get_account(savings).unwrap().get_history().iter().filter(|tx| tx.tx_type.is_fee()).cloned().collect();
+    let [fee] = fees.as_slice() else { panic!("expected one transfer fee") };
+    assert_eq!(fee.tx_type, TransactionType::Fee { reason: FeeReason::Transfer });
+    assert_eq!(fee.amount, usd(5));
+}
+
+#[test]
+fn test_reversals_survive_save_and_replay() {
+    let (replayed, (checking, savings)) = common::replayed(|bank| {
+        let (checking, savings) = teller_day(bank);
+        bank.transfer(checking, savings, usd(300), Some("Savings".to_string())).unwrap();
+        bank.reverse_transaction(last_id(bank, checking), "Duplicate".to_string()).unwrap();
+        bank.withdraw(checking, usd(20)).unwrap();
+        bank.reverse_transaction(last_id(bank, checking), "Cash drawer error".to_string()).unwrap();
+        (checking, savings)
+    });
+
+    // Both legs of the transfer stay marked as reversed.
+    let history = replayed.get_account(checking).unwrap().get_history();
+    assert_eq!(history.iter().filter(|tx| tx.reversed_by.is_some()).count(), 2);
+    let savings = replayed.get_account(savings).unwrap();
+    assert!(savings.get_history()[0].reversed_by.is_some());
+    assert_eq!(savings.balance, usd(0));
+    assert_eq!(replayed.get_account(checking).unwrap().balance, usd(1_000));
+}
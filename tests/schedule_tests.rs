@@ -0,0 +1,169 @@
+mod common;
+
+use chrono::NaiveDate;
+use banksystem_rs::bank::{AccountId, AccountProduct, Bank, BankError, ScheduleId};
+use banksystem_rs::bank::schedule::{InsufficientFunds, Outcome, Recurrence, StandingOrder};
+use common::{at, date, usd};
+
+/// A customer's checking account holding `checking` and an empty savings
+/// account, on 1 January 2024.
+fn checking_and_savings(bank: &mut Bank, checking: i64) -> (AccountId, AccountId) {
+    bank.set_clock(at(2024, 1, 1));
+    let customer_id = common::customer(bank, "Standing Order");
+    let checking_id = common::account(bank, customer_id, AccountProduct::Checking, checking);
+    let savings_id = common::account(bank, customer_id, AccountProduct::Savings, 0);
+    (checking_id, savings_id)
+}
+
+fn outcomes(bank: &Bank, id: ScheduleId) -> Vec<(NaiveDate, Outcome)> {
+    bank.scheduled_transfer(id).unwrap().history.iter().map(|e| (e.due, e.outcome.clone())).collect()
+}
+
+#[test]
+fn test_recurrence_rules() {
+    let monthly_31 = Recurrence::MonthlyOnDay { day: 31 };
+    assert_eq!(monthly_31.first_on_or_after(date(2024, 1, 15)), Some(date(2024, 1, 31)));
+    assert_eq!(monthly_31.next_after(date(2024, 1, 31)), Some(date(2024, 2, 29)));
+    assert_eq!(monthly_31.next_after(date(2024, 2, 29)), Some(date(2024, 3, 31)));
+
+    let first = Recurrence::MonthlyOnDay { day: 1 };
+    assert_eq!(first.first_on_or_after(date(2024, 1, 15)), Some(date(2024, 2, 1)));
+    assert_eq!(first.first_on_or_after(date(2024, 1, 1)), Some(date(2024, 1, 1)));
+    assert_eq!(first.next_after(date(2024, 12, 1)), Some(date(2025, 1, 1)));
+
+    assert_eq!(Recurrence::EndOfMonth.first_on_or_after(date(2023, 2, 10)), Some(date(2023, 2, 28)));
+    assert_eq!(Recurrence::EndOfMonth.next_after(date(2024, 2, 29)), Some(date(2024, 3, 31)));
+    assert_eq!(Recurrence::Weekly { interval: 4 }.next_after(date(2024, 1, 1)), Some(date(2024, 1, 29)));
+    assert_eq!(Recurrence::Daily.next_after(date(2024, 12, 31)), Some(date(2025, 1, 1)));
+    assert_eq!(Recurrence::Once.next_after(date(2024, 1, 1)), None);
+}
+
+#[test]
+fn test_monthly_standing_order() {
+    let mut bank = Bank::new();
+    let (checking, savings) = checking_and_savings(&mut bank, 1_000);
+    let order = StandingOrder::new(checking, savings, usd(200), Recurrence::MonthlyOnDay { day: 1 }, date(2024, 1, 1))
+        .with_memo("Savings plan".to_string())
+        .until(date(2024, 3, 31));
+    let id = bank.schedule_transfer(order).unwrap();
+    assert_eq!(bank.scheduled_transfer(id).unwrap().next_due, Some(date(2024, 1, 1)));
+
+    assert_eq!(bank.run_due_transfers(date(2024, 1, 1)).unwrap().len(), 1);
+    assert!(bank.run_due_transfers(date(2024, 1, 1)).unwrap().is_empty());
+    assert_eq!(bank.get_account(savings).unwrap().get_history()[0].memo.as_deref(), Some("Savings plan"));
+
+    // A late run catches up on every missed date.
+    let executions = bank.run_due_transfers(date(2024, 3, 15)).unwrap();
+    assert_eq!(executions.iter().map(|e| e.due).collect::<Vec<_>>(), vec![date(2024, 2, 1), date(2024, 3, 1)]);
+    assert!(executions.iter().all(|e| e.ran_on == date(2024, 3, 15) && e.outcome == Outcome::Executed));
+
+    assert!(bank.run_due_transfers(date(2024, 6, 1)).unwrap().is_empty());
+    assert_eq!(bank.scheduled_transfer(id).unwrap().next_due, None);
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(600));
+    assert_eq!(outcomes(&bank, id).len(), 3);
+}
+
+#[test]
+fn test_future_dated_one_off() {
+    let mut bank = Bank::new();
+    let (checking, savings) = checking_and_savings(&mut bank, 100);
+    let id = bank.schedule_transfer(StandingOrder::new(checking, savings, usd(40), Recurrence::Once, date(2024, 2, 14))).unwrap();
+
+    assert!(bank.run_due_transfers(date(2024, 2, 13)).unwrap().is_empty());
+    assert_eq!(bank.run_due_transfers(date(2024, 2, 14)).unwrap()[0].outcome, Outcome::Executed);
+    assert_eq!(bank.get_account(checking).unwrap().balance, usd(60));
+    assert_eq!(bank.scheduled_transfer(id).unwrap().next_due, None);
+    assert!(bank.cancel_scheduled_transfer(id).is_err());
+}
+
+#[test]
+fn test_insufficient_funds_retry_and_skip() {
+    let mut bank = Bank::new();
+    let (checking, savings) = checking_and_savings(&mut bank, 0);
+    let retried = bank.schedule_transfer(
+        StandingOrder::new(checking, savings, usd(50), Recurrence::MonthlyOnDay { day: 1 }, date(2024, 1, 1))
+            .on_insufficient_funds(InsufficientFunds::Retry { attempts: 3 }),
+    ).unwrap();
+    let skipped = bank.schedule_transfer(
+        StandingOrder::new(checking, savings, usd(500), Recurrence::Weekly { interval: 4 }, date(2024, 1, 1)),
+    ).unwrap();
+
+    bank.run_due_transfers(date(2024, 1, 1)).unwrap();
+    bank.run_due_transfers(date(2024, 1, 1)).unwrap();
+    bank.run_due_transfers(date(2024, 1, 2)).unwrap();
+    bank.run_due_transfers(date(2024, 1, 3)).unwrap();
+    let history = outcomes(&bank, retried);
+    assert_eq!(history[0], (date(2024, 1, 1), Outcome::Retrying { attempt: 1 }));
+    assert_eq!(history[1], (date(2024, 1, 1), Outcome::Retrying { attempt: 2 }));
+    assert!(matches!(history[2].1, Outcome::Skipped { .. }));
+    assert_eq!(bank.scheduled_transfer(retried).unwrap().next_due, Some(date(2024, 2, 1)));
+
+    let history = outcomes(&bank, skipped);
+    assert_eq!(history.len(), 1);
+    assert!(matches!(&history[0].1, Outcome::Skipped { reason } if reason.starts_with("insufficient funds")));
+    assert_eq!(bank.scheduled_transfer(skipped).unwrap().next_due, Some(date(2024, 1, 29)));
+
+    // A retry that finds the money goes through.
+    bank.run_due_transfers(date(2024, 2, 1)).unwrap();
+    bank.deposit(checking, usd(50)).unwrap();
+    let executions = bank.run_due_transfers(date(2024, 2, 2)).unwrap();
+    assert_eq!(executions[0].outcome, Outcome::Executed);
+    assert_eq!(executions[0].due, date(2024, 2, 1));
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(50));
+}
+
+#[test]
+fn test_cancelling_and_validation() {
+    let mut bank = Bank::new();
+    let (checking, savings) = checking_and_savings(&mut bank, 100);
+    let id = bank.schedule_transfer(StandingOrder::new(checking, savings, usd(10), Recurrence::Daily, date(2024, 1, 1))).unwrap();
+
+    // Other failures skip the occurrence.
+    bank.freeze_account(checking).unwrap();
+    let executions = bank.run_due_transfers(date(2024, 1, 1)).unwrap();
+    assert_eq!(executions[0].outcome, Outcome::Skipped { reason: format!("account {} is frozen", checking) });
+
+    bank.cancel_scheduled_transfer(id).unwrap();
+    assert!(bank.scheduled_transfer(id).unwrap().cancelled);
+    assert!(bank.run_due_transfers(date(2024, 1, 5)).unwrap().is_empty());
+    assert!(matches!(
+        bank.cancel_scheduled_transfer(ScheduleId(9)),
+        Err(BankError::InvalidField { field: "scheduled transfer", .. })
+    ));
+
+    let start = date(2024, 1, 1);
+    assert_eq!(
+        bank.schedule_transfer(StandingOrder::new(checking, checking, usd(1), Recurrence::Daily, start)),
+        Err(BankError::SameAccount(checking))
+    );
+    assert_eq!(
+        bank.schedule_transfer(StandingOrder::new(checking, AccountId(99), usd(1), Recurrence::Daily, start)),
+        Err(BankError::AccountNotFound(AccountId(99)))
+    );
+    assert!(bank.schedule_transfer(StandingOrder::new(checking, savings, usd(1), Recurrence::Weekly { interval: 0 }, start)).is_err());
+    assert!(bank.schedule_transfer(StandingOrder::new(checking, savings, usd(1), Recurrence::MonthlyOnDay { day: 32 }, start)).is_err());
+    assert!(matches!(
+        bank.schedule_transfer(
+            StandingOrder::new(checking, savings, usd(1), Recurrence::MonthlyOnDay { day: 20 }, start).until(date(2024, 1, 10))
+        ),
+        Err(BankError::InvalidField { field: "end date", .. })
+    ));
+}
+
+#[test]
+fn test_schedules_survive_save_and_replay() {
+    let (mut replayed, (checking, savings, id)) = common::replayed(|bank| {
+        let (checking, savings) = checking_and_savings(bank, 100);
+        let id = bank.schedule_transfer(StandingOrder::new(checking, savings, usd(60), Recurrence::EndOfMonth, date(2024, 1, 1))).unwrap();
+        bank.run_due_transfers(date(2024, 2, 29)).unwrap();
+        (checking, savings, id)
+    });
+
+    // January went through and February could not; the order carries on.
+    let history = outcomes(&replayed, id);
+    assert_eq!(history[0], (date(2024, 1, 31), Outcome::Executed));
+    assert!(matches!(history[1], (due, Outcome::Skipped { .. }) if due == date(2024, 2, 29)));
+    assert_eq!(replayed.scheduled_transfer(id).unwrap().next_due, Some(date(2024, 3, 31)));
+    let next = replayed.schedule_transfer(StandingOrder::new(savings, checking, usd(1), Recurrence::Once, date(2024, 3, 1))).unwrap();
+    assert_eq!(next, ScheduleId(2));
+}
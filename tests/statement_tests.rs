@@ -0,0 +1,175 @@
+mod common;
+
+use banksystem_rs::bank::{AccountId, AccountProduct, Bank, BankError, Money};
+use banksystem_rs::bank::interest::{InterestPolicy, Rate};
+use banksystem_rs::bank::statement::AccountStatement;
+use common::{at, date, usd};
+
+/// A checking and a savings account with activity in January and March
+/// 2024, and none in February.
+fn active_customer(bank: &mut Bank) -> (AccountId, AccountId) {
+    bank.set_clock(at(2024, 1, 2));
+    let customer_id = common::customer(bank, "Statement Reader");
+    let checking_id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    let savings_id = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.deposit(checking_id, usd(1_000)).unwrap();
+    bank.set_clock(at(2024, 1, 15));
+    bank.withdraw(checking_id, usd(150)).unwrap();
+    bank.transfer(checking_id, savings_id, usd(200), Some("Rainy day".to_string())).unwrap();
+    bank.set_clock(at(2024, 3, 4));
+    bank.deposit(checking_id, usd(75)).unwrap();
+    (checking_id, savings_id)
+}
+
+#[test]
+fn test_statement_lines_and_totals() {
+    let mut bank = Bank::new();
+    let (checking, savings) = active_customer(&mut bank);
+    let statement = bank.monthly_statement(checking, 2024, 1).unwrap();
+
+    assert_eq!((statement.period_start, statement.period_end), (date(2024, 1, 1), date(2024, 1, 31)));
+    assert_eq!(statement.opening_balance, usd(0));
+    let running: Vec<(Money, Money)> = statement.lines.iter().map(|line| (line.amount, line.balance)).collect();
+    assert_eq!(running, vec![(usd(1_000), usd(1_000)), (usd(-150), usd(850)), (usd(-200), usd(650))]);
+    assert_eq!(statement.lines[2].description, format!("Transfer to account {}", savings));
+    assert_eq!(statement.lines[2].memo.as_deref(), Some("Rainy day"));
+    assert_eq!(statement.total_credits, usd(1_000));
+    assert_eq!(statement.total_debits, usd(350));
+    assert_eq!(statement.closing_balance, usd(650));
+
+    let savings_statement = bank.monthly_statement(savings, 2024, 1).unwrap();
+    assert_eq!(savings_statement.total_credits, usd(200));
+    assert_eq!(savings_statement.closing_balance, usd(200));
+}
+
+#[test]
+fn test_closing_balances_chain() {
+    let mut bank = Bank::new();
+    let (checking, _) = active_customer(&mut bank);
+    let months: Vec<AccountStatement> = (1..=4).map(|month| bank.monthly_statement(checking, 2024, month).unwrap()).collect();
+
+    for pair in months.windows(2) {
+        assert_eq!(pair[1].opening_balance, pair[0].closing_balance);
+        assert_eq!(pair[1].period_start, pair[0].period_end.succ_opt().unwrap());
+    }
+    assert!(months[1].lines.is_empty());
+    assert_eq!(months[1].opening_balance, usd(650));
+    assert_eq!(months[1].period_end, date(2024, 2, 29));
+    assert_eq!(months[2].closing_balance, usd(725));
+    assert_eq!(months[3].closing_balance, bank.get_account(checking).unwrap().balance);
+}
+
+#[test]
+fn test_past_periods_do_not_change() {
+    let mut bank = Bank::new();
+    let (checking, _) = active_customer(&mut bank);
+    let january = bank.monthly_statement(checking, 2024, 1).unwrap();
+    let text = january.to_text();
+
+    bank.set_clock(at(2024, 6, 1));
+    bank.withdraw(checking, usd(500)).unwrap();
+    bank.deposit(checking, usd(42)).unwrap();
+    assert_eq!(bank.monthly_statement(checking, 2024, 1).unwrap(), january);
+    assert_eq!(bank.monthly_statement(checking, 2024, 1).unwrap().to_text(), text);
+    assert_eq!(bank.monthly_statement(checking, 2024, 6).unwrap().opening_balance, usd(725));
+}
+
+#[test]
+fn test_late_postings_leave_issued_months_alone() {
+    let mut bank = Bank::new();
+    bank.set_interest_policy(AccountProduct::Savings, InterestPolicy::new(Rate::from_bps(100))).unwrap();
+    let (_, savings) = active_customer(&mut bank);
+    let january = bank.monthly_statement(savings, 2024, 1).unwrap();
+
+    // January's interest, posted in February and valued on 31 January.
+    bank.set_clock(at(2024, 2, 5));
+    bank.accrue_interest(date(2024, 1, 31)).unwrap();
+    let posted = bank.get_account(savings).unwrap().get_history().last().unwrap().clone();
+    assert_eq!(posted.timestamp.date_naive(), date(2024, 1, 31));
+    assert_eq!(posted.booked_on(), date(2024, 2, 5));
+
+    assert_eq!(bank.monthly_statement(savings, 2024, 1).unwrap(), january);
+    let february = bank.monthly_statement(savings, 2024, 2).unwrap();
+    assert_eq!(february.opening_balance, january.closing_balance);
+    assert_eq!(february.lines.len(), 1);
+    assert_eq!(february.interest_paid, posted.amount);
+    assert_eq!(february.closing_balance, bank.get_account(savings).unwrap().balance);
+    // Balances still count the interest from the day it is valued.
+    assert_eq!(bank.get_account(savings).unwrap().balance_at_end_of(date(2024, 1, 31)).unwrap(), february.closing_balance);
+}
+
+#[test]
+fn test_fees_and_interest_totals() {
+    let mut bank = Bank::new();
+    bank.set_interest_policy(AccountProduct::Savings, InterestPolicy::new(Rate::from_bps(100))).unwrap();
+    bank.set_overdraft_fee(AccountProduct::Checking, usd(25)).unwrap();
+    let (checking, savings) = active_customer(&mut bank);
+    bank.set_clock(at(2024, 3, 10));
+    bank.set_overdraft_limit(checking, usd(1_000)).unwrap();
+    bank.withdraw(checking, usd(800)).unwrap();
+    bank.withdraw(checking, usd(10)).unwrap();
+    let first_fee = bank.get_account(checking).unwrap().get_history().iter().find(|tx| tx.tx_type.is_fee()).unwrap().id;
+    bank.reverse_fee(checking, first_fee).unwrap();
+
+    let statement = bank.monthly_statement(checking, 2024, 3).unwrap();
+    assert_eq!(statement.lines.iter().filter(|line| line.description == "Overdraft fee").count(), 2);
+    assert_eq!(statement.lines.last().unwrap().description, format!("Refund of fee {}", first_fee));
+    assert_eq!(statement.fees, usd(25));
+    assert_eq!(statement.interest_paid, usd(0));
+    assert_eq!(statement.closing_balance, usd(-110));
+    assert_eq!(statement.closing_balance, bank.get_account(checking).unwrap().balance);
+
+    // 200 from 15 January to 31 March at 1% a year over 365 days, posted
+    // on 2 April for each month end. The postings show on April's
+    // statement, dated the month ends they count from.
+    bank.set_clock(at(2024, 4, 2));
+    bank.accrue_interest(date(2024, 3, 31)).unwrap();
+    let months: Vec<AccountStatement> = (1..=4).map(|month| bank.monthly_statement(savings, 2024, month).unwrap()).collect();
+    assert!(months[..3].iter().all(|statement| statement.interest_paid == usd(0)));
+    assert!(months.iter().all(|statement| statement.fees == usd(0)));
+    let april = &months[3];
+    assert_eq!(april.opening_balance, usd(200));
+    let dates: Vec<_> = april.lines.iter().map(|line| line.timestamp.date_naive()).collect();
+    assert_eq!(dates, vec![date(2024, 1, 31), date(2024, 2, 29), date(2024, 3, 31)]);
+    assert!(april.interest_paid.is_positive());
+    assert_eq!(april.closing_balance, usd(200).checked_add(april.interest_paid).unwrap());
+    assert_eq!(april.closing_balance, bank.get_account(savings).unwrap().balance);
+}
+
+#[test]
+fn test_text_and_json_renderers() {
+    let mut bank = Bank::new();
+    let (checking, _) = active_customer(&mut bank);
+    let statement = bank.monthly_statement(checking, 2024, 1).unwrap();
+
+    let text = statement.to_text();
+    assert!(text.contains("Period: 2024-01-01 to 2024-01-31"));
+    assert!(text.contains("Opening balance"));
+    assert!(text.contains("Transfer to account"));
+    assert!(text.contains("Rainy day"));
+    assert!(text.contains("Total debits:     350.00 USD"));
+    assert!(text.lines().any(|line| line.starts_with("2024-01-31") && line.contains("Closing balance") && line.ends_with("650.00 USD")));
+
+    let json = statement.to_json().unwrap();
+    let parsed: AccountStatement = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, statement);
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["lines"].as_array().unwrap().len(), 3);
+    assert_eq!(BankError::Render("bad".to_string()).to_string(), "could not render document: bad");
+}
+
+#[test]
+fn test_statement_errors() {
+    let mut bank = Bank::new();
+    let (checking, _) = active_customer(&mut bank);
+    assert!(matches!(bank.monthly_statement(checking, 2024, 13), Err(BankError::InvalidField { field: "statement month", .. })));
+    assert_eq!(bank.monthly_statement(AccountId(999), 2024, 1), Err(BankError::AccountNotFound(AccountId(999))));
+    let account = bank.get_account(checking).unwrap();
+    assert!(matches!(
+        AccountStatement::for_period(account, date(2024, 2, 1), date(2024, 1, 31)),
+        Err(BankError::InvalidField { field: "statement period", .. })
+    ));
+    let quarter = AccountStatement::for_period(account, date(2024, 1, 1), date(2024, 3, 31)).unwrap();
+    assert_eq!(quarter.lines.len(), 4);
+    assert_eq!(quarter.closing_balance, usd(725));
+}
@@ -0,0 +1,282 @@
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use banksystem_rs::bank::{Account, AccountId, AccountProduct, Bank, BankError, Currency, Customer, CustomerId, JsonFileStorage, MemoryStorage, Money, Storage, Transaction, TransactionId, TransactionType};
+use banksystem_rs::bank::journal::EventJournal;
+use banksystem_rs::bank::ledger::LedgerAccount;
+use common::usd;
+
+fn temp_path(name: &str) -> PathBuf {
+    common::temp_dir(name).join("bank.json")
+}
+
+#[test]
+fn test_save_and_load_round_trip() {
+    let path = temp_path("round-trip");
+    let mut bank = Bank::new();
+    let customer_id = bank.create_customer(
+        "Saved Customer".to_string(),
+        "1 Disk Rd".to_string(),
+        "555-2222".to_string(),
+        "saved@example.com".to_string(),
+    ).unwrap();
+    let checking = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    let savings = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.deposit(checking, usd(100)).unwrap();
+    bank.transfer(checking, savings, usd(25), Some("saved".to_string())).unwrap();
+
+    bank.save(&path).unwrap();
+    assert!(!path.with_file_name("bank.json.tmp").exists());
+
+    let mut loaded = Bank::load(&path).unwrap();
+    assert_eq!(loaded.get_customer(customer_id).unwrap().name, "Saved Customer");
+    assert_eq!(loaded.get_account(checking).unwrap().balance, usd(75));
+    assert_eq!(loaded.get_account(savings).unwrap().get_history().len(), 1);
+    assert_eq!(loaded.ledger().entries(), bank.ledger().entries());
+
+    // Each ID sequence continues where it left off.
+    let next = loaded.create_account(customer_id, AccountProduct::Savings).unwrap();
+    assert_eq!(next, AccountId(savings.0 + 1));
+    loaded.deposit(next, usd(1)).unwrap();
+    assert_eq!(loaded.get_account(next).unwrap().get_history()[0].id, TransactionId(4));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_deserializing_a_bank_rebuilds_its_indexes() {
+    let mut bank = Bank::new();
+    let customer_id = common::customer(&mut bank, "Serde Customer");
+    let checking = common::account(&mut bank, customer_id, AccountProduct::Checking, 40);
+    let number = bank.get_account(checking).unwrap().number.to_string();
+
+    let mut loaded: Bank = serde_json::from_str(&serde_json::to_string(&bank).unwrap()).unwrap();
+    assert_eq!(loaded.list_customer_accounts(customer_id).len(), 1);
+    assert_eq!(loaded.find_account_by_number(&number).unwrap().id, checking);
+    let next = loaded.create_account(customer_id, AccountProduct::Savings).unwrap();
+    assert_eq!(next, AccountId(checking.0 + 1));
+    assert_eq!(loaded.list_customer_accounts(customer_id).len(), 2);
+}
+
+#[test]
+fn test_load_legacy_file_posts_opening_balances() {
+    let path = temp_path("legacy");
+    // Written before amounts were fixed-point and before the ledger existed.
+    let legacy = r#"{
+        "accounts": [{
+            "id": 2,
+            "customer_id": 1,
+            "name": "Checking Account",
+            "account_type": "Checking",
+            "balance": 150.25,
+            "history": [{"tx_type": "Deposit", "amount": 150.25, "timestamp": "2024-01-01T00:00:00Z"}]
+        }],
+        "customers": [{"id": 1, "name": "Old", "address": "Old St", "phone": "555", "email": "old@example.com"}],
+        "next_id": 3
+    }"#;
+    fs::write(&path, legacy).unwrap();
+
+    let mut bank = Bank::load(&path).unwrap();
+    let account_id = AccountId(2);
+    assert_eq!(bank.get_account(account_id).unwrap().balance, Money::from_minor(15025, Currency::USD));
+    assert_eq!(bank.ledger().balance(LedgerAccount::Suspense, Currency::USD), Money::from_minor(15025, Currency::USD));
+    assert!(bank.trial_balance().is_balanced());
+
+    bank.withdraw(account_id, usd(50)).unwrap();
+    assert_eq!(bank.get_account(account_id).unwrap().balance, Money::from_minor(10025, Currency::USD));
+
+    // Legacy records are numbered and the sequences start past them.
+    let number = bank.get_account(account_id).unwrap().number.clone();
+    assert!(bank.account_number_format().parse(number.as_str()).is_ok());
+    assert_eq!(bank.find_account_by_number(number.as_str()).unwrap().id, account_id);
+    let history = bank.get_account(account_id).unwrap().get_history();
+    assert_eq!(history[0].id, TransactionId(1));
+    assert_eq!(history[1].id, TransactionId(2));
+    let customer_id = bank.create_customer(
+        "New".to_string(),
+        "New St".to_string(),
+        "555-1111".to_string(),
+        "new@example.com".to_string(),
+    ).unwrap();
+    assert_eq!(customer_id, CustomerId(2));
+    assert_eq!(bank.create_account(customer_id, AccountProduct::Savings).unwrap(), AccountId(3));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_load_legacy_file_with_unknown_account_type() {
+    let path = temp_path("legacy-types");
+    let legacy = r#"{
+        "accounts": [
+            {"id": 1, "customer_id": 1, "name": "Business", "account_type": "Business", "balance": 10.0, "history": []},
+            {"id": 2, "customer_id": 1, "name": "Rainy Day", "account_type": "saving", "balance": 5.0, "history": []}
+        ],
+        "customers": [{"id": 1, "name": "Old", "address": "Old St", "phone": "555", "email": "old@example.com"}]
+    }"#;
+    fs::write(&path, legacy).unwrap();
+
+    // One unrecognised type doesn't stop the rest of the file loading.
+    let bank = Bank::load(&path).unwrap();
+    assert_eq!(bank.get_account(AccountId(1)).unwrap().product, AccountProduct::Checking);
+    assert_eq!(bank.get_account(AccountId(1)).unwrap().name, "Business");
+    assert_eq!(bank.get_account(AccountId(2)).unwrap().product, AccountProduct::Savings);
+    assert_eq!(AccountProduct::from_legacy("Money-Market"), AccountProduct::MoneyMarket);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_load_errors() {
+    let path = temp_path("errors");
+    assert!(matches!(Bank::load(&path), Err(BankError::Storage(_))));
+
+    fs::write(&path, "not json").unwrap();
+    assert!(matches!(Bank::load(&path), Err(BankError::Storage(_))));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_json_file_storage_persists_every_change() {
+    let path = temp_path("json-file-storage");
+    {
+        let mut bank = Bank::with_storage(JsonFileStorage::open(&path).unwrap()).unwrap();
+        let customer_id = bank.create_customer(
+            "File Backed".to_string(),
+            "2 Disk Rd".to_string(),
+            "555-3333".to_string(),
+            "file@example.com".to_string(),
+        ).unwrap();
+        let account_id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+        bank.deposit(account_id, usd(40)).unwrap();
+        assert_eq!(bank.storage().path(), path.as_path());
+        // Written without an explicit save, before the bank is dropped.
+        assert_eq!(Bank::load(&path).unwrap().list_accounts()[0].balance, usd(40));
+    }
+
+    let bank = Bank::with_storage(JsonFileStorage::open(&path).unwrap()).unwrap();
+    assert_eq!(bank.list_customers().len(), 1);
+    assert_eq!(bank.list_accounts()[0].balance, usd(40));
+    assert_eq!(bank.ledger().entries().len(), 1);
+
+    // The file is the same layout Bank::save writes.
+    let loaded = Bank::load(&path).unwrap();
+    assert_eq!(loaded.list_accounts()[0].balance, usd(40));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_json_file_storage_snapshots_in_batches_with_a_journal() {
+    let path = temp_path("json-file-batches");
+    let journal = path.with_file_name("events.jsonl");
+    let open = || Bank::with_journal(JsonFileStorage::open(&path).unwrap().with_snapshot_every(3), &journal).unwrap();
+    let mut bank = open();
+    let customer_id = bank.create_customer(
+        "Batch Writer".to_string(),
+        "3 Disk Rd".to_string(),
+        "555-3334".to_string(),
+        "batch@example.com".to_string(),
+    ).unwrap();
+    let account_id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    assert!(!path.exists());
+
+    // The third event writes the file; the next waits for another batch.
+    bank.deposit(account_id, usd(40)).unwrap();
+    assert_eq!(Bank::load(&path).unwrap().list_accounts()[0].balance, usd(40));
+    bank.deposit(account_id, usd(10)).unwrap();
+    assert_eq!(Bank::load(&path).unwrap().list_accounts()[0].balance, usd(40));
+
+    // Opening the file with its journal replays only the event it lacks.
+    let reopened = open();
+    assert_eq!(reopened.get_account(account_id).unwrap().balance, usd(50));
+    assert_eq!(reopened.last_sequence(), 4);
+    drop(reopened);
+
+    bank.sync().unwrap();
+    assert_eq!(Bank::load(&path).unwrap().list_accounts()[0].balance, usd(50));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_json_file_storage_reopens_with_its_journal() {
+    let path = temp_path("json-file-journal");
+    let journal = path.with_file_name("events.jsonl");
+    let open = || Bank::with_journal(JsonFileStorage::open(&path).unwrap(), &journal).unwrap();
+    let (customer_id, account_id) = {
+        let mut bank = open();
+        let customer_id = bank.create_customer(
+            "Journaled File".to_string(),
+            "4 Disk Rd".to_string(),
+            "555-3335".to_string(),
+            "journaled@example.com".to_string(),
+        ).unwrap();
+        let account_id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+        bank.deposit(account_id, usd(40)).unwrap();
+        (customer_id, account_id)
+    };
+
+    // The snapshot written on drop already holds every journaled event.
+    let mut bank = open();
+    assert_eq!(bank.list_customers().len(), 1);
+    assert_eq!(bank.get_account(account_id).unwrap().balance, usd(40));
+    let savings = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.transfer(account_id, savings, usd(15), None).unwrap();
+    drop(bank);
+
+    let bank = open();
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(15));
+    assert_eq!(bank.last_sequence(), EventJournal::read(&journal).unwrap().len() as u64);
+    assert_eq!(Bank::replay(&journal).unwrap().ledger().entries(), bank.ledger().entries());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_memory_storage_contract() {
+    let mut storage = MemoryStorage::new();
+    assert_eq!(storage.sequences_mut().allocate_customer_id(), CustomerId(1));
+    assert_eq!(storage.sequences_mut().allocate_account_id(), AccountId(1));
+    assert_eq!(storage.sequences().next_transaction_id, 1);
+
+    let customer_id = CustomerId(1);
+    let account_id = AccountId(1);
+    storage.insert_customer(Customer::new(customer_id, "A".to_string(), "B".to_string(), "1".to_string(), "a@b".to_string())).unwrap();
+    assert!(storage.insert_customer(Customer::new(customer_id, "Dup".to_string(), "B".to_string(), "1".to_string(), "d@b".to_string())).is_err());
+    storage.insert_account(Account::new(account_id, customer_id, "Checking Account".to_string(), AccountProduct::Checking)).unwrap();
+
+    let tx = Transaction::new(TransactionType::Deposit, usd(5));
+    storage.record_transaction(account_id, tx.clone(), usd(5)).unwrap();
+    assert_eq!(storage.account(account_id).unwrap().balance, usd(5));
+    assert_eq!(storage.account(account_id).unwrap().history.len(), 1);
+    assert_eq!(storage.record_transaction(AccountId(9), tx, usd(5)), Err(BankError::AccountNotFound(AccountId(9))));
+
+    let bank = Bank::with_storage(storage).unwrap();
+    assert_eq!(bank.get_customer(customer_id).unwrap().name, "A");
+    // The unexplained balance is booked against suspense.
+    assert!(bank.trial_balance().is_balanced());
+}
+
+#[test]
+fn test_ids_serialize_as_plain_numbers() {
+    let mut bank = Bank::new();
+    let customer_id = bank.create_customer(
+        "Plain".to_string(),
+        "3 Number Rd".to_string(),
+        "555-4444".to_string(),
+        "plain@example.com".to_string(),
+    ).unwrap();
+    let account_id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    bank.deposit(account_id, usd(10)).unwrap();
+
+    let json = serde_json::to_value(&bank).unwrap();
+    assert_eq!(json["customers"][0]["id"], 1);
+    assert_eq!(json["accounts"][0]["id"], 1);
+    assert_eq!(json["accounts"][0]["customer_id"], 1);
+    assert_eq!(json["accounts"][0]["history"][0]["id"], 1);
+    assert_eq!(json["sequences"]["next_account_id"], 2);
+    assert_eq!(serde_json::from_value::<AccountId>(json["accounts"][0]["id"].clone()).unwrap(), account_id);
+}
@@ -1,4 +1,8 @@
-use banksystem_rs::bank::{Bank, TransactionType};
+use banksystem_rs::bank::{AccountId, AccountProduct, Bank, BankError, Currency, Money, TransactionType};
+
+fn usd(major: i64) -> Money {
+    Money::from_major(major, Currency::USD).unwrap()
+}
 
 #[test]
 fn test_transaction_history() {
@@ -8,19 +12,18 @@ fn test_transaction_history() {
         "Test Address".to_string(),
         "555-0000".to_string(),
         "test@example.com".to_string(),
-    );
-    let account_id = bank.create_account(customer_id, "Checking".to_string());
+    ).unwrap();
+    let account_id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
     
-    let account = bank.get_account_mut(account_id).unwrap();
-    account.deposit(100.0);
-    account.withdraw(30.0);
+    bank.deposit(account_id, usd(100)).unwrap();
+    bank.withdraw(account_id, usd(30)).unwrap();
     
-    let history = account.get_history();
+    let history = bank.get_account(account_id).unwrap().get_history();
     assert_eq!(history.len(), 2);
-    assert_eq!(history[0].transaction_type, TransactionType::Deposit);
-    assert_eq!(history[0].amount, 100.0);
-    assert_eq!(history[1].transaction_type, TransactionType::Withdrawal);
-    assert_eq!(history[1].amount, 30.0);
+    assert_eq!(history[0].tx_type, TransactionType::Deposit);
+    assert_eq!(history[0].amount, usd(100));
+    assert_eq!(history[1].tx_type, TransactionType::Withdrawal);
+    assert_eq!(history[1].amount, usd(30));
 }
 
 #[test]
@@ -31,27 +34,28 @@ fn test_invalid_operations() {
         "Edge Address".to_string(),
         "555-9999".to_string(),
         "edge@example.com".to_string(),
-    );
-    let account_id = bank.create_account(customer_id, "Checking".to_string());
+    ).unwrap();
+    let account_id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
     
-    let account = bank.get_account_mut(account_id).unwrap();
+    let balance = |bank: &Bank| bank.get_account(account_id).unwrap().balance;
     
     // Test negative deposit
-    assert_eq!(account.balance, 0.0);
-    account.deposit(-50.0);  // Should be ignored or handled
-    assert_eq!(account.balance, 0.0);
+    assert_eq!(balance(&bank), usd(0));
+    let deposit_result = bank.deposit(account_id, usd(-50));
+    assert_eq!(deposit_result, Err(BankError::InvalidAmount(usd(-50))));
+    assert_eq!(balance(&bank), usd(0));
     
     // Test overdraft
-    let withdraw_result = account.withdraw(100.0);
-    assert!(!withdraw_result);
-    assert_eq!(account.balance, 0.0);
+    let withdraw_result = bank.withdraw(account_id, usd(100));
+    assert!(matches!(withdraw_result, Err(BankError::InsufficientFunds { .. })));
+    assert_eq!(balance(&bank), usd(0));
     
     // Test valid operations after invalid ones
-    account.deposit(100.0);
-    assert_eq!(account.balance, 100.0);
-    let withdraw_result = account.withdraw(50.0);
-    assert!(withdraw_result);
-    assert_eq!(account.balance, 50.0);
+    bank.deposit(account_id, usd(100)).unwrap();
+    assert_eq!(balance(&bank), usd(100));
+    let withdraw_result = bank.withdraw(account_id, usd(50));
+    assert!(withdraw_result.is_ok());
+    assert_eq!(balance(&bank), usd(50));
 }
 
 #[test]
@@ -62,16 +66,92 @@ fn test_account_types() {
         "Type Address".to_string(),
         "555-8888".to_string(),
         "type@example.com".to_string(),
-    );
+    ).unwrap();
     
     // Test different account types
-    let checking_id = bank.create_account(customer_id, "Checking".to_string());
-    let savings_id = bank.create_account(customer_id, "Savings".to_string());
-    let credit_id = bank.create_account(customer_id, "Credit".to_string());
+    let checking_id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    let savings_id = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    let market_id = bank.create_account(customer_id, "Money Market".parse().unwrap()).unwrap();
     
-    assert_eq!(bank.get_account(checking_id).unwrap().account_type, "Checking");
-    assert_eq!(bank.get_account(savings_id).unwrap().account_type, "Savings");
-    assert_eq!(bank.get_account(credit_id).unwrap().account_type, "Credit");
+    assert_eq!(bank.get_account(checking_id).unwrap().product, AccountProduct::Checking);
+    assert_eq!(bank.get_account(savings_id).unwrap().product, AccountProduct::Savings);
+    assert_eq!(bank.get_account(market_id).unwrap().product, AccountProduct::MoneyMarket);
+    assert_eq!(bank.get_account(market_id).unwrap().name, "Money Market Account");
+
+    // Products with terms have their own ways in.
+    for (product, opener) in [
+        (AccountProduct::Loan, "open_loan"),
+        (AccountProduct::CertificateOfDeposit, "open_certificate"),
+        (AccountProduct::CreditCard, "open_credit_card"),
+    ] {
+        match bank.create_account(customer_id, product) {
+            Err(BankError::InvalidField { field: "account type", reason }) => assert!(reason.contains(opener)),
+            other => panic!("expected {} to be refused, got {:?}", product, other),
+        }
+    }
+    assert_eq!(bank.list_customer_accounts(customer_id).len(), 3);
+
+    // Spelling and case no longer make a different product.
+    for name in ["checking", "CHECKING", "Chequing", " checking "] {
+        assert_eq!(name.parse::<AccountProduct>().unwrap(), AccountProduct::Checking);
+    }
+    assert_eq!("money-market".parse::<AccountProduct>().unwrap(), AccountProduct::MoneyMarket);
+    assert_eq!("CD".parse::<AccountProduct>().unwrap(), AccountProduct::CertificateOfDeposit);
+    assert!(matches!(
+        "Business".parse::<AccountProduct>(),
+        Err(BankError::InvalidField { field: "account type", .. })
+    ));
+    for product in AccountProduct::ALL {
+        assert_eq!(product.name().parse::<AccountProduct>().unwrap(), product);
+    }
+}
+
+#[test]
+fn test_product_rules() {
+    assert!(AccountProduct::Checking.rules().overdraft_allowed);
+    assert!(!AccountProduct::Savings.rules().overdraft_allowed);
+    assert!(AccountProduct::Savings.rules().earns_interest);
+    assert!(!AccountProduct::Checking.rules().earns_interest);
+    assert_eq!(AccountProduct::Checking.rules().monthly_withdrawal_limit, None);
+    assert_eq!(AccountProduct::MoneyMarket.rules().monthly_withdrawal_limit, Some(6));
+    assert_eq!(AccountProduct::CertificateOfDeposit.rules().monthly_withdrawal_limit, Some(0));
+}
+
+#[test]
+fn test_withdrawal_limits_are_enforced() {
+    let mut bank = Bank::new();
+    let customer_id = bank.create_customer(
+        "Limit Test".to_string(),
+        "Limit Lane".to_string(),
+        "555-4321".to_string(),
+        "limit@example.com".to_string(),
+    ).unwrap();
+    let savings = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    let checking = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    bank.deposit(savings, usd(100)).unwrap();
+
+    // Withdrawals and outgoing transfers share the monthly allowance.
+    for _ in 0..3 {
+        bank.withdraw(savings, usd(1)).unwrap();
+        bank.transfer(savings, checking, usd(1), None).unwrap();
+    }
+    assert_eq!(
+        bank.withdraw(savings, usd(1)),
+        Err(BankError::WithdrawalLimitExceeded { account_id: savings, limit: 6 })
+    );
+    assert!(matches!(
+        bank.transfer(savings, checking, usd(1), None),
+        Err(BankError::WithdrawalLimitExceeded { .. })
+    ));
+    // Deposits and incoming transfers don't count.
+    bank.deposit(savings, usd(1)).unwrap();
+    bank.transfer(checking, savings, usd(1), None).unwrap();
+    assert_eq!(bank.get_account(savings).unwrap().balance, usd(96));
+
+    // Checking has no limit.
+    for _ in 0..10 {
+        bank.withdraw(checking, Money::from_minor(10, Currency::USD)).unwrap();
+    }
 }
 
 #[test]
@@ -82,26 +162,91 @@ fn test_transfer_validation() {
         "Transfer Address".to_string(),
         "555-7777".to_string(),
         "transfer@example.com".to_string(),
-    );
-    
-    let account1_id = bank.create_account(customer_id, "Checking".to_string());
-    let account2_id = bank.create_account(customer_id, "Savings".to_string());
+    ).unwrap();
     
-    // Get mutable references to both accounts
-    let account1 = bank.get_account_mut(account1_id).unwrap();
-    account1.deposit(100.0);
+    let account1_id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    let account2_id = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
     
-    let account2 = bank.get_account_mut(account2_id).unwrap();
+    bank.deposit(account1_id, usd(100)).unwrap();
+    let balance = |bank: &Bank, id: AccountId| bank.get_account(id).unwrap().balance;
     
     // Test transfer with insufficient funds
-    let result = account1.transfer(150.0, account2);
-    assert!(!result);
-    assert_eq!(account1.balance, 100.0);
-    assert_eq!(account2.balance, 0.0);
+    let result = bank.transfer(account1_id, account2_id, usd(150), None);
+    assert!(matches!(result, Err(BankError::InsufficientFunds { .. })));
+    assert_eq!(balance(&bank, account1_id), usd(100));
+    assert_eq!(balance(&bank, account2_id), usd(0));
     
     // Test valid transfer
-    let result = account1.transfer(50.0, account2);
-    assert!(result);
-    assert_eq!(account1.balance, 50.0);
-    assert_eq!(account2.balance, 50.0);
+    let result = bank.transfer(account1_id, account2_id, usd(50), None);
+    assert!(result.is_ok());
+    assert_eq!(balance(&bank, account1_id), usd(50));
+    assert_eq!(balance(&bank, account2_id), usd(50));
+}
+
+#[test]
+fn test_bank_transfer_records_both_legs() {
+    let mut bank = Bank::new();
+    let customer_id = bank.create_customer(
+        "Bank Transfer".to_string(),
+        "Transfer Lane".to_string(),
+        "555-6666".to_string(),
+        "bank.transfer@example.com".to_string(),
+    ).unwrap();
+    let from_id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    let to_id = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.deposit(from_id, usd(100)).unwrap();
+
+    bank.transfer(from_id, to_id, usd(40), Some("rent".to_string())).unwrap();
+
+    let from = bank.get_account(from_id).unwrap();
+    let to = bank.get_account(to_id).unwrap();
+    assert_eq!(from.balance, usd(60));
+    assert_eq!(to.balance, usd(40));
+
+    let debit = from.get_history().last().unwrap();
+    let credit = to.get_history().last().unwrap();
+    assert_eq!(debit.tx_type, TransactionType::Transfer { to_account: to_id });
+    assert_eq!(credit.tx_type, TransactionType::TransferIn { from_account: from_id });
+    assert_eq!(debit.amount, credit.amount);
+    assert_eq!(debit.timestamp, credit.timestamp);
+    assert_eq!(credit.memo.as_deref(), Some("rent"));
+    assert_eq!(to.get_history().len(), 1);
+}
+
+#[test]
+fn test_bank_transfer_is_all_or_nothing() {
+    let mut bank = Bank::new();
+    let customer_id = bank.create_customer(
+        "Atomic".to_string(),
+        "Atomic Ave".to_string(),
+        "555-5555".to_string(),
+        "atomic@example.com".to_string(),
+    ).unwrap();
+    let from_id = bank.create_account(customer_id, AccountProduct::Checking).unwrap();
+    let to_id = bank.create_account(customer_id, AccountProduct::Savings).unwrap();
+    bank.deposit(from_id, usd(50)).unwrap();
+
+    assert!(matches!(
+        bank.transfer(from_id, to_id, usd(80), None),
+        Err(BankError::InsufficientFunds { .. })
+    ));
+    assert_eq!(bank.transfer(from_id, AccountId(999), usd(10), None), Err(BankError::AccountNotFound(AccountId(999))));
+    assert_eq!(bank.transfer(from_id, from_id, usd(10), None), Err(BankError::SameAccount(from_id)));
+    assert!(matches!(
+        bank.transfer(from_id, to_id, Money::from_major(10, Currency::EUR).unwrap(), None),
+        Err(BankError::Money(_))
+    ));
+
+    let from = bank.get_account(from_id).unwrap();
+    let to = bank.get_account(to_id).unwrap();
+    assert_eq!(from.balance, usd(50));
+    assert_eq!(from.get_history().len(), 1);
+    assert_eq!(to.balance, usd(0));
+    assert!(to.get_history().is_empty());
+
+    // Transfers work in either direction regardless of storage order.
+    bank.transfer(from_id, to_id, usd(50), None).unwrap();
+    bank.transfer(to_id, from_id, usd(20), None).unwrap();
+    assert_eq!(bank.get_account(from_id).unwrap().balance, usd(20));
+    assert_eq!(bank.get_account(to_id).unwrap().balance, usd(30));
 }